@@ -0,0 +1,809 @@
+//! Helpers for writing command lines and reading responses
+
+use crate::{
+    AtContext, AtReadable, AtReadableWith, AtResponse, AtWritable, AtWritableWith, CmeError,
+    CmsError, HayesError, ParseOptions, error::Expected,
+};
+
+/// Report a failed write at `offset` relative to the whole output
+fn offset_error(error: HayesError, offset: usize, available: usize) -> HayesError {
+    match error {
+        HayesError::InsufficientBuffer { required, .. } => HayesError::InsufficientBuffer {
+            required: offset + required,
+            available,
+        },
+        error => error,
+    }
+}
+
+/// Writes a command line such as `AT+CPMS="SM","SM","SM"\r\n`, one parameter
+/// at a time
+///
+/// Trailing empty parameters are dropped, so that omitted optional parameters
+/// don't leave dangling commas.
+pub(crate) struct CommandWriter<'o> {
+    output: &'o mut [u8],
+    pos: usize,
+    params: usize,
+    /// End of the command name, or of the last non-empty parameter
+    end: usize,
+}
+
+impl<'o> CommandWriter<'o> {
+    /// Start a command line with `AT` followed by `command`, such as `+CMGF`
+    pub(crate) fn new(output: &'o mut [u8], command: &str) -> Result<Self, HayesError> {
+        let mut writer = CommandWriter {
+            output,
+            pos: 0,
+            params: 0,
+            end: 0,
+        };
+        writer.raw(b"AT")?;
+        writer.raw(command.as_bytes())?;
+        writer.end = writer.pos;
+
+        Ok(writer)
+    }
+
+    /// Write bytes as they are
+    pub(crate) fn raw(&mut self, bytes: &[u8]) -> Result<(), HayesError> {
+        let required = self.pos + bytes.len();
+        if self.output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: self.output.len(),
+            });
+        }
+
+        self.output[self.pos..required].copy_from_slice(bytes);
+        self.pos = required;
+        Ok(())
+    }
+
+    fn separator(&mut self) -> Result<(), HayesError> {
+        self.raw(if self.params == 0 { b"=" } else { b"," })?;
+        self.params += 1;
+        Ok(())
+    }
+
+    fn written(&mut self, result: Result<usize, HayesError>) -> Result<(), HayesError> {
+        let available = self.output.len();
+        let len = result.map_err(|error| offset_error(error, self.pos, available))?;
+        self.pos += len;
+        if len > 0 {
+            self.end = self.pos;
+        }
+        Ok(())
+    }
+
+    /// Write the next parameter
+    pub(crate) fn param<T: AtWritable + ?Sized>(&mut self, value: &T) -> Result<(), HayesError> {
+        self.separator()?;
+        let result = value.write(&mut self.output[self.pos..]);
+        self.written(result)
+    }
+
+    /// Write the next parameter using the given context
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(crate) fn param_with<T: AtWritableWith + ?Sized>(
+        &mut self,
+        value: &T,
+        context: &AtContext,
+    ) -> Result<(), HayesError> {
+        self.separator()?;
+        let result = value.write_with(&mut self.output[self.pos..], context);
+        self.written(result)
+    }
+
+    /// Finish the command line with `\r\n`
+    pub(crate) fn finish(self) -> Result<usize, HayesError> {
+        self.finish_with(b"\r\n")
+    }
+
+    /// Finish the command line with `terminator`
+    pub(crate) fn finish_with(mut self, terminator: &[u8]) -> Result<usize, HayesError> {
+        self.pos = self.end;
+        self.raw(terminator)?;
+        Ok(self.pos)
+    }
+}
+
+/// The error of a `+CME ERROR` or `+CMS ERROR` line, in numeric or verbose form
+fn error_code<E>(
+    rest: &[u8],
+    from_code: fn(u16) -> E,
+    from_text: fn(&[u8]) -> Option<E>,
+) -> Option<E> {
+    match u16::read(rest.trim_ascii()) {
+        Ok((code, _)) => Some(from_code(code)),
+        Err(_) => from_text(rest),
+    }
+}
+
+/// The outcome of a final result code line, or `None` for any other line
+///
+/// The line may deviate from V.250 as far as `options` allow.
+pub(crate) fn final_result(line: &[u8], options: ParseOptions) -> Option<Result<(), HayesError>> {
+    let line = if options.trailing_whitespace {
+        line.trim_ascii_end()
+    } else {
+        line
+    };
+
+    if let Some(rest) = options.strip_prefix(line, "+CME ERROR") {
+        let error = error_code(rest, CmeError::from_code, CmeError::from_text);
+        return Some(Err(
+            error.map_or(HayesError::ModemError, HayesError::CmeError)
+        ));
+    }
+    if let Some(rest) = options.strip_prefix(line, "+CMS ERROR") {
+        let error = error_code(rest, CmsError::from_code, CmsError::from_text);
+        return Some(Err(
+            error.map_or(HayesError::ModemError, HayesError::CmsError)
+        ));
+    }
+
+    let is = |code: &str| {
+        if options.case_insensitive_prefixes {
+            line.eq_ignore_ascii_case(code.as_bytes())
+        } else {
+            line == code.as_bytes()
+        }
+    };
+    if is("OK") {
+        Some(Ok(()))
+    } else if ["ERROR", "NO CARRIER", "BUSY", "NO ANSWER", "NO DIALTONE"]
+        .into_iter()
+        .any(is)
+    {
+        Some(Err(HayesError::ModemError))
+    } else {
+        None
+    }
+}
+
+/// Reads the lines of a response, up to and including the final result code
+#[derive(Debug, Clone)]
+pub(crate) struct ResponseReader<'at> {
+    input: &'at [u8],
+    pos: usize,
+    options: ParseOptions,
+    /// Whether errors are reported with their location
+    locate: bool,
+}
+
+/// Shows the lines that are still to be read
+#[cfg(feature = "defmt")]
+impl defmt::Format for ResponseReader<'_> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "ResponseReader {{ unread: {=[u8]:a} }}",
+            &self.input[self.pos..]
+        );
+    }
+}
+
+impl<'at> ResponseReader<'at> {
+    /// Start reading a response, skipping the echoed command line if present
+    ///
+    /// The response may deviate from V.250 as far as the context's parse
+    /// options allow.
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(crate) fn new(input: &'at [u8], context: &AtContext) -> Self {
+        let mut reader = ResponseReader {
+            input,
+            pos: 0,
+            options: context.parse,
+            locate: context.locate_errors,
+        };
+        if let Some((line, end)) = reader.peek()
+            && line.len() >= 2
+            && line[..2].eq_ignore_ascii_case(b"AT")
+        {
+            reader.pos = end;
+        }
+
+        reader
+    }
+
+    /// The next non-empty line and the position after it
+    fn peek(&self) -> Option<(&'at [u8], usize)> {
+        let trim = self.options.trailing_whitespace;
+        let is_blank = |b: u8| b == b'\r' || b == b'\n' || (trim && (b == b' ' || b == b'\t'));
+
+        let start = self.pos + self.input[self.pos..].iter().position(|&b| !is_blank(b))?;
+        let rest = &self.input[start..];
+        let mut line = &rest[..rest
+            .iter()
+            .position(|&b| b == b'\r' || b == b'\n')
+            .unwrap_or(rest.len())];
+        if trim {
+            line = line.trim_ascii_end();
+        }
+
+        Some((line, start + line.len()))
+    }
+
+    /// Read the next non-empty line
+    ///
+    /// An error result code is returned as the error it reports.
+    pub(crate) fn line(&mut self) -> Result<&'at [u8], HayesError> {
+        let (line, end) = self.peek().ok_or(self.end())?;
+        if let Some(Err(error)) = final_result(line, self.options) {
+            return Err(error);
+        }
+
+        self.pos = end;
+        Ok(line)
+    }
+
+    /// Read a line starting with `prefix`, such as `+CMGS`, and return the
+    /// fields following the colon
+    pub(crate) fn prefixed(&mut self, prefix: &str) -> Result<Fields<'at>, HayesError> {
+        match self.optional(prefix)? {
+            Some(fields) => Ok(fields),
+            None => Err(self.unexpected_line(Expected::Prefix)),
+        }
+    }
+
+    /// Read a line starting with `prefix` if it's the next one
+    ///
+    /// Returns `None` at the final result code or the end of the input.
+    pub(crate) fn optional(&mut self, prefix: &str) -> Result<Option<Fields<'at>>, HayesError> {
+        let Some((line, end)) = self.peek() else {
+            return Ok(None);
+        };
+        if let Some(result) = final_result(line, self.options) {
+            return result.map(|()| None);
+        }
+
+        let Some(mut fields) = Fields::after_prefix_with(line, prefix, self.options) else {
+            return Ok(None);
+        };
+        fields.locate = self.locate;
+        fields.base = end - fields.input.len();
+
+        self.line()?;
+        Ok(Some(fields))
+    }
+
+    /// Read the lines following an information line, such as a message body
+    ///
+    /// The body ends at the final result code, at a line starting with the
+    /// prefix `next` if given, such as `+CMGL`, or at the end of the input.
+    /// Line breaks within the body are kept and trailing ones are dropped.
+    pub(crate) fn body(&mut self, next: Option<&str>) -> &'at [u8] {
+        let line_break = |rest: &[u8]| match rest {
+            [b'\r', b'\n', ..] => 2,
+            [b'\r' | b'\n', ..] => 1,
+            _ => 0,
+        };
+
+        let start = self.pos + line_break(&self.input[self.pos..]);
+        let mut end = start;
+        let mut pos = start;
+        while pos < self.input.len() {
+            let rest = &self.input[pos..];
+            let len = rest
+                .iter()
+                .position(|&b| b == b'\r' || b == b'\n')
+                .unwrap_or(rest.len());
+            let line = &rest[..len];
+
+            if final_result(line, self.options).is_some()
+                || next.is_some_and(|prefix| self.options.strip_prefix(line, prefix).is_some())
+            {
+                break;
+            }
+            if !line.is_empty() {
+                end = pos + len;
+            }
+
+            pos += len + line_break(&rest[len..]);
+        }
+
+        self.pos = end;
+        &self.input[start..end]
+    }
+
+    /// Read the final result code, which must be `OK`
+    pub(crate) fn finish(self) -> Result<(), HayesError> {
+        let (line, _) = self.peek().ok_or(self.end())?;
+        if let Some(result) = final_result(line, self.options) {
+            return result;
+        }
+
+        Err(self.unexpected_line(Expected::ResultCode))
+    }
+
+    /// Error for running out of input
+    fn end(&self) -> HayesError {
+        self.report(
+            HayesError::UnexpectedEnd
+                .expected(Expected::ResultCode)
+                .at_offset(self.input.len()),
+        )
+    }
+
+    /// Error for the next line not being what was `expected`
+    fn unexpected_line(&self, expected: Expected) -> HayesError {
+        match self.peek() {
+            Some((line, end)) => self.report(
+                HayesError::InvalidFormat
+                    .expected(expected)
+                    .at_offset(end - line.len()),
+            ),
+            None => self.end(),
+        }
+    }
+
+    /// The error with its location, if it should be reported
+    fn report(&self, error: HayesError) -> HayesError {
+        if self.locate {
+            error
+        } else {
+            error.unlocated()
+        }
+    }
+}
+
+/// A command that only returns a result code
+impl AtResponse<'_> for () {
+    fn read(buffer: &[u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &[u8], context: &AtContext) -> Result<Self, HayesError> {
+        ResponseReader::new(buffer, context).finish()
+    }
+}
+
+/// Comma-separated fields of a response line
+pub(crate) struct Fields<'at> {
+    input: &'at [u8],
+    /// Offset of the input in the whole response, for locating errors
+    base: usize,
+    pos: usize,
+    count: usize,
+    options: ParseOptions,
+    /// Whether errors are reported with their location
+    locate: bool,
+    /// Name of the field being read, for locating errors
+    name: Option<&'static str>,
+}
+
+impl<'at> Fields<'at> {
+    /// Fields of an information line or URC, such as `+CMTI: "SM",3`
+    pub(crate) fn after_prefix(line: &'at [u8], prefix: &str) -> Option<Self> {
+        Fields::after_prefix_with(line, prefix, ParseOptions::STRICT)
+    }
+
+    /// Fields of an information line or URC that may deviate from V.250 as
+    /// allowed by `options`
+    pub(crate) fn after_prefix_with(
+        line: &'at [u8],
+        prefix: &str,
+        options: ParseOptions,
+    ) -> Option<Self> {
+        let rest = options.strip_prefix(line, prefix)?;
+        let rest = if options.field_spaces {
+            &rest[options.spaces(rest)..]
+        } else {
+            rest.strip_prefix(b" ").unwrap_or(rest)
+        };
+
+        let mut fields = Fields::new(rest);
+        fields.base = line.len() - rest.len();
+        fields.options = options;
+        Some(fields)
+    }
+
+    /// Fields of a line with no prefix
+    pub(crate) const fn new(input: &'at [u8]) -> Self {
+        Fields {
+            input,
+            base: 0,
+            pos: 0,
+            count: 0,
+            options: ParseOptions::STRICT,
+            locate: false,
+            name: None,
+        }
+    }
+
+    /// Whether every field has been read
+    pub(crate) fn is_empty(&self) -> bool {
+        self.pos >= self.input.len()
+    }
+
+    fn separator(&mut self) -> Result<(), HayesError> {
+        if self.count > 0 {
+            let error = match self.input.get(self.pos) {
+                Some(b',') => None,
+                Some(_) => Some(HayesError::InvalidFormat),
+                None => Some(HayesError::UnexpectedEnd),
+            };
+            if let Some(error) = error {
+                return Err(self.report(
+                    error
+                        .expected(Expected::Comma)
+                        .at_offset(self.base + self.pos),
+                ));
+            }
+            self.pos += 1;
+        }
+        self.count += 1;
+        self.skip_spaces();
+        Ok(())
+    }
+
+    /// Skip spaces around a field, if allowed
+    fn skip_spaces(&mut self) {
+        self.pos += self.options.spaces(&self.input[self.pos..]);
+    }
+
+    /// Read the next field
+    pub(crate) fn next<T: AtReadable<'at>>(&mut self) -> Result<T, HayesError> {
+        self.separator()?;
+        let (value, consumed) =
+            T::read_located(&self.input[self.pos..]).map_err(|e| self.locate(e))?;
+        self.pos += consumed;
+        self.skip_spaces();
+        Ok(value)
+    }
+
+    /// Read the next field, giving its `name` in a located error
+    pub(crate) fn next_named<T: AtReadable<'at>>(
+        &mut self,
+        name: &'static str,
+    ) -> Result<T, HayesError> {
+        self.name = Some(name);
+        let value = self.next();
+        self.name = None;
+        value
+    }
+
+    /// Read the next field using the given context
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    pub(crate) fn next_with<T: AtReadableWith<'at>>(
+        &mut self,
+        context: &AtContext,
+    ) -> Result<T, HayesError> {
+        self.separator()?;
+        let (value, consumed) =
+            T::read_with(&self.input[self.pos..], context).map_err(|e| self.locate(e))?;
+        self.pos += consumed;
+        self.skip_spaces();
+        Ok(value)
+    }
+
+    /// Read the raw bytes of the next field, which must be quoted
+    pub(crate) fn next_quoted(&mut self) -> Result<&'at [u8], HayesError> {
+        self.separator()?;
+        let (raw, consumed) = read_quoted(&self.input[self.pos..]).map_err(|e| self.locate(e))?;
+        self.pos += consumed;
+        self.skip_spaces();
+        Ok(raw)
+    }
+
+    /// Locate an error from reading the current field
+    fn locate(&self, error: HayesError) -> HayesError {
+        let field = u16::try_from(self.count - 1).unwrap_or(u16::MAX);
+        self.report(
+            error
+                .at_offset(self.base + self.pos)
+                .in_field(field, self.name),
+        )
+    }
+
+    /// The error with its location, if it should be reported
+    fn report(&self, error: HayesError) -> HayesError {
+        if self.locate {
+            error
+        } else {
+            error.unlocated()
+        }
+    }
+
+    /// Read the rest of the line as it is, for free-form fields
+    pub(crate) fn rest(&mut self) -> &'at [u8] {
+        let rest = &self.input[self.pos.min(self.input.len())..];
+        self.pos = self.input.len();
+        self.count += 1;
+        rest
+    }
+
+    /// Read the next field if present, which it isn't at the end of the line
+    pub(crate) fn optional<T: AtReadable<'at>>(&mut self) -> Result<Option<T>, HayesError> {
+        if self.is_empty() {
+            return Ok(None);
+        }
+
+        self.next()
+    }
+}
+
+/// Check that the contents of a quoted string parameter can't end it or the
+/// command line
+///
+/// Quotes, CR and LF are rejected, so that a `;` or another command can't
+/// follow. Other control characters are rejected too, unless the string is
+/// GSM 7-bit `septets`, where they stand for characters such as `@`.
+pub(crate) fn check_quoted(bytes: &[u8], septets: bool) -> Result<(), HayesError> {
+    let unsafe_byte = |b: u8| match b {
+        b'"' | b'\r' | b'\n' => true,
+        0x00..=0x1F | 0x7F => !septets,
+        _ => false,
+    };
+
+    match bytes.iter().position(|&b| unsafe_byte(b)) {
+        Some(offset) => Err(HayesError::UnsafeCharacter {
+            byte: bytes[offset],
+            offset,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// Read the raw bytes of a quoted string, returning them and the bytes consumed
+pub(crate) fn read_quoted(input: &[u8]) -> Result<(&[u8], usize), HayesError> {
+    if input.is_empty() {
+        return Err(HayesError::UnexpectedEnd.expected(Expected::Quote));
+    }
+
+    if input[0] != b'"' {
+        return Err(HayesError::InvalidFormat.expected(Expected::Quote));
+    }
+
+    let closing_quote_pos = input[1..].iter().position(|&b| b == b'"').ok_or(
+        HayesError::UnexpectedEnd
+            .expected(Expected::Quote)
+            .at_offset(input.len()),
+    )?;
+
+    Ok((&input[1..=closing_quote_pos], closing_quote_pos + 2))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_writer() {
+        let mut buf = [0u8; 32];
+
+        let mut writer = CommandWriter::new(&mut buf, "+CNMI").unwrap();
+        writer.param(&2u8).unwrap();
+        writer.param(&1u8).unwrap();
+        writer.param(&None::<u8>).unwrap();
+        writer.param(&Some(1u8)).unwrap();
+        writer.param(&None::<u8>).unwrap();
+        assert_eq!(writer.finish(), Ok(16));
+        assert_eq!(&buf[..16], b"AT+CNMI=2,1,,1\r\n");
+
+        let mut writer = CommandWriter::new(&mut buf, "+CMGL").unwrap();
+        writer.param(&None::<&str>).unwrap();
+        assert_eq!(writer.finish_with(b"\r"), Ok(8));
+        assert_eq!(&buf[..8], b"AT+CMGL\r");
+
+        let mut writer = CommandWriter::new(&mut buf[..12], "+CSCA").unwrap();
+        assert_eq!(
+            writer.param(&"+15551234"),
+            Err(HayesError::InsufficientBuffer {
+                required: 19,
+                available: 12,
+            })
+        );
+    }
+
+    #[test]
+    fn test_response_reader() {
+        let mut reader = ResponseReader::new(
+            b"AT+CMGS=23\r\r\n+CMGS: 42\r\n\r\nOK\r\n",
+            &AtContext::default(),
+        );
+        let mut fields = reader.prefixed("+CMGS").unwrap();
+        assert_eq!(fields.next::<u8>(), Ok(42));
+        assert_eq!(fields.optional::<u8>(), Ok(None));
+        assert_eq!(reader.optional("+CMGS").map(|f| f.is_some()), Ok(false));
+        assert_eq!(reader.finish(), Ok(()));
+
+        let mut reader = ResponseReader::new(b"\r\n+CMS ERROR: 500\r\n", &AtContext::default());
+        assert_eq!(
+            reader.prefixed("+CMGS").map(|_| ()),
+            Err(HayesError::CmsError(CmsError::Unknown))
+        );
+
+        let reader = ResponseReader::new(
+            b"\r\n+CMS ERROR: SMSC address unknown\r\n",
+            &AtContext::default(),
+        );
+        assert_eq!(
+            reader.finish(),
+            Err(HayesError::CmsError(CmsError::SmscAddressUnknown))
+        );
+
+        let reader = ResponseReader::new(b"\r\n+CME ERROR: 10\r\n", &AtContext::default());
+        assert_eq!(
+            reader.finish(),
+            Err(HayesError::CmeError(CmeError::SimNotInserted))
+        );
+
+        let reader = ResponseReader::new(
+            b"\r\n+CME ERROR: SIM PIN required\r\n",
+            &AtContext::default(),
+        );
+        assert_eq!(
+            reader.finish(),
+            Err(HayesError::CmeError(CmeError::SimPinRequired))
+        );
+
+        let reader = ResponseReader::new(b"\r\n+CME ERROR: 767\r\n", &AtContext::default());
+        assert_eq!(
+            reader.finish(),
+            Err(HayesError::CmeError(CmeError::Other(767)))
+        );
+
+        let reader = ResponseReader::new(b"\r\n+CME ERROR: SIM on fire\r\n", &AtContext::default());
+        assert_eq!(reader.finish(), Err(HayesError::ModemError));
+
+        let reader = ResponseReader::new(b"\r\nERROR\r\n", &AtContext::default());
+        assert_eq!(reader.finish(), Err(HayesError::ModemError));
+
+        let mut reader = ResponseReader::new(b"\r\n+CPMS: 1\r\n", &AtContext::default());
+        assert!(reader.prefixed("+CMGS").is_err());
+        assert!(reader.prefixed("+CPMS").is_ok());
+        assert_eq!(reader.finish(), Err(HayesError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_body() {
+        let mut reader = ResponseReader::new(
+            b"\r\n+CMGL: 1\r\nfirst\r\n\r\nline\r\n+CMGL: 2\r\n\r\n\r\nOK\r\n",
+            &AtContext::default(),
+        );
+        reader.prefixed("+CMGL").unwrap();
+        assert_eq!(reader.body(Some("+CMGL")), b"first\r\n\r\nline");
+        reader.prefixed("+CMGL").unwrap();
+        assert_eq!(reader.body(Some("+CMGL")), b"");
+        assert_eq!(reader.finish(), Ok(()));
+
+        let mut reader = ResponseReader::new(b"\r\n+CMT: 1\r\nHi", &AtContext::default());
+        reader.prefixed("+CMT").unwrap();
+        assert_eq!(reader.body(None), b"Hi");
+
+        assert_eq!(<()>::read(b"AT+CMGF=1\r\r\nOK\r\n"), Ok(()));
+        assert_eq!(<()>::read(b"\r\nERROR\r\n"), Err(HayesError::ModemError));
+    }
+
+    #[test]
+    fn test_fields() {
+        let mut fields = Fields::new(b"\"REC UNREAD\",\"+1555\",,\"24/01/02,03:04:05+00\"");
+        assert_eq!(fields.next::<&str>(), Ok("REC UNREAD"));
+        assert_eq!(fields.next::<&str>(), Ok("+1555"));
+        assert_eq!(fields.next::<Option<&str>>(), Ok(None));
+        assert!(!fields.is_empty());
+        assert_eq!(fields.next::<&str>(), Ok("24/01/02,03:04:05+00"));
+        assert!(fields.is_empty());
+
+        let mut fields = Fields::new(b"1 2");
+        assert_eq!(fields.next::<u8>(), Ok(1));
+        assert_eq!(fields.next::<u8>(), Err(HayesError::InvalidFormat));
+
+        let mut fields = Fields::after_prefix(b"+CEER: Call rejected, 17", "+CEER").unwrap();
+        assert_eq!(fields.rest(), b"Call rejected, 17");
+        assert!(fields.is_empty());
+    }
+
+    #[test]
+    fn test_parse_options() {
+        use crate::sms::{CpmsQueryResponse, Storage};
+
+        let buffer = b"\r\n+cpms: \"SM\", 1, 20 ,\"SM\",1,20, \"ME\",0,10  \r\n\r\nOK \r\n";
+        assert!(CpmsQueryResponse::read(buffer).is_err());
+
+        let tolerant = AtContext::with_parse_options(ParseOptions::TOLERANT);
+        let response = CpmsQueryResponse::read_with(buffer, &tolerant).unwrap();
+        assert_eq!(response.read.0, Storage::Sim);
+        assert_eq!(response.read.1.used, 1);
+        assert_eq!(response.receive.0, Storage::Me);
+        assert_eq!(response.receive.1.total, 10);
+
+        // Only the options that are enabled are tolerated
+        let spaces = AtContext::with_parse_options(ParseOptions {
+            field_spaces: true,
+            ..ParseOptions::STRICT
+        });
+        let mut reader = ResponseReader::new(b"\r\n+CPMS: 1, 2\r\n", &spaces);
+        let mut fields = reader.prefixed("+CPMS").unwrap();
+        assert_eq!(fields.next::<u8>(), Ok(1));
+        assert_eq!(fields.next::<u8>(), Ok(2));
+        assert!(
+            ResponseReader::new(b"\r\n+cpms: 1\r\n", &spaces)
+                .prefixed("+CPMS")
+                .is_err()
+        );
+
+        let registration = b"\r\n+CGREG: 0,1\r\n\r\nOK\r\n";
+        assert!(
+            ResponseReader::new(registration, &AtContext::default())
+                .prefixed("+CREG")
+                .is_err()
+        );
+        let mut reader = ResponseReader::new(registration, &tolerant);
+        let mut fields = reader.prefixed("+CREG").unwrap();
+        assert_eq!(fields.next::<u8>(), Ok(0));
+        assert_eq!(fields.next::<u8>(), Ok(1));
+        assert_eq!(reader.finish(), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_option_knobs() {
+        use crate::sms::CmgsResponse;
+
+        let strict = AtContext::default();
+        let tolerant = AtContext::with_parse_options(ParseOptions::TOLERANT);
+        let only = |knob: fn(&mut ParseOptions)| {
+            let mut options = ParseOptions::STRICT;
+            knob(&mut options);
+            AtContext::with_parse_options(options)
+        };
+        let case = only(|o| o.case_insensitive_prefixes = true);
+        let spaces = only(|o| o.field_spaces = true);
+        let trailing = only(|o| o.trailing_whitespace = true);
+        let aliases = only(|o| o.prefix_aliases = &[ParseOptions::REGISTRATION]);
+
+        // Each input is accepted by TOLERANT and its own knob, and rejected by
+        // STRICT
+        let check = |input: &[u8], knob: &AtContext| {
+            assert!(CmgsResponse::read_with(input, &strict).is_err());
+            assert_eq!(
+                CmgsResponse::read_with(input, knob).map(|r| r.message_reference),
+                Ok(42)
+            );
+            assert_eq!(
+                CmgsResponse::read_with(input, &tolerant).map(|r| r.message_reference),
+                Ok(42)
+            );
+        };
+        check(b"\r\n+cmgs: 42\r\n\r\nOK\r\n", &case);
+        check(b"\r\n+CMGS: 42\r\n\r\nok\r\n", &case);
+        check(b"\r\n+CMGS:  42\r\n\r\nOK\r\n", &spaces);
+        // Only spaces are skipped after the colon, as around fields
+        assert!(CmgsResponse::read_with(b"\r\n+CMGS:\t42\r\n\r\nOK\r\n", &tolerant).is_err());
+        check(b"\r\n+CMGS: 42\r\n\r\nOK \r\n", &trailing);
+        check(b"\r\n+CMGS: 42\r\n\r\nOK\t\r\n", &trailing);
+
+        let registration = b"\r\n+CGREG: 0,1\r\n\r\nOK\r\n";
+        for context in [&strict, &aliases, &tolerant] {
+            let mut reader = ResponseReader::new(registration, context);
+            let read = reader.prefixed("+CREG").map(|mut f| f.next::<u8>());
+            assert_eq!(read.is_ok(), context != &strict);
+        }
+
+        // Error result codes are recognised as far as the options allow
+        let error = b"\r\n+cms error: 500 \r\n";
+        assert_eq!(
+            <()>::read_with(error, &strict),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            <()>::read_with(error, &tolerant),
+            Err(HayesError::CmsError(CmsError::Unknown))
+        );
+
+        // A message body ends at the next entry or result code as the options
+        // allow
+        let listing = b"\r\n+CMGL: 1\r\nHi\r\n+cmgl: 2\r\nBye\r\nok \r\n";
+        let mut reader = ResponseReader::new(listing, &strict);
+        reader.prefixed("+CMGL").unwrap();
+        assert_eq!(reader.body(Some("+CMGL")), b"Hi\r\n+cmgl: 2\r\nBye\r\nok ");
+        let mut reader = ResponseReader::new(listing, &tolerant);
+        reader.prefixed("+CMGL").unwrap();
+        assert_eq!(reader.body(Some("+CMGL")), b"Hi");
+        reader.prefixed("+CMGL").unwrap();
+        assert_eq!(reader.body(Some("+CMGL")), b"Bye");
+        assert_eq!(reader.finish(), Ok(()));
+    }
+}
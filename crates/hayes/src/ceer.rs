@@ -0,0 +1,267 @@
+//! Extended error report (3GPP TS 27.007 §6.10)
+//!
+//! After a failed call, attach or PDP context activation, [`Ceer`] reads the
+//! modem's report on why it failed. The report is free-form text, but LTE
+//! modems often include the EMM or ESM cause the network rejected the
+//! request with, such as `+CEER: "EMM cause",15`, which is read as a
+//! [`RejectCause`].
+
+use crate::{
+    AtCommand, AtContext, AtReadable, AtResponse, HayesError,
+    at::{CommandWriter, ResponseReader, read_quoted},
+    error::error_codes,
+};
+
+error_codes!(
+    /// EPS mobility management cause, from 3GPP TS 24.301 §9.9.3.9
+    ///
+    /// Sent by the network when it rejects an attach, tracking area update or
+    /// service request.
+    EmmCause {
+        2 => ImsiUnknownInHss, "IMSI unknown in HSS",
+        3 => IllegalUe, "illegal UE",
+        5 => ImeiNotAccepted, "IMEI not accepted",
+        6 => IllegalMe, "illegal ME",
+        7 => EpsNotAllowed, "EPS services not allowed",
+        8 => EpsAndNonEpsNotAllowed, "EPS services and non-EPS services not allowed",
+        9 => IdentityNotDerived, "UE identity cannot be derived by the network",
+        10 => ImplicitlyDetached, "implicitly detached",
+        11 => PlmnNotAllowed, "PLMN not allowed",
+        12 => TrackingAreaNotAllowed, "tracking area not allowed",
+        13 => RoamingNotAllowed, "roaming not allowed in this tracking area",
+        14 => EpsNotAllowedInPlmn, "EPS services not allowed in this PLMN",
+        15 => NoSuitableCells, "no suitable cells in tracking area",
+        16 => MscNotReachable, "MSC temporarily not reachable",
+        17 => NetworkFailure, "network failure",
+        18 => CsDomainNotAvailable, "CS domain not available",
+        19 => EsmFailure, "ESM failure",
+        20 => MacFailure, "MAC failure",
+        21 => SynchFailure, "synch failure",
+        22 => Congestion, "congestion",
+        23 => SecurityCapabilitiesMismatch, "UE security capabilities mismatch",
+        24 => SecurityModeRejected, "security mode rejected, unspecified",
+        25 => CsgNotAuthorized, "not authorized for this CSG",
+        26 => NonEpsAuthenticationUnacceptable, "non-EPS authentication unacceptable",
+        31 => RedirectionTo5gcn, "redirection to 5GCN required",
+        35 => ServiceOptionNotAuthorized, "requested service option not authorized in this PLMN",
+        39 => CsServiceNotAvailable, "CS service temporarily not available",
+        40 => NoBearerContext, "no EPS bearer context activated",
+        42 => SevereNetworkFailure, "severe network failure",
+        78 => PlmnNotAllowedAtLocation, "PLMN not allowed to operate at the present UE location",
+        95 => SemanticallyIncorrect, "semantically incorrect message",
+        96 => InvalidMandatoryInformation, "invalid mandatory information",
+        97 => MessageTypeNotImplemented, "message type non-existent or not implemented",
+        98 => MessageTypeNotCompatible, "message type not compatible with the protocol state",
+        99 => ElementNotImplemented, "information element non-existent or not implemented",
+        100 => ConditionalElementError, "conditional IE error",
+        101 => MessageNotCompatible, "message not compatible with the protocol state",
+        111 => ProtocolError, "protocol error, unspecified",
+    }
+);
+
+error_codes!(
+    /// EPS session management cause, from 3GPP TS 24.301 §9.9.4.4
+    ///
+    /// Sent by the network when it rejects or deactivates a PDN connection
+    /// or EPS bearer.
+    EsmCause {
+        8 => OperatorBarring, "operator determined barring",
+        26 => InsufficientResources, "insufficient resources",
+        27 => UnknownApn, "missing or unknown APN",
+        28 => UnknownPdnType, "unknown PDN type",
+        29 => AuthenticationFailed, "user authentication failed",
+        30 => RejectedByGateway, "request rejected by Serving GW or PDN GW",
+        31 => Rejected, "request rejected, unspecified",
+        32 => ServiceOptionNotSupported, "service option not supported",
+        33 => ServiceOptionNotSubscribed, "requested service option not subscribed",
+        34 => ServiceOptionOutOfOrder, "service option temporarily out of order",
+        35 => PtiInUse, "PTI already in use",
+        36 => RegularDeactivation, "regular deactivation",
+        37 => QosNotAccepted, "EPS QoS not accepted",
+        38 => NetworkFailure, "network failure",
+        39 => ReactivationRequested, "reactivation requested",
+        41 => TftSemanticError, "semantic error in the TFT operation",
+        42 => TftSyntacticalError, "syntactical error in the TFT operation",
+        43 => InvalidBearerIdentity, "invalid EPS bearer identity",
+        44 => FilterSemanticError, "semantic errors in packet filter(s)",
+        45 => FilterSyntacticalError, "syntactical errors in packet filter(s)",
+        47 => PtiMismatch, "PTI mismatch",
+        49 => LastPdnDisconnectionNotAllowed, "last PDN disconnection not allowed",
+        50 => Ipv4OnlyAllowed, "PDN type IPv4 only allowed",
+        51 => Ipv6OnlyAllowed, "PDN type IPv6 only allowed",
+        52 => SingleAddressBearersOnly, "single address bearers only allowed",
+        53 => EsmInformationNotReceived, "ESM information not received",
+        54 => PdnConnectionDoesNotExist, "PDN connection does not exist",
+        55 => MultiplePdnConnectionsNotAllowed, "multiple PDN connections for a given APN not allowed",
+        56 => Collision, "collision with network initiated request",
+        57 => Ipv4v6OnlyAllowed, "PDN type IPv4v6 only allowed",
+        58 => NonIpOnlyAllowed, "PDN type non IP only allowed",
+        59 => UnsupportedQci, "unsupported QCI value",
+        60 => BearerHandlingNotSupported, "bearer handling not supported",
+        61 => EthernetOnlyAllowed, "PDN type Ethernet only allowed",
+        65 => MaximumBearersReached, "maximum number of EPS bearers reached",
+        66 => ApnNotSupported, "requested APN not supported in current RAT and PLMN combination",
+        81 => InvalidPti, "invalid PTI value",
+        95 => SemanticallyIncorrect, "semantically incorrect message",
+        96 => InvalidMandatoryInformation, "invalid mandatory information",
+        97 => MessageTypeNotImplemented, "message type non-existent or not implemented",
+        98 => MessageTypeNotCompatible, "message type not compatible with the protocol state",
+        99 => ElementNotImplemented, "information element non-existent or not implemented",
+        100 => ConditionalElementError, "conditional IE error",
+        101 => MessageNotCompatible, "message not compatible with the protocol state",
+        111 => ProtocolError, "protocol error, unspecified",
+        112 => ApnRestrictionIncompatible, "APN restriction value incompatible with active EPS bearer context",
+        113 => MultipleAccessesNotAllowed, "multiple accesses to a PDN connection not allowed",
+    }
+);
+
+/// A network reject cause found in an extended error report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RejectCause {
+    /// EPS mobility management, such as a rejected attach
+    Emm(EmmCause),
+    /// EPS session management, such as a rejected PDN connection
+    Esm(EsmCause),
+}
+
+impl RejectCause {
+    /// Find the cause in a free-form report
+    ///
+    /// The cause is the first number following `EMM` or `ESM`, ignoring
+    /// ASCII case, as in `EMM cause: 15` or `"ESM reject cause",33`.
+    #[must_use]
+    pub fn find(report: &[u8]) -> Option<Self> {
+        let start = report.windows(3).position(|word| {
+            word.eq_ignore_ascii_case(b"EMM") || word.eq_ignore_ascii_case(b"ESM")
+        })?;
+        let is_emm = report[start + 1].eq_ignore_ascii_case(&b'M');
+
+        let rest = &report[start + 3..];
+        let digits = rest.iter().position(u8::is_ascii_digit)?;
+        let (code, _) = u16::read(&rest[digits..]).ok()?;
+
+        Some(if is_emm {
+            RejectCause::Emm(EmmCause::from_code(code))
+        } else {
+            RejectCause::Esm(EsmCause::from_code(code))
+        })
+    }
+}
+
+/// `AT+CEER`, read the extended report on the last failed call, attach or
+/// PDP context activation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ceer;
+
+impl AtCommand for Ceer {
+    type Response<'at> = CeerResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        CommandWriter::new(buffer, "+CEER")?.finish()
+    }
+}
+
+/// Response to [`Ceer`], `+CEER: <report>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CeerResponse<'at> {
+    /// The report, without quotes when it's a single quoted string
+    ///
+    /// Vendors add their own fields, so anything else is returned as sent.
+    pub report: &'at [u8],
+    /// The EMM or ESM cause given in the report, if any
+    pub cause: Option<RejectCause>,
+}
+
+impl<'at> AtResponse<'at> for CeerResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let report = reader.prefixed("+CEER")?.rest();
+        reader.finish()?;
+
+        let report = match read_quoted(report) {
+            Ok((quoted, consumed)) if consumed == report.len() => quoted,
+            _ => report,
+        };
+
+        Ok(CeerResponse {
+            report,
+            cause: RejectCause::find(report),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let mut buf = [0u8; 16];
+        let len = Ceer.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CEER\r\n");
+    }
+
+    #[test]
+    fn test_read() {
+        let response =
+            CeerResponse::read(b"\r\n+CEER: \"No cause information available\"\r\n\r\nOK\r\n")
+                .unwrap();
+        assert_eq!(response.report, b"No cause information available");
+        assert_eq!(response.cause, None);
+
+        let response = CeerResponse::read(b"\r\n+CEER: \"EMM cause\",15\r\n\r\nOK\r\n").unwrap();
+        assert_eq!(response.report, b"\"EMM cause\",15");
+        assert_eq!(
+            response.cause,
+            Some(RejectCause::Emm(EmmCause::NoSuitableCells))
+        );
+
+        let response = CeerResponse::read(b"\r\n+CEER: ESM cause: 33\r\n\r\nOK\r\n").unwrap();
+        assert_eq!(
+            response.cause,
+            Some(RejectCause::Esm(EsmCause::ServiceOptionNotSubscribed))
+        );
+
+        assert_eq!(
+            CeerResponse::read(b"\r\nOK\r\n"),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_find() {
+        assert_eq!(
+            RejectCause::find(b"LTE esm reject, cause 27 (missing APN)"),
+            Some(RejectCause::Esm(EsmCause::UnknownApn))
+        );
+        assert_eq!(
+            RejectCause::find(b"emm: 200"),
+            Some(RejectCause::Emm(EmmCause::Other(200)))
+        );
+        assert_eq!(RejectCause::find(b"EMM"), None);
+        assert_eq!(RejectCause::find(b"Normal call clearing 16"), None);
+    }
+
+    #[test]
+    fn test_causes() {
+        assert_eq!(EmmCause::from_code(7), EmmCause::EpsNotAllowed);
+        assert_eq!(
+            EsmCause::from_text(b"Missing or unknown APN"),
+            Some(EsmCause::UnknownApn)
+        );
+
+        for &cause in EmmCause::ALL {
+            assert_eq!(EmmCause::from_code(cause.code()), cause);
+        }
+        for &cause in EsmCause::ALL {
+            assert_eq!(EsmCause::from_code(cause.code()), cause);
+        }
+    }
+}
@@ -0,0 +1,432 @@
+//! GSM 7-bit default alphabet (3GPP TS 23.038 §6.2.1)
+//!
+//! Text is handled as unpacked septets, one per byte, which is how modems
+//! exchange string parameters when `+CSCS="GSM"` is selected. [`pack`] and
+//! [`unpack`] convert to and from the packed form used in SMS PDUs.
+
+use crate::HayesError;
+
+/// Escape to the extension table
+pub const ESCAPE: u8 = 0x1B;
+
+/// Default alphabet, indexed by septet
+const DEFAULT: [char; 128] = [
+    '@', '£', '$', '¥', 'è', 'é', 'ù', 'ì', 'ò', 'Ç', '\n', 'Ø', 'ø', '\r', 'Å', 'å', //
+    'Δ', '_', 'Φ', 'Γ', 'Λ', 'Ω', 'Π', 'Ψ', 'Σ', 'Θ', 'Ξ', '\u{1B}', 'Æ', 'æ', 'ß', 'É', //
+    ' ', '!', '"', '#', '¤', '%', '&', '\'', '(', ')', '*', '+', ',', '-', '.', '/', //
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', ':', ';', '<', '=', '>', '?', //
+    '¡', 'A', 'B', 'C', 'D', 'E', 'F', 'G', 'H', 'I', 'J', 'K', 'L', 'M', 'N', 'O', //
+    'P', 'Q', 'R', 'S', 'T', 'U', 'V', 'W', 'X', 'Y', 'Z', 'Ä', 'Ö', 'Ñ', 'Ü', '§', //
+    '¿', 'a', 'b', 'c', 'd', 'e', 'f', 'g', 'h', 'i', 'j', 'k', 'l', 'm', 'n', 'o', //
+    'p', 'q', 'r', 's', 't', 'u', 'v', 'w', 'x', 'y', 'z', 'ä', 'ö', 'ñ', 'ü', 'à', //
+];
+
+/// Extension table entries, as (septet following [`ESCAPE`], character)
+const EXTENSION: [(u8, char); 10] = [
+    (0x0A, '\u{0C}'),
+    (0x14, '^'),
+    (0x28, '{'),
+    (0x29, '}'),
+    (0x2F, '\\'),
+    (0x3C, '['),
+    (0x3D, '~'),
+    (0x3E, ']'),
+    (0x40, '|'),
+    (0x65, '€'),
+];
+
+/// Encode a character as one septet, or two for extension table characters
+///
+/// Returns `None` if the character isn't in the alphabet.
+#[must_use]
+pub fn encode_char(c: char) -> Option<([u8; 2], usize)> {
+    if let Some(&(septet, _)) = EXTENSION.iter().find(|&&(_, ext)| ext == c) {
+        return Some(([ESCAPE, septet], 2));
+    }
+
+    // The escape code itself is not a character
+    if c == '\u{1B}' {
+        return None;
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    DEFAULT
+        .iter()
+        .position(|&d| d == c)
+        .map(|septet| ([septet as u8, 0], 1))
+}
+
+/// Decode a single septet from the default alphabet
+///
+/// The high bit is ignored.
+#[must_use]
+pub fn decode_septet(septet: u8) -> char {
+    DEFAULT[usize::from(septet & 0x7F)]
+}
+
+/// Decode a septet following [`ESCAPE`]
+///
+/// Codes without an extension character fall back to the default alphabet,
+/// as required by the specification.
+#[must_use]
+pub fn decode_extension(septet: u8) -> char {
+    EXTENSION
+        .iter()
+        .find(|&&(ext, _)| ext == septet & 0x7F)
+        .map_or_else(|| decode_septet(septet), |&(_, c)| c)
+}
+
+/// Number of septets needed to encode `text`
+///
+/// # Errors
+///
+/// Returns [`HayesError::OutOfRange`] if `text` contains a character that
+/// isn't in the alphabet.
+pub fn encoded_len(text: &str) -> Result<usize, HayesError> {
+    text.chars().try_fold(0, |len, c| {
+        encode_char(c)
+            .map(|(_, n)| len + n)
+            .ok_or(HayesError::OutOfRange)
+    })
+}
+
+/// Encode `text` as unpacked septets
+///
+/// Returns the number of septets written.
+///
+/// # Errors
+///
+/// Returns [`HayesError::OutOfRange`] if `text` contains a character that
+/// isn't in the alphabet, or [`HayesError::InsufficientBuffer`] if `output`
+/// is too small.
+pub fn encode(text: &str, output: &mut [u8]) -> Result<usize, HayesError> {
+    let required = encoded_len(text)?;
+    if output.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: output.len(),
+        });
+    }
+
+    let mut pos = 0;
+    for c in text.chars() {
+        // Representability was checked by encoded_len
+        let (septets, len) = encode_char(c).ok_or(HayesError::OutOfRange)?;
+        output[pos..pos + len].copy_from_slice(&septets[..len]);
+        pos += len;
+    }
+
+    Ok(pos)
+}
+
+/// Iterator over the characters of unpacked septets
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Chars<I> {
+    septets: I,
+}
+
+impl<I: Iterator<Item = u8>> Chars<I> {
+    /// Lazily decode a stream of septets, such as from [`unpacked`]
+    ///
+    /// The high bit of each septet is ignored.
+    pub fn new(septets: impl IntoIterator<IntoIter = I>) -> Self {
+        Chars {
+            septets: septets.into_iter(),
+        }
+    }
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Chars<I> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let septet = self.septets.next()?;
+        if septet & 0x7F != ESCAPE {
+            return Some(decode_septet(septet));
+        }
+
+        // A dangling escape is displayed as a space
+        Some(self.septets.next().map_or(' ', decode_extension))
+    }
+}
+
+/// Lazily decode unpacked septets
+///
+/// The high bit of each byte is ignored.
+#[must_use]
+pub fn chars(septets: &[u8]) -> Chars<core::iter::Copied<core::slice::Iter<'_, u8>>> {
+    Chars::new(septets.iter().copied())
+}
+
+/// Decode unpacked septets into UTF-8
+///
+/// # Errors
+///
+/// Returns [`HayesError::InvalidFormat`] if a byte has its high bit set, or
+/// [`HayesError::InsufficientBuffer`] if `output` is too small.
+pub fn decode<'o>(septets: &[u8], output: &'o mut [u8]) -> Result<&'o str, HayesError> {
+    if septets.iter().any(|&b| b > 0x7F) {
+        return Err(HayesError::InvalidFormat);
+    }
+
+    let required = chars(septets).map(char::len_utf8).sum();
+    if output.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: output.len(),
+        });
+    }
+
+    let mut pos = 0;
+    for c in chars(septets) {
+        pos += c.encode_utf8(&mut output[pos..]).len();
+    }
+
+    core::str::from_utf8(&output[..pos]).map_err(|_| HayesError::InvalidUtf8)
+}
+
+/// Number of octets needed to pack `count` septets after `padding` fill bits
+#[must_use]
+pub const fn packed_len(count: usize, padding: u8) -> usize {
+    (count * 7 + padding as usize).div_ceil(8)
+}
+
+/// Pack septets into octets, least significant bit first
+///
+/// `padding` fill bits (0–6) are inserted before the first septet so that it
+/// starts on a septet boundary after a user data header. Returns the number of
+/// octets written.
+///
+/// # Errors
+///
+/// Returns [`HayesError::InsufficientBuffer`] if `output` is too small.
+pub fn pack(septets: &[u8], padding: u8, output: &mut [u8]) -> Result<usize, HayesError> {
+    let required = packed_len(septets.len(), padding);
+    if output.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: output.len(),
+        });
+    }
+
+    output[..required].fill(0);
+
+    let mut bit = usize::from(padding);
+    for &septet in septets {
+        let value = u16::from(septet & 0x7F) << (bit % 8);
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            output[bit / 8] |= value as u8;
+            if bit % 8 > 1 {
+                output[bit / 8 + 1] |= (value >> 8) as u8;
+            }
+        }
+        bit += 7;
+    }
+
+    Ok(required)
+}
+
+/// Unpack `count` septets from octets, skipping `padding` fill bits
+///
+/// Returns the number of septets written.
+///
+/// # Errors
+///
+/// Returns [`HayesError::UnexpectedEnd`] if `packed` is too short, or
+/// [`HayesError::InsufficientBuffer`] if `output` is too small.
+pub fn unpack(
+    packed: &[u8],
+    padding: u8,
+    count: usize,
+    output: &mut [u8],
+) -> Result<usize, HayesError> {
+    if packed.len() < packed_len(count, padding) {
+        return Err(HayesError::UnexpectedEnd);
+    }
+    if output.len() < count {
+        return Err(HayesError::InsufficientBuffer {
+            required: count,
+            available: output.len(),
+        });
+    }
+
+    let mut bit = usize::from(padding);
+    for slot in &mut output[..count] {
+        let low = u16::from(packed[bit / 8]);
+        let high = packed.get(bit / 8 + 1).map_or(0, |&b| u16::from(b));
+        #[allow(clippy::cast_possible_truncation)]
+        {
+            *slot = (((high << 8 | low) >> (bit % 8)) & 0x7F) as u8;
+        }
+        bit += 7;
+    }
+
+    Ok(count)
+}
+
+/// Iterator over septets unpacked from a stream of octets
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Unpacked<I> {
+    octets: I,
+    padding: u8,
+    remaining: usize,
+    bits: u16,
+    available: u8,
+}
+
+impl<I: Iterator<Item = u8>> Iterator for Unpacked<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        while self.available < 7 {
+            self.bits |= u16::from(self.octets.next()?) << self.available;
+            self.available += 8;
+
+            if self.padding > 0 {
+                self.bits >>= self.padding;
+                self.available -= self.padding;
+                self.padding = 0;
+            }
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        let septet = (self.bits & 0x7F) as u8;
+        self.bits >>= 7;
+        self.available -= 7;
+        self.remaining -= 1;
+
+        Some(septet)
+    }
+}
+
+/// Lazily unpack `count` septets from octets, skipping `padding` fill bits
+///
+/// Unlike [`unpack`], this doesn't need a buffer for the septets. Iteration
+/// stops early if the octets run out.
+pub fn unpacked<I: IntoIterator<Item = u8>>(
+    octets: I,
+    padding: u8,
+    count: usize,
+) -> Unpacked<I::IntoIter> {
+    Unpacked {
+        octets: octets.into_iter(),
+        padding,
+        remaining: count,
+        bits: 0,
+        available: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unpacked() {
+        let packed = [0xE8, 0x32, 0x9B, 0xFD, 0x46, 0x97, 0xD9, 0xEC, 0x37];
+        let mut buf = [0u8; 10];
+        assert_eq!(unpack(&packed, 0, 10, &mut buf), Ok(10));
+        assert!(unpacked(packed, 0, 10).eq(buf));
+        assert!(Chars::new(unpacked(packed, 0, 10)).eq("hellohello".chars()));
+
+        // One fill bit, as after a 6 octet user data header
+        assert!(unpacked([0x90, 0x69], 1, 2).eq(*b"Hi"));
+
+        // Stops when the octets run out
+        assert_eq!(unpacked([0x48], 0, 3).count(), 1);
+    }
+
+    #[test]
+    fn test_encode() {
+        let mut buf = [0u8; 16];
+        assert_eq!(encode("@£$", &mut buf), Ok(3));
+        assert_eq!(&buf[..3], &[0x00, 0x01, 0x02]);
+
+        assert_eq!(encode("€{}", &mut buf), Ok(6));
+        assert_eq!(&buf[..6], &[0x1B, 0x65, 0x1B, 0x28, 0x1B, 0x29]);
+
+        assert_eq!(encode("Hi!", &mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"Hi!");
+
+        assert_eq!(encode("日本", &mut buf), Err(HayesError::OutOfRange));
+        assert_eq!(encode("\u{1B}", &mut buf), Err(HayesError::OutOfRange));
+        assert_eq!(
+            encode("€€€", &mut buf[..5]),
+            Err(HayesError::InsufficientBuffer {
+                required: 6,
+                available: 5,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut buf = [0u8; 32];
+        assert_eq!(decode(&[0x00, 0x01, 0x1B, 0x65], &mut buf), Ok("@£€"));
+        assert_eq!(decode(b"Hello", &mut buf), Ok("Hello"));
+
+        // Unknown extension codes fall back to the default table, dangling escape is a space
+        assert_eq!(decode(&[0x1B, 0x41, 0x1B], &mut buf), Ok("A "));
+
+        assert_eq!(decode(&[0x80], &mut buf), Err(HayesError::InvalidFormat));
+        assert_eq!(
+            decode(&[0x01], &mut buf[..1]),
+            Err(HayesError::InsufficientBuffer {
+                required: 2,
+                available: 1,
+            })
+        );
+    }
+
+    #[test]
+    fn test_encoded_len() {
+        assert_eq!(encoded_len("hello"), Ok(5));
+        assert_eq!(encoded_len("[€]"), Ok(6));
+        assert_eq!(encoded_len("ü"), Ok(1));
+        assert_eq!(encoded_len("ê"), Err(HayesError::OutOfRange));
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        // "hellohello" from 3GPP TS 23.038 examples
+        let mut packed = [0u8; 16];
+        assert_eq!(pack(b"hellohello", 0, &mut packed), Ok(9));
+        assert_eq!(
+            &packed[..9],
+            &[0xE8, 0x32, 0x9B, 0xFD, 0x46, 0x97, 0xD9, 0xEC, 0x37]
+        );
+
+        let mut septets = [0u8; 16];
+        assert_eq!(unpack(&packed[..9], 0, 10, &mut septets), Ok(10));
+        assert_eq!(&septets[..10], b"hellohello");
+
+        // Eight septets fill exactly seven octets
+        assert_eq!(pack(b"ABCDEFGH", 0, &mut packed), Ok(7));
+        assert_eq!(unpack(&packed[..7], 0, 8, &mut septets), Ok(8));
+        assert_eq!(&septets[..8], b"ABCDEFGH");
+
+        assert_eq!(
+            unpack(&packed[..6], 0, 8, &mut septets),
+            Err(HayesError::UnexpectedEnd)
+        );
+    }
+
+    #[test]
+    fn test_pack_unpack_padding() {
+        // One fill bit after a six-octet user data header
+        let mut packed = [0u8; 16];
+        assert_eq!(pack(b"Hi", 1, &mut packed), Ok(2));
+        assert_eq!(&packed[..2], &[0x90, 0x69]);
+
+        let mut septets = [0u8; 4];
+        assert_eq!(unpack(&packed[..2], 1, 2, &mut septets), Ok(2));
+        assert_eq!(&septets[..2], b"Hi");
+    }
+}
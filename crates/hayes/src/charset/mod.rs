@@ -0,0 +1,812 @@
+//! Character set support for string parameters
+//!
+//! Modems transcode string parameters to the TE character set selected with
+//! `+CSCS`. The types here read and write strings in those character sets
+//! without allocating: received strings borrow the input buffer and are decoded
+//! on demand, outgoing strings are encoded straight into the output buffer.
+
+pub mod gsm7;
+pub mod ucs2;
+
+use crate::{
+    AtContext, AtReadable, AtReadableWith, AtWritable, AtWritableWith, HayesError,
+    at::{check_quoted, read_quoted},
+    types::hex::{DIGITS, hex_digit},
+};
+
+/// TE character set, as selected with `+CSCS`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Charset {
+    /// International Reference Alphabet (7-bit ASCII), `"IRA"`
+    ///
+    /// This is the default after reset. When reading, UTF-8 is accepted as
+    /// many modems pass it through unchanged.
+    #[default]
+    Ira,
+    /// GSM 7-bit default alphabet, `"GSM"`
+    Gsm,
+    /// UCS2 as hex digits, `"UCS2"`
+    Ucs2,
+    /// UTF-8, `"UTF-8"`
+    Utf8,
+    /// ISO 8859-1 (Latin-1), `"8859-1"`
+    Latin1,
+    /// Bytes as hex digit pairs, `"HEX"`
+    Hex,
+}
+
+impl Charset {
+    /// The name used by `+CSCS`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Charset::Ira => "IRA",
+            Charset::Gsm => "GSM",
+            Charset::Ucs2 => "UCS2",
+            Charset::Utf8 => "UTF-8",
+            Charset::Latin1 => "8859-1",
+            Charset::Hex => "HEX",
+        }
+    }
+
+    /// Look up a character set by its `+CSCS` name, ignoring case
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        [
+            Charset::Ira,
+            Charset::Gsm,
+            Charset::Ucs2,
+            Charset::Utf8,
+            Charset::Latin1,
+            Charset::Hex,
+        ]
+        .into_iter()
+        .find(|charset| charset.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Check that `raw` is a valid string in this character set
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidUtf8`] for invalid IRA or UTF-8 strings,
+    /// [`HayesError::InvalidFormat`] for septets with the high bit set or an
+    /// odd number of hex digits, and [`HayesError::ParseError`] for
+    /// non-hex characters.
+    pub fn validate(self, raw: &[u8]) -> Result<(), HayesError> {
+        match self {
+            Charset::Ira | Charset::Utf8 => core::str::from_utf8(raw)
+                .map(|_| ())
+                .map_err(|_| HayesError::InvalidUtf8),
+            Charset::Gsm => Gsm7Str::from_septets(raw).map(|_| ()),
+            Charset::Ucs2 => ucs2::validate(raw),
+            Charset::Latin1 => Ok(()),
+            Charset::Hex => {
+                if !raw.len().is_multiple_of(2) {
+                    return Err(HayesError::InvalidFormat);
+                }
+                if raw.iter().any(|&b| hex_digit(b).is_none()) {
+                    return Err(HayesError::ParseError);
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Encode a character, returning the bytes and how many are used
+    fn encode_char(self, c: char) -> Result<([u8; 8], usize), HayesError> {
+        let mut buf = [0u8; 8];
+        let len = match self {
+            Charset::Ira if c.is_ascii() => c.encode_utf8(&mut buf).len(),
+            Charset::Ira => return Err(HayesError::OutOfRange),
+            Charset::Utf8 => c.encode_utf8(&mut buf).len(),
+            Charset::Gsm => {
+                let (septets, len) = gsm7::encode_char(c).ok_or(HayesError::OutOfRange)?;
+                buf[..len].copy_from_slice(&septets[..len]);
+                len
+            }
+            Charset::Ucs2 => {
+                let mut units = [0u16; 2];
+                let units = c.encode_utf16(&mut units);
+                for (unit, digits) in units.iter().zip(buf.chunks_exact_mut(4)) {
+                    for (i, digit) in digits.iter_mut().enumerate() {
+                        *digit = DIGITS[usize::from((unit >> (12 - 4 * i)) & 0xF)];
+                    }
+                }
+                units.len() * 4
+            }
+            Charset::Latin1 => {
+                buf[0] = u8::try_from(c).map_err(|_| HayesError::OutOfRange)?;
+                1
+            }
+            Charset::Hex => {
+                let byte = u8::try_from(c).map_err(|_| HayesError::OutOfRange)?;
+                buf[0] = DIGITS[usize::from(byte >> 4)];
+                buf[1] = DIGITS[usize::from(byte & 0xF)];
+                2
+            }
+        };
+
+        Ok((buf, len))
+    }
+
+    /// Number of bytes needed to encode `chars`
+    fn encoded_len(self, chars: impl Iterator<Item = char>) -> Result<usize, HayesError> {
+        chars.map(|c| self.encode_char(c).map(|(_, len)| len)).sum()
+    }
+}
+
+impl<'at> AtReadable<'at> for Charset {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (name, consumed) = <&str>::read(input)?;
+        let charset = Charset::from_name(name).ok_or(HayesError::UnsupportedError)?;
+        Ok((charset, consumed))
+    }
+}
+
+impl AtWritable for Charset {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        self.name().write(output)
+    }
+}
+
+/// Copy already-decoded text into a caller's buffer
+fn copy_str<'b>(text: &str, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+    if buffer.len() < text.len() {
+        return Err(HayesError::InsufficientBuffer {
+            required: text.len(),
+            available: buffer.len(),
+        });
+    }
+
+    buffer[..text.len()].copy_from_slice(text.as_bytes());
+    core::str::from_utf8(&buffer[..text.len()]).map_err(|_| HayesError::InvalidUtf8)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Gsm7Repr<'a> {
+    Septets(&'a [u8]),
+    Text(&'a str),
+}
+
+/// String in the GSM 7-bit default alphabet, as used with `+CSCS="GSM"`
+///
+/// When read, the unpacked septets are borrowed from the input and decoded
+/// with [`Gsm7Str::chars`] or [`Gsm7Str::decode_into`]. When created from a
+/// `&str` with [`Gsm7Str::new`], the text is encoded as it's written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Gsm7Str<'a>(Gsm7Repr<'a>);
+
+impl<'a> Gsm7Str<'a> {
+    /// Wrap text to be written in the GSM alphabet
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `text` contains a character that
+    /// isn't in the alphabet.
+    pub fn new(text: &'a str) -> Result<Self, HayesError> {
+        gsm7::encoded_len(text)?;
+        Ok(Gsm7Str(Gsm7Repr::Text(text)))
+    }
+
+    /// Wrap unpacked septets
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if a byte has its high bit set.
+    pub fn from_septets(septets: &'a [u8]) -> Result<Self, HayesError> {
+        if septets.iter().any(|&b| b > 0x7F) {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        Ok(Gsm7Str(Gsm7Repr::Septets(septets)))
+    }
+
+    /// Number of septets in the encoded string
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self.0 {
+            Gsm7Repr::Septets(septets) => septets.len(),
+            Gsm7Repr::Text(text) => gsm7::encoded_len(text).unwrap_or_default(),
+        }
+    }
+
+    /// Whether the string is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the decoded characters
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        let (septets, text) = match self.0 {
+            Gsm7Repr::Septets(septets) => (septets, ""),
+            Gsm7Repr::Text(text) => (&[][..], text),
+        };
+
+        gsm7::chars(septets).chain(text.chars())
+    }
+
+    /// Decode the string into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        match self.0 {
+            Gsm7Repr::Septets(septets) => gsm7::decode(septets, buffer),
+            Gsm7Repr::Text(text) => copy_str(text, buffer),
+        }
+    }
+
+    /// Encode the string into `buffer` as unpacked septets
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn encode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b [u8], HayesError> {
+        let len = match self.0 {
+            Gsm7Repr::Septets(septets) => {
+                if buffer.len() < septets.len() {
+                    return Err(HayesError::InsufficientBuffer {
+                        required: septets.len(),
+                        available: buffer.len(),
+                    });
+                }
+                buffer[..septets.len()].copy_from_slice(septets);
+                septets.len()
+            }
+            Gsm7Repr::Text(text) => gsm7::encode(text, buffer)?,
+        };
+
+        Ok(&buffer[..len])
+    }
+}
+
+impl<'at> AtReadable<'at> for Gsm7Str<'at> {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        Self::read_located(input).map_err(HayesError::unlocated)
+    }
+
+    fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (septets, consumed) = read_quoted(input)?;
+        Ok((Gsm7Str::from_septets(septets)?, consumed))
+    }
+}
+
+impl AtWritable for Gsm7Str<'_> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let required = self.len() + 2;
+
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        output[0] = b'"';
+        let len = self.encode_into(&mut output[1..])?.len();
+        check_quoted(&output[1..=len], true)?;
+        output[1 + len] = b'"';
+
+        Ok(required)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum Ucs2Repr<'a> {
+    Hex(&'a [u8]),
+    Text(&'a str),
+}
+
+/// String encoded as UCS2 hex, as used with `+CSCS="UCS2"`
+///
+/// When read, the hex digits (such as `"004800690020"`) are borrowed from the
+/// input and decoded lazily with [`Ucs2Str::chars`] or
+/// [`Ucs2Str::decode_into`]. When created from a `&str` with
+/// [`Ucs2Str::new`], the text is encoded as it's written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ucs2Str<'a>(Ucs2Repr<'a>);
+
+impl<'a> Ucs2Str<'a> {
+    /// Wrap text to be written as UCS2 hex
+    #[must_use]
+    pub const fn new(text: &'a str) -> Self {
+        Ucs2Str(Ucs2Repr::Text(text))
+    }
+
+    /// Wrap UCS2 hex digits
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `hex` isn't a whole number of four-digit code units.
+    pub fn from_hex(hex: &'a [u8]) -> Result<Self, HayesError> {
+        ucs2::validate(hex)?;
+        Ok(Ucs2Str(Ucs2Repr::Hex(hex)))
+    }
+
+    /// Number of hex digits in the encoded string
+    #[must_use]
+    pub fn len(&self) -> usize {
+        match self.0 {
+            Ucs2Repr::Hex(hex) => hex.len(),
+            Ucs2Repr::Text(text) => ucs2::encoded_len(text),
+        }
+    }
+
+    /// Whether the string is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterate over the decoded characters
+    pub fn chars(&self) -> impl Iterator<Item = char> + 'a {
+        let (hex, text) = match self.0 {
+            Ucs2Repr::Hex(hex) => (hex, ""),
+            Ucs2Repr::Text(text) => (&[][..], text),
+        };
+
+        ucs2::chars(hex).chain(text.chars())
+    }
+
+    /// Decode the string into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        match self.0 {
+            Ucs2Repr::Hex(hex) => ucs2::decode(hex, buffer),
+            Ucs2Repr::Text(text) => copy_str(text, buffer),
+        }
+    }
+}
+
+impl<'at> AtReadable<'at> for Ucs2Str<'at> {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        Self::read_located(input).map_err(HayesError::unlocated)
+    }
+
+    fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (hex, consumed) = read_quoted(input)?;
+        Ok((Ucs2Str::from_hex(hex)?, consumed))
+    }
+}
+
+impl AtWritable for Ucs2Str<'_> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let required = self.len() + 2;
+
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        output[0] = b'"';
+        match self.0 {
+            Ucs2Repr::Hex(hex) => output[1..=hex.len()].copy_from_slice(hex),
+            Ucs2Repr::Text(text) => {
+                ucs2::encode(text, &mut output[1..])?;
+            }
+        }
+        output[required - 1] = b'"';
+
+        Ok(required)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum TextRepr<'a> {
+    Encoded(&'a [u8], Charset),
+    Text(&'a str),
+}
+
+/// String in whichever character set the modem is configured for
+///
+/// Unlike [`Gsm7Str`] and [`Ucs2Str`], the encoding isn't fixed: `Text` is read
+/// and written with [`AtReadableWith`] and [`AtWritableWith`] using the
+/// [`AtContext::charset`]. When read, the encoded string is borrowed from the
+/// input and decoded on demand. When created from a `&str` with [`Text::new`],
+/// the text is encoded as it's written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Text<'a>(TextRepr<'a>);
+
+impl<'a> Text<'a> {
+    /// Wrap text to be written in the context's character set
+    #[must_use]
+    pub const fn new(text: &'a str) -> Self {
+        Text(TextRepr::Text(text))
+    }
+
+    /// Wrap a string encoded in `charset`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `raw` isn't valid in `charset`, see
+    /// [`Charset::validate`].
+    pub fn from_encoded(raw: &'a [u8], charset: Charset) -> Result<Self, HayesError> {
+        charset.validate(raw)?;
+        Ok(Text(TextRepr::Encoded(raw, charset)))
+    }
+
+    /// The character set the string was read in, if it was read
+    #[must_use]
+    pub const fn charset(&self) -> Option<Charset> {
+        match self.0 {
+            TextRepr::Encoded(_, charset) => Some(charset),
+            TextRepr::Text(_) => None,
+        }
+    }
+
+    /// Whether the string is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        match self.0 {
+            TextRepr::Encoded(raw, _) => raw.is_empty(),
+            TextRepr::Text(text) => text.is_empty(),
+        }
+    }
+
+    /// Iterate over the decoded characters
+    #[must_use]
+    pub fn chars(&self) -> TextChars<'a> {
+        TextChars(match self.0 {
+            TextRepr::Encoded(raw, Charset::Ira | Charset::Utf8) => {
+                // Validated when the text was created
+                CharsRepr::Utf8(core::str::from_utf8(raw).unwrap_or_default().chars())
+            }
+            TextRepr::Encoded(raw, Charset::Gsm) => CharsRepr::Gsm(gsm7::chars(raw)),
+            TextRepr::Encoded(raw, Charset::Ucs2) => CharsRepr::Ucs2(ucs2::chars(raw)),
+            TextRepr::Encoded(raw, Charset::Latin1) => CharsRepr::Latin1(raw.iter()),
+            TextRepr::Encoded(raw, Charset::Hex) => CharsRepr::Hex(raw.chunks_exact(2)),
+            TextRepr::Text(text) => CharsRepr::Utf8(text.chars()),
+        })
+    }
+
+    /// Decode the string into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        let required = self.chars().map(char::len_utf8).sum();
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        let mut pos = 0;
+        for c in self.chars() {
+            pos += c.encode_utf8(&mut buffer[pos..]).len();
+        }
+
+        core::str::from_utf8(&buffer[..pos]).map_err(|_| HayesError::InvalidUtf8)
+    }
+
+    /// Number of bytes needed to encode the string in `charset`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if a character can't be represented
+    /// in `charset`.
+    pub fn encoded_len(&self, charset: Charset) -> Result<usize, HayesError> {
+        match self.0 {
+            TextRepr::Encoded(raw, encoded) if encoded == charset => Ok(raw.len()),
+            _ => charset.encoded_len(self.chars()),
+        }
+    }
+
+    /// Encode the string into `buffer` in `charset`, without quotes
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if a character can't be represented
+    /// in `charset`, or [`HayesError::InsufficientBuffer`] if `buffer` is too
+    /// small.
+    pub fn encode_into<'b>(
+        &self,
+        buffer: &'b mut [u8],
+        charset: Charset,
+    ) -> Result<&'b [u8], HayesError> {
+        let required = self.encoded_len(charset)?;
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        match self.0 {
+            TextRepr::Encoded(raw, encoded) if encoded == charset => {
+                buffer[..raw.len()].copy_from_slice(raw);
+            }
+            _ => {
+                let mut pos = 0;
+                for c in self.chars() {
+                    let (bytes, len) = charset.encode_char(c)?;
+                    buffer[pos..pos + len].copy_from_slice(&bytes[..len]);
+                    pos += len;
+                }
+            }
+        }
+
+        Ok(&buffer[..required])
+    }
+}
+
+#[derive(Debug, Clone)]
+enum CharsRepr<'a> {
+    Utf8(core::str::Chars<'a>),
+    Gsm(gsm7::Chars<core::iter::Copied<core::slice::Iter<'a, u8>>>),
+    Ucs2(ucs2::Chars<'a>),
+    Latin1(core::slice::Iter<'a, u8>),
+    Hex(core::slice::ChunksExact<'a, u8>),
+}
+
+/// Iterator over the characters of a [`Text`]
+#[derive(Debug, Clone)]
+pub struct TextChars<'a>(CharsRepr<'a>);
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for TextChars<'_> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        format_chars(f, "TextChars", self.clone());
+    }
+}
+
+/// Show the remaining characters of an iterator, such as `TextChars("Hi")`
+#[cfg(feature = "defmt")]
+pub(crate) fn format_chars(f: defmt::Formatter<'_>, name: &str, chars: impl Iterator<Item = char>) {
+    defmt::write!(f, "{=str}(\"", name);
+    for c in chars {
+        defmt::write!(f, "{=char}", c);
+    }
+    defmt::write!(f, "\")");
+}
+
+impl Iterator for TextChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match &mut self.0 {
+            CharsRepr::Utf8(chars) => chars.next(),
+            CharsRepr::Gsm(chars) => chars.next(),
+            CharsRepr::Ucs2(chars) => chars.next(),
+            CharsRepr::Latin1(bytes) => bytes.next().map(|&b| char::from(b)),
+            CharsRepr::Hex(digits) => digits.next().map(|pair| {
+                let byte = pair
+                    .iter()
+                    .fold(0, |byte, &b| (byte << 4) | hex_digit(b).unwrap_or_default());
+                char::from(byte)
+            }),
+        }
+    }
+}
+
+impl<'at> AtReadableWith<'at> for Text<'at> {
+    fn read_with(input: &'at [u8], context: &AtContext) -> Result<(Self, usize), HayesError> {
+        let (raw, consumed) = read_quoted(input).map_err(HayesError::unlocated)?;
+        Ok((Text::from_encoded(raw, context.charset)?, consumed))
+    }
+}
+
+impl AtWritableWith for Text<'_> {
+    fn write_with(&self, output: &mut [u8], context: &AtContext) -> Result<usize, HayesError> {
+        let required = self.encoded_len(context.charset)? + 2;
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        output[0] = b'"';
+        let encoded = self.encode_into(&mut output[1..], context.charset)?;
+        check_quoted(encoded, context.charset == Charset::Gsm)?;
+        output[required - 1] = b'"';
+
+        Ok(required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_gsm7_str() {
+        let (s, consumed) = Gsm7Str::read(b"\"\x00\x01 5\x1B\x65\",").unwrap();
+        assert_eq!(consumed, 8);
+        assert_eq!(s.len(), 6);
+
+        let mut buf = [0u8; 16];
+        assert_eq!(s.decode_into(&mut buf), Ok("@£ 5€"));
+        assert!(s.chars().eq("@£ 5€".chars()));
+
+        assert!(Gsm7Str::read(b"\"\xC3\xA9\"").is_err());
+        assert!(Gsm7Str::read(b"\"abc").is_err());
+    }
+
+    #[test]
+    fn test_write_gsm7_str() {
+        let mut buf = [0u8; 16];
+
+        let s = Gsm7Str::new("@£{x}").unwrap();
+        assert_eq!(s.len(), 7);
+        assert_eq!(s.write(&mut buf), Ok(9));
+        assert_eq!(&buf[..9], b"\"\x00\x01\x1B\x28x\x1B\x29\"");
+
+        assert!(s.write(&mut buf[..8]).is_err());
+        assert_eq!(Gsm7Str::new("ê"), Err(HayesError::OutOfRange));
+
+        assert_eq!(
+            Gsm7Str::new("a\"b").unwrap().write(&mut buf),
+            Err(HayesError::UnsafeCharacter {
+                byte: b'"',
+                offset: 1
+            })
+        );
+    }
+
+    #[test]
+    fn test_gsm7_str_round_trip() {
+        let mut buf = [0u8; 16];
+        let mut text = [0u8; 16];
+
+        let s = Gsm7Str::new("Δ€ß").unwrap();
+        let len = s.write(&mut buf).unwrap();
+
+        let (read, _) = Gsm7Str::read(&buf[..len]).unwrap();
+        assert_eq!(read.decode_into(&mut text), Ok("Δ€ß"));
+    }
+
+    #[test]
+    fn test_read_ucs2_str() {
+        let (s, consumed) = Ucs2Str::read(b"\"004800690020\",145").unwrap();
+        assert_eq!(consumed, 14);
+        assert_eq!(s.len(), 12);
+
+        let mut buf = [0u8; 16];
+        assert_eq!(s.decode_into(&mut buf), Ok("Hi "));
+
+        let (s, _) = Ucs2Str::read(b"\"4E2D56FD79FB52A8\"").unwrap();
+        assert!(s.chars().eq("中国移动".chars()));
+
+        assert!(Ucs2Str::read(b"\"00480\"").is_err());
+        assert!(Ucs2Str::read(b"004800690020").is_err());
+    }
+
+    #[test]
+    fn test_write_ucs2_str() {
+        let mut buf = [0u8; 32];
+
+        assert_eq!(Ucs2Str::new("Ωk").write(&mut buf), Ok(10));
+        assert_eq!(&buf[..10], b"\"03A9006B\"");
+
+        let (s, consumed) = Ucs2Str::read(b"\"D83DDE00\"").unwrap();
+        assert_eq!(s.write(&mut buf), Ok(consumed));
+        assert_eq!(&buf[..consumed], b"\"D83DDE00\"");
+
+        assert!(Ucs2Str::new("Ω").write(&mut buf[..5]).is_err());
+    }
+
+    #[test]
+    fn test_charset() {
+        let mut buf = [0u8; 16];
+
+        assert_eq!(Charset::read(b"\"UCS2\"\r\n"), Ok((Charset::Ucs2, 6)));
+        assert_eq!(Charset::read(b"\"8859-1\""), Ok((Charset::Latin1, 8)));
+        assert_eq!(Charset::read(b"\"gsm\""), Ok((Charset::Gsm, 5)));
+        assert_eq!(
+            Charset::read(b"\"PCCP437\""),
+            Err(HayesError::UnsupportedError)
+        );
+
+        assert_eq!(Charset::Utf8.write(&mut buf), Ok(7));
+        assert_eq!(&buf[..7], b"\"UTF-8\"");
+        assert_eq!(Charset::default(), Charset::Ira);
+    }
+
+    #[test]
+    fn test_read_text_with_context() {
+        let mut buf = [0u8; 32];
+        let input = b"\"00480069\",\"Hi\"";
+
+        let ucs2 = AtContext::with_charset(Charset::Ucs2);
+        let (text, consumed) = Text::read_with(input, &ucs2).unwrap();
+        assert_eq!(consumed, 10);
+        assert_eq!(text.charset(), Some(Charset::Ucs2));
+        assert_eq!(text.decode_into(&mut buf), Ok("Hi"));
+
+        // The same bytes mean something else in another character set
+        let (text, _) = Text::read_with(input, &AtContext::default()).unwrap();
+        assert_eq!(text.decode_into(&mut buf), Ok("00480069"));
+
+        let gsm = AtContext::with_charset(Charset::Gsm);
+        let (text, _) = Text::read_with(b"\"\x1B\x65 5\"", &gsm).unwrap();
+        assert!(text.chars().eq("€ 5".chars()));
+
+        let (text, _) =
+            Text::read_with(b"\"C4E9\"", &AtContext::with_charset(Charset::Hex)).unwrap();
+        assert!(text.chars().eq("Äé".chars()));
+
+        let latin1 = AtContext::with_charset(Charset::Latin1);
+        let (text, _) = Text::read_with(b"\"caf\xE9\"", &latin1).unwrap();
+        assert_eq!(text.decode_into(&mut buf), Ok("café"));
+
+        assert!(Text::read_with(b"\"004\"", &ucs2).is_err());
+        assert!(Text::read_with(b"\"\x80\"", &gsm).is_err());
+        assert!(Text::read_with(b"\"\xFF\"", &AtContext::default()).is_err());
+    }
+
+    #[test]
+    fn test_write_text_with_context() {
+        let mut buf = [0u8; 32];
+        let text = Text::new("Ωk");
+
+        let ucs2 = AtContext::with_charset(Charset::Ucs2);
+        assert_eq!(text.write_with(&mut buf, &ucs2), Ok(10));
+        assert_eq!(&buf[..10], b"\"03A9006B\"");
+
+        let gsm = AtContext::with_charset(Charset::Gsm);
+        assert_eq!(text.write_with(&mut buf, &gsm), Ok(4));
+        assert_eq!(&buf[..4], b"\"\x15k\"");
+
+        let utf8 = AtContext::with_charset(Charset::Utf8);
+        assert_eq!(text.write_with(&mut buf, &utf8), Ok(5));
+        assert_eq!(&buf[..5], "\"Ωk\"".as_bytes());
+
+        assert_eq!(
+            text.write_with(&mut buf, &AtContext::default()),
+            Err(HayesError::OutOfRange)
+        );
+        assert_eq!(
+            text.write_with(&mut buf[..9], &ucs2),
+            Err(HayesError::InsufficientBuffer {
+                required: 10,
+                available: 9,
+            })
+        );
+
+        let hex = AtContext::with_charset(Charset::Hex);
+        assert_eq!(Text::new("é!").write_with(&mut buf, &hex), Ok(6));
+        assert_eq!(&buf[..6], b"\"E921\"");
+
+        // Unsafe in the text itself, but not once hex encoded
+        let text = Text::new("a\r\nb");
+        assert_eq!(
+            text.write_with(&mut buf, &AtContext::default()),
+            Err(HayesError::UnsafeCharacter {
+                byte: b'\r',
+                offset: 1
+            })
+        );
+        assert_eq!(text.write_with(&mut buf, &hex), Ok(10));
+    }
+
+    #[test]
+    fn test_text_transcode() {
+        let mut buf = [0u8; 32];
+        let ucs2 = AtContext::with_charset(Charset::Ucs2);
+        let gsm = AtContext::with_charset(Charset::Gsm);
+
+        let (text, _) = Text::read_with(b"\"00400041\"", &ucs2).unwrap();
+        assert_eq!(text.write_with(&mut buf, &ucs2), Ok(10));
+        assert_eq!(&buf[..10], b"\"00400041\"");
+        assert_eq!(text.write_with(&mut buf, &gsm), Ok(4));
+        assert_eq!(&buf[..4], b"\"\x00A\"");
+    }
+}
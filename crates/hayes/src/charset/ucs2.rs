@@ -0,0 +1,197 @@
+//! UCS2 hex encoding, as used with `+CSCS="UCS2"`
+//!
+//! Each UTF-16 code unit is written as four hexadecimal digits, so `"Hi"` is
+//! `00480069`. Characters outside the Basic Multilingual Plane are written as
+//! surrogate pairs.
+
+use crate::{
+    HayesError,
+    types::hex::{DIGITS, hex_digit},
+};
+
+/// Number of hex digits needed to encode `text`
+#[must_use]
+pub fn encoded_len(text: &str) -> usize {
+    text.encode_utf16().count() * 4
+}
+
+/// Check that `hex` is a whole number of four-digit code units
+///
+/// # Errors
+///
+/// Returns [`HayesError::InvalidFormat`] if the length isn't a multiple of
+/// four, or [`HayesError::ParseError`] if it contains a non-hex character.
+pub fn validate(hex: &[u8]) -> Result<(), HayesError> {
+    if !hex.len().is_multiple_of(4) {
+        return Err(HayesError::InvalidFormat);
+    }
+
+    if hex.iter().any(|&b| hex_digit(b).is_none()) {
+        return Err(HayesError::ParseError);
+    }
+
+    Ok(())
+}
+
+/// Encode `text` as UCS2 hex digits
+///
+/// Returns the number of digits written.
+///
+/// # Errors
+///
+/// Returns [`HayesError::InsufficientBuffer`] if `output` is too small.
+pub fn encode(text: &str, output: &mut [u8]) -> Result<usize, HayesError> {
+    let required = encoded_len(text);
+    if output.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: output.len(),
+        });
+    }
+
+    for (unit, digits) in text.encode_utf16().zip(output.chunks_exact_mut(4)) {
+        for (i, digit) in digits.iter_mut().enumerate() {
+            *digit = DIGITS[usize::from((unit >> (12 - 4 * i)) & 0xF)];
+        }
+    }
+
+    Ok(required)
+}
+
+/// Iterator over the UTF-16 code units of UCS2 hex digits
+#[derive(Debug, Clone)]
+pub struct Units<'a> {
+    hex: core::slice::ChunksExact<'a, u8>,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Units<'_> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(f, "Units([");
+        for (i, unit) in self.clone().enumerate() {
+            let separator = if i == 0 { "" } else { ", " };
+            defmt::write!(f, "{=str}{=u16:04X}", separator, unit);
+        }
+        defmt::write!(f, "])");
+    }
+}
+
+impl Iterator for Units<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        let digits = self.hex.next()?;
+        Some(digits.iter().fold(0, |unit, &b| {
+            (unit << 4) | u16::from(hex_digit(b).unwrap_or_default())
+        }))
+    }
+}
+
+/// Iterator over the characters of UCS2 hex digits
+#[derive(Debug, Clone)]
+pub struct Chars<'a> {
+    inner: core::char::DecodeUtf16<Units<'a>>,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Chars<'_> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        super::format_chars(f, "Chars", self.clone());
+    }
+}
+
+impl Iterator for Chars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        self.inner
+            .next()
+            .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+    }
+}
+
+/// Lazily decode UCS2 hex digits
+///
+/// Surrogate pairs are combined. Unpaired surrogates decode to
+/// [`char::REPLACEMENT_CHARACTER`]. `hex` should have been checked with
+/// [`validate`]; invalid digits decode as zero.
+#[must_use]
+pub fn chars(hex: &[u8]) -> Chars<'_> {
+    Chars {
+        inner: char::decode_utf16(Units {
+            hex: hex.chunks_exact(4),
+        }),
+    }
+}
+
+/// Decode UCS2 hex digits into UTF-8
+///
+/// # Errors
+///
+/// Returns an error if `hex` fails [`validate`], or
+/// [`HayesError::InsufficientBuffer`] if `output` is too small.
+pub fn decode<'o>(hex: &[u8], output: &'o mut [u8]) -> Result<&'o str, HayesError> {
+    validate(hex)?;
+
+    let required = chars(hex).map(char::len_utf8).sum();
+    if output.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: output.len(),
+        });
+    }
+
+    let mut pos = 0;
+    for c in chars(hex) {
+        pos += c.encode_utf8(&mut output[pos..]).len();
+    }
+
+    core::str::from_utf8(&output[..pos]).map_err(|_| HayesError::InvalidUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode() {
+        let mut buf = [0u8; 32];
+        assert_eq!(encode("Hi ", &mut buf), Ok(12));
+        assert_eq!(&buf[..12], b"004800690020");
+
+        assert_eq!(encode("مرحبا", &mut buf), Ok(20));
+        assert_eq!(&buf[..20], b"06450631062D06280627");
+
+        // Outside the BMP, encoded as a surrogate pair
+        assert_eq!(encode("😀", &mut buf), Ok(8));
+        assert_eq!(&buf[..8], b"D83DDE00");
+
+        assert_eq!(
+            encode("你好", &mut buf[..7]),
+            Err(HayesError::InsufficientBuffer {
+                required: 8,
+                available: 7,
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode() {
+        let mut buf = [0u8; 32];
+        assert_eq!(decode(b"004800690020", &mut buf), Ok("Hi "));
+        assert_eq!(decode(b"4F60597D", &mut buf), Ok("你好"));
+        assert_eq!(decode(b"d83dde00", &mut buf), Ok("😀"));
+
+        // Unpaired surrogate
+        assert_eq!(decode(b"D83D0041", &mut buf), Ok("\u{FFFD}A"));
+
+        assert_eq!(decode(b"00480", &mut buf), Err(HayesError::InvalidFormat));
+        assert_eq!(decode(b"004G", &mut buf), Err(HayesError::ParseError));
+    }
+
+    #[test]
+    fn test_chars() {
+        assert!(chars(b"004100D8").eq(['A', 'Ø']));
+        assert_eq!(chars(b"").count(), 0);
+    }
+}
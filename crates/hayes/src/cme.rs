@@ -0,0 +1,130 @@
+use crate::error::error_codes;
+
+error_codes!(
+    /// `+CME ERROR` codes, from 3GPP TS 27.007 §9.2
+    ///
+    /// Read from both the numeric (`AT+CMEE=1`) and verbose (`AT+CMEE=2`)
+    /// forms. Codes missing from the table, including manufacturer-specific
+    /// ones, are kept as [`CmeError::Other`].
+    CmeError {
+        0 => PhoneFailure, "phone failure",
+        1 => NoConnection, "no connection to phone",
+        2 => LinkReserved, "phone-adaptor link reserved",
+        3 => OperationNotAllowed, "operation not allowed",
+        4 => OperationNotSupported, "operation not supported",
+        5 => PhSimPinRequired, "PH-SIM PIN required",
+        6 => PhFsimPinRequired, "PH-FSIM PIN required",
+        7 => PhFsimPukRequired, "PH-FSIM PUK required",
+        10 => SimNotInserted, "SIM not inserted",
+        11 => SimPinRequired, "SIM PIN required",
+        12 => SimPukRequired, "SIM PUK required",
+        13 => SimFailure, "SIM failure",
+        14 => SimBusy, "SIM busy",
+        15 => SimWrong, "SIM wrong",
+        16 => IncorrectPassword, "incorrect password",
+        17 => SimPin2Required, "SIM PIN2 required",
+        18 => SimPuk2Required, "SIM PUK2 required",
+        20 => MemoryFull, "memory full",
+        21 => InvalidIndex, "invalid index",
+        22 => NotFound, "not found",
+        23 => MemoryFailure, "memory failure",
+        24 => TextTooLong, "text string too long",
+        25 => InvalidTextCharacters, "invalid characters in text string",
+        26 => DialStringTooLong, "dial string too long",
+        27 => InvalidDialCharacters, "invalid characters in dial string",
+        30 => NoNetworkService, "no network service",
+        31 => NetworkTimeout, "network timeout",
+        32 => EmergencyCallsOnly, "network not allowed - emergency calls only",
+        40 => NetworkPinRequired, "network personalization PIN required",
+        41 => NetworkPukRequired, "network personalization PUK required",
+        42 => NetworkSubsetPinRequired, "network subset personalization PIN required",
+        43 => NetworkSubsetPukRequired, "network subset personalization PUK required",
+        44 => ServiceProviderPinRequired, "service provider personalization PIN required",
+        45 => ServiceProviderPukRequired, "service provider personalization PUK required",
+        46 => CorporatePinRequired, "corporate personalization PIN required",
+        47 => CorporatePukRequired, "corporate personalization PUK required",
+        48 => HiddenKeyRequired, "hidden key required",
+        49 => EapMethodNotSupported, "EAP method not supported",
+        50 => IncorrectParameters, "incorrect parameters",
+        51 => CommandDisabled, "command implemented but currently disabled",
+        52 => CommandAborted, "command aborted by user",
+        53 => NotAttachedRestricted, "not attached to network due to MT functionality restrictions",
+        54 => ModemEmergencyOnly, "modem not allowed - MT restricted to emergency calls only",
+        55 => OperationRestricted, "operation not allowed because of MT functionality restrictions",
+        56 => FixedDialNumberOnly, "fixed dial number only allowed - called number is not a fixed dial number",
+        57 => OutOfServiceOtherUsage, "temporarily out of service due to other MT usage",
+        58 => LanguageNotSupported, "language/alphabet not supported",
+        59 => UnexpectedDataValue, "unexpected data value",
+        60 => SystemFailure, "system failure",
+        61 => DataMissing, "data missing",
+        62 => CallBarred, "call barred",
+        63 => MessageWaitingFailure, "message waiting indication subscription failure",
+        100 => Unknown, "unknown",
+        103 => IllegalMs, "illegal MS",
+        106 => IllegalMe, "illegal ME",
+        107 => GprsNotAllowed, "GPRS services not allowed",
+        108 => GprsAndNonGprsNotAllowed, "GPRS services and non-GPRS services not allowed",
+        111 => PlmnNotAllowed, "PLMN not allowed",
+        112 => LocationAreaNotAllowed, "location area not allowed",
+        113 => RoamingNotAllowed, "roaming not allowed in this location area",
+        114 => GprsNotAllowedInPlmn, "GPRS services not allowed in this PLMN",
+        115 => NoSuitableCells, "no suitable cells in location area",
+        122 => Congestion, "congestion",
+        125 => CsgNotAuthorized, "not authorized for this CSG",
+        126 => InsufficientResources, "insufficient resources",
+        127 => MissingApn, "missing or unknown APN",
+        128 => UnknownPdpAddress, "unknown PDP address or PDP type",
+        129 => AuthenticationFailed, "user authentication failed",
+        130 => ActivationRejectedByGateway, "activation rejected by GGSN, Serving GW or PDN GW",
+        131 => ActivationRejected, "activation rejected, unspecified",
+        132 => ServiceOptionNotSupported, "service option not supported",
+        133 => ServiceOptionNotSubscribed, "requested service option not subscribed",
+        134 => ServiceOptionOutOfOrder, "service option temporarily out of order",
+        140 => FeatureNotSupported, "feature not supported",
+        148 => UnspecifiedGprsError, "unspecified GPRS error",
+        149 => PdpAuthenticationFailure, "PDP authentication failure",
+        150 => InvalidMobileClass, "invalid mobile class",
+    }
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(CmeError::from_code(10), CmeError::SimNotInserted);
+        assert_eq!(CmeError::from(148), CmeError::UnspecifiedGprsError);
+        assert_eq!(CmeError::from_code(767), CmeError::Other(767));
+        assert_eq!(CmeError::IncorrectPassword.code(), 16);
+        assert_eq!(u16::from(CmeError::Other(767)), 767);
+
+        for &error in CmeError::ALL {
+            assert_eq!(CmeError::from_code(error.code()), error);
+        }
+    }
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(
+            CmeError::from_text(b" SIM not inserted"),
+            Some(CmeError::SimNotInserted)
+        );
+        assert_eq!(
+            CmeError::from_text(b"Incorrect Password"),
+            Some(CmeError::IncorrectPassword)
+        );
+        assert_eq!(
+            CmeError::from_text(b"network not allowed - emergency calls only"),
+            Some(CmeError::EmergencyCallsOnly)
+        );
+        assert_eq!(CmeError::from_text(b"SIM on fire"), None);
+        assert_eq!(CmeError::from_text(b""), None);
+    }
+
+    #[test]
+    fn test_description() {
+        assert_eq!(CmeError::MemoryFull.description(), Some("memory full"));
+        assert_eq!(CmeError::Other(700).description(), None);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::error::error_codes;
+
+error_codes!(
+    /// `+CMS ERROR` codes, from 3GPP TS 27.005 §3.2.5
+    ///
+    /// Codes 0–127 are network (RP) causes from TS 24.011 and codes 128–255
+    /// are TP-Failure-Cause values from TS 23.040; the common ones of the
+    /// latter are named. Other codes, including manufacturer-specific ones
+    /// from 512, are kept as [`CmsError::Other`].
+    CmsError {
+        128 => TelematicInterworkingNotSupported, "telematic interworking not supported",
+        129 => Type0NotSupported, "short message Type 0 not supported",
+        130 => CannotReplace, "cannot replace short message",
+        143 => UnspecifiedPidError, "unspecified TP-PID error",
+        144 => DcsNotSupported, "data coding scheme (alphabet) not supported",
+        145 => MessageClassNotSupported, "message class not supported",
+        159 => UnspecifiedDcsError, "unspecified TP-DCS error",
+        160 => CommandCannotBeActioned, "command cannot be actioned",
+        161 => CommandUnsupported, "command unsupported",
+        175 => UnspecifiedCommandError, "unspecified TP-Command error",
+        176 => TpduNotSupported, "TPDU not supported",
+        192 => ScBusy, "SC busy",
+        193 => NoScSubscription, "no SC subscription",
+        194 => ScSystemFailure, "SC system failure",
+        195 => InvalidSmeAddress, "invalid SME address",
+        196 => DestinationSmeBarred, "destination SME barred",
+        197 => DuplicateRejected, "SM rejected-duplicate SM",
+        198 => VpfNotSupported, "TP-VPF not supported",
+        199 => VpNotSupported, "TP-VP not supported",
+        208 => SimStorageFull, "(U)SIM SMS storage full",
+        209 => NoSimStorage, "no SMS storage capability in (U)SIM",
+        210 => ErrorInMs, "error in MS",
+        211 => MemoryCapacityExceeded, "memory capacity exceeded",
+        212 => ToolkitBusy, "(U)SIM application toolkit busy",
+        213 => DataDownloadError, "(U)SIM data download error",
+        255 => UnspecifiedFailure, "unspecified error cause",
+        300 => MeFailure, "ME failure",
+        301 => ServiceReserved, "SMS service of ME reserved",
+        302 => OperationNotAllowed, "operation not allowed",
+        303 => OperationNotSupported, "operation not supported",
+        304 => InvalidPduParameter, "invalid PDU mode parameter",
+        305 => InvalidTextParameter, "invalid text mode parameter",
+        310 => SimNotInserted, "(U)SIM not inserted",
+        311 => SimPinRequired, "(U)SIM PIN required",
+        312 => PhSimPinRequired, "PH-(U)SIM PIN required",
+        313 => SimFailure, "(U)SIM failure",
+        314 => SimBusy, "(U)SIM busy",
+        315 => SimWrong, "(U)SIM wrong",
+        316 => SimPukRequired, "(U)SIM PUK required",
+        317 => SimPin2Required, "(U)SIM PIN2 required",
+        318 => SimPuk2Required, "(U)SIM PUK2 required",
+        320 => MemoryFailure, "memory failure",
+        321 => InvalidMemoryIndex, "invalid memory index",
+        322 => MemoryFull, "memory full",
+        330 => SmscAddressUnknown, "SMSC address unknown",
+        331 => NoNetworkService, "no network service",
+        332 => NetworkTimeout, "network timeout",
+        340 => NoAckExpected, "no +CNMA acknowledgement expected",
+        500 => Unknown, "unknown error",
+    }
+);
+
+impl CmsError {
+    /// Whether the error is a network (RP) cause, codes 0–127
+    #[must_use]
+    pub const fn is_network_cause(self) -> bool {
+        self.code() < 128
+    }
+
+    /// Whether the error is a TP-Failure-Cause reported by the network or
+    /// SIM, codes 128–255
+    #[must_use]
+    pub const fn is_tp_failure(self) -> bool {
+        matches!(self.code(), 128..=255)
+    }
+
+    /// Whether the error is manufacturer specific, codes from 512
+    #[must_use]
+    pub const fn is_manufacturer_specific(self) -> bool {
+        self.code() >= 512
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codes() {
+        assert_eq!(CmsError::from_code(321), CmsError::InvalidMemoryIndex);
+        assert_eq!(CmsError::from(0xC0), CmsError::ScBusy);
+        assert_eq!(CmsError::from_code(42), CmsError::Other(42));
+        assert_eq!(CmsError::SmscAddressUnknown.code(), 330);
+
+        for &error in CmsError::ALL {
+            assert_eq!(CmsError::from_code(error.code()), error);
+        }
+    }
+
+    #[test]
+    fn test_ranges() {
+        assert!(CmsError::Other(42).is_network_cause());
+        assert!(CmsError::ScBusy.is_tp_failure());
+        assert!(CmsError::Other(0xE0).is_tp_failure());
+        assert!(!CmsError::MeFailure.is_tp_failure());
+        assert!(CmsError::Other(532).is_manufacturer_specific());
+        assert!(!CmsError::Unknown.is_manufacturer_specific());
+    }
+
+    #[test]
+    fn test_from_text() {
+        assert_eq!(
+            CmsError::from_text(b"SMSC address unknown"),
+            Some(CmsError::SmscAddressUnknown)
+        );
+        assert_eq!(
+            CmsError::from_text(b"(U)SIM not inserted "),
+            Some(CmsError::SimNotInserted)
+        );
+        assert_eq!(CmsError::from_text(b"no credit"), None);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::charset::Charset;
+
+/// Modem state that affects how parameters are encoded
+///
+/// Passed to [`AtReadableWith`](crate::AtReadableWith) and
+/// [`AtWritableWith`](crate::AtWritableWith) so that a single type can be read
+/// and written correctly whatever the modem is configured for. Keep it in sync
+/// with the settings sent to the modem, such as `+CSCS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub struct AtContext {
+    /// TE character set selected with `+CSCS`
+    pub charset: Charset,
+    /// How closely responses must follow V.250
+    pub parse: ParseOptions,
+    /// Report where reading a response failed, as [`HayesError::Located`]
+    ///
+    /// Off by default, so that errors are the plain variants.
+    ///
+    /// [`HayesError::Located`]: crate::HayesError::Located
+    pub locate_errors: bool,
+}
+
+impl AtContext {
+    /// Context for a modem using the given character set
+    #[must_use]
+    pub const fn with_charset(charset: Charset) -> Self {
+        AtContext {
+            charset,
+            parse: ParseOptions::STRICT,
+            locate_errors: false,
+        }
+    }
+
+    /// Context for a modem whose responses are read with the given options
+    #[must_use]
+    pub const fn with_parse_options(parse: ParseOptions) -> Self {
+        AtContext {
+            charset: Charset::Ira,
+            parse,
+            locate_errors: false,
+        }
+    }
+}
+
+/// Deviations from V.250 to accept when reading responses
+///
+/// [`ParseOptions::STRICT`] is the default. [`ParseOptions::TOLERANT`] accepts
+/// everything known to be sent by real modems, and other profiles can be built
+/// from either:
+///
+/// ```rust,ignore
+/// let options = ParseOptions {
+///     field_spaces: true,
+///     ..ParseOptions::STRICT
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ParseOptions {
+    /// Match prefixes such as `+CSQ`, and final result codes such as `OK`,
+    /// ignoring ASCII case
+    pub case_insensitive_prefixes: bool,
+    /// Accept spaces before and after fields, such as `+CSQ: 15, 99`, and
+    /// more than the one space after the colon
+    pub field_spaces: bool,
+    /// Ignore spaces and tabs at the end of lines
+    pub trailing_whitespace: bool,
+    /// Groups of prefixes accepted in place of each other, such as
+    /// [`ParseOptions::REGISTRATION`]
+    pub prefix_aliases: &'static [&'static [&'static str]],
+}
+
+impl ParseOptions {
+    /// Responses exactly as V.250 describes them
+    pub const STRICT: Self = ParseOptions {
+        case_insensitive_prefixes: false,
+        field_spaces: false,
+        trailing_whitespace: false,
+        prefix_aliases: &[],
+    };
+
+    /// Every deviation known to be sent by real modems
+    pub const TOLERANT: Self = ParseOptions {
+        case_insensitive_prefixes: true,
+        field_spaces: true,
+        trailing_whitespace: true,
+        prefix_aliases: &[ParseOptions::REGISTRATION],
+    };
+
+    /// Network registration prefixes, which some modems use in place of the
+    /// one that was asked for
+    pub const REGISTRATION: &'static [&'static str] = &["+CREG", "+CGREG", "+CEREG", "+C5GREG"];
+
+    /// Whether `line` starts with `prefix`, or a prefix accepted in its place,
+    /// followed by a colon
+    ///
+    /// Returns what follows the colon.
+    pub(crate) fn strip_prefix<'a>(&self, line: &'a [u8], prefix: &str) -> Option<&'a [u8]> {
+        let strip = |candidate: &str| {
+            let candidate = candidate.as_bytes();
+            let head = line.get(..candidate.len())?;
+            let matches = if self.case_insensitive_prefixes {
+                head.eq_ignore_ascii_case(candidate)
+            } else {
+                head == candidate
+            };
+
+            if matches {
+                line[candidate.len()..].strip_prefix(b":")
+            } else {
+                None
+            }
+        };
+
+        strip(prefix).or_else(|| {
+            self.prefix_aliases
+                .iter()
+                .find(|group| group.contains(&prefix))?
+                .iter()
+                .find_map(|&alias| strip(alias))
+        })
+    }
+
+    /// Number of spaces at the start of `input` to skip around a field
+    pub(crate) fn spaces(&self, input: &[u8]) -> usize {
+        if self.field_spaces {
+            input.iter().take_while(|&&b| b == b' ').count()
+        } else {
+            0
+        }
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions::STRICT
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_prefix() {
+        let strict = ParseOptions::STRICT;
+        assert_eq!(
+            strict.strip_prefix(b"+CSQ: 15,99", "+CSQ"),
+            Some(&b" 15,99"[..])
+        );
+        assert_eq!(strict.strip_prefix(b"+csq: 15,99", "+CSQ"), None);
+        assert_eq!(strict.strip_prefix(b"+CSQX: 1", "+CSQ"), None);
+        assert_eq!(strict.strip_prefix(b"+CGREG: 0,1", "+CREG"), None);
+
+        let tolerant = ParseOptions::TOLERANT;
+        assert_eq!(
+            tolerant.strip_prefix(b"+csq: 15,99", "+CSQ"),
+            Some(&b" 15,99"[..])
+        );
+        assert_eq!(
+            tolerant.strip_prefix(b"+CGREG: 0,1", "+CREG"),
+            Some(&b" 0,1"[..])
+        );
+        assert_eq!(tolerant.strip_prefix(b"+CGREG: 0,1", "+CSQ"), None);
+    }
+
+    #[test]
+    fn test_spaces() {
+        assert_eq!(ParseOptions::STRICT.spaces(b"  1"), 0);
+        assert_eq!(ParseOptions::TOLERANT.spaces(b"  1"), 2);
+    }
+}
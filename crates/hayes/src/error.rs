@@ -1,5 +1,17 @@
+use core::fmt;
+
+use crate::{CmeError, CmsError, VendorError};
+
 /// Error type for AT command parsing and generation
+///
+/// Errors are returned as the plain variant, such as
+/// [`HayesError::InvalidFormat`], so they can be matched on. Responses read
+/// with [`AtContext::locate_errors`](crate::AtContext::locate_errors) set
+/// instead wrap them in [`HayesError::Located`], carrying an
+/// [`ErrorLocation`] describing where in the input they happened. Use
+/// [`HayesError::unlocated`] before matching on those.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum HayesError {
     /// Buffer does not have sufficient space for the operation
     InsufficientBuffer {
@@ -18,16 +30,209 @@ pub enum HayesError {
     InvalidUtf8,
     /// Unexpected end of input
     UnexpectedEnd,
+    /// Value cannot be represented in the target encoding
+    OutOfRange,
     /// Modem returned an error
     ModemError,
-    /// CME error with code
-    CmeError(u16),
-    /// CMS error with code
-    CmsError(u16),
+    /// A string parameter contains a character that could end it or the
+    /// command line, such as a quote or CR
+    UnsafeCharacter {
+        /// The character
+        byte: u8,
+        /// Offset of the character in the string
+        offset: usize,
+    },
+    /// Modem returned `+CME ERROR`
+    CmeError(CmeError),
+    /// Modem returned `+CMS ERROR`
+    CmsError(CmsError),
+    /// Modem returned an error from a manufacturer-specific family, recognized
+    /// by [`VendorErrors`](crate::VendorErrors)
+    Vendor(VendorError),
+    /// An error while reading input, with where it happened
+    Located {
+        /// What went wrong
+        kind: ErrorKind,
+        /// Where it went wrong
+        location: ErrorLocation,
+    },
+}
+
+/// Errors that can carry an [`ErrorLocation`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ErrorKind {
+    /// [`HayesError::ParseError`]
+    ParseError,
+    /// [`HayesError::InvalidFormat`]
+    InvalidFormat,
+    /// [`HayesError::UnsupportedError`]
+    UnsupportedError,
+    /// [`HayesError::InvalidUtf8`]
+    InvalidUtf8,
+    /// [`HayesError::UnexpectedEnd`]
+    UnexpectedEnd,
+    /// [`HayesError::OutOfRange`]
+    OutOfRange,
+}
+
+impl From<ErrorKind> for HayesError {
+    fn from(kind: ErrorKind) -> Self {
+        match kind {
+            ErrorKind::ParseError => HayesError::ParseError,
+            ErrorKind::InvalidFormat => HayesError::InvalidFormat,
+            ErrorKind::UnsupportedError => HayesError::UnsupportedError,
+            ErrorKind::InvalidUtf8 => HayesError::InvalidUtf8,
+            ErrorKind::UnexpectedEnd => HayesError::UnexpectedEnd,
+            ErrorKind::OutOfRange => HayesError::OutOfRange,
+        }
+    }
+}
+
+/// What the input should have contained where reading failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Expected {
+    /// A decimal digit
+    Digit,
+    /// A hexadecimal digit
+    HexDigit,
+    /// A double quote, opening or closing a string
+    Quote,
+    /// A comma between fields
+    Comma,
+    /// The prefix of an information line, such as `+CSQ:`
+    Prefix,
+    /// A final result code, such as `OK`
+    ResultCode,
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Expected::Digit => "a digit",
+            Expected::HexDigit => "a hex digit",
+            Expected::Quote => "a quote",
+            Expected::Comma => "a comma",
+            Expected::Prefix => "a prefix",
+            Expected::ResultCode => "a result code",
+        })
+    }
+}
+
+/// Where in the input an error happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ErrorLocation {
+    /// Offset of the error in bytes from the start of the input
+    pub offset: usize,
+    /// Index of the field being read, counted from 0
+    pub field: Option<u16>,
+    /// Name of the field being read, for responses that give one
+    pub name: Option<&'static str>,
+    /// What the input should have contained
+    pub expected: Option<Expected>,
+}
+
+impl HayesError {
+    /// The error without its location
+    #[must_use]
+    pub fn unlocated(self) -> Self {
+        match self {
+            HayesError::Located { kind, .. } => kind.into(),
+            error => error,
+        }
+    }
+
+    /// Where the error happened, if known
+    #[must_use]
+    pub const fn location(&self) -> Option<&ErrorLocation> {
+        match self {
+            HayesError::Located { location, .. } => Some(location),
+            _ => None,
+        }
+    }
+
+    /// The error located at the start of the input, or the error itself if it
+    /// can't carry a location
+    fn located(self) -> Self {
+        let kind = match self {
+            HayesError::ParseError => ErrorKind::ParseError,
+            HayesError::InvalidFormat => ErrorKind::InvalidFormat,
+            HayesError::UnsupportedError => ErrorKind::UnsupportedError,
+            HayesError::InvalidUtf8 => ErrorKind::InvalidUtf8,
+            HayesError::UnexpectedEnd => ErrorKind::UnexpectedEnd,
+            HayesError::OutOfRange => ErrorKind::OutOfRange,
+            error => return error,
+        };
+
+        HayesError::Located {
+            kind,
+            location: ErrorLocation {
+                offset: 0,
+                field: None,
+                name: None,
+                expected: None,
+            },
+        }
+    }
+
+    /// Record what the input should have contained
+    ///
+    /// Errors that don't concern the input, such as
+    /// [`HayesError::InsufficientBuffer`], are returned unchanged.
+    #[must_use]
+    pub fn expected(self, expected: Expected) -> Self {
+        match self.located() {
+            HayesError::Located { kind, mut location } => {
+                location.expected = Some(expected);
+                HayesError::Located { kind, location }
+            }
+            error => error,
+        }
+    }
+
+    /// Move the error `offset` bytes further into the input, for an error
+    /// from reading a part of it
+    #[must_use]
+    pub fn at_offset(self, offset: usize) -> Self {
+        match self.located() {
+            HayesError::Located { kind, mut location } => {
+                location.offset += offset;
+                HayesError::Located { kind, location }
+            }
+            error => error,
+        }
+    }
+
+    /// Record the index and name of the field being read, unless already
+    /// known
+    #[must_use]
+    pub fn in_field(self, index: u16, name: Option<&'static str>) -> Self {
+        match self.located() {
+            HayesError::Located { kind, mut location } => {
+                if location.field.is_none() {
+                    location.field = Some(index);
+                    location.name = name;
+                }
+                HayesError::Located { kind, location }
+            }
+            error => error,
+        }
+    }
+
+    /// Display the error with an excerpt of the line of `input` it happened
+    /// in, and a caret under the offending byte
+    ///
+    /// `input` must be the buffer that was read.
+    #[must_use]
+    pub const fn excerpt<'a>(&'a self, input: &'a [u8]) -> Excerpt<'a> {
+        Excerpt { error: self, input }
+    }
 }
 
-impl core::fmt::Display for HayesError {
-    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+impl fmt::Display for HayesError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             HayesError::InsufficientBuffer {
                 required,
@@ -41,11 +246,350 @@ impl core::fmt::Display for HayesError {
             HayesError::UnsupportedError => write!(f, "Unsupported error code"),
             HayesError::InvalidUtf8 => write!(f, "Invalid UTF-8 in string data"),
             HayesError::UnexpectedEnd => write!(f, "Unexpected end of input"),
+            HayesError::OutOfRange => write!(f, "Value out of range"),
             HayesError::ModemError => write!(f, "Modem returned ERROR"),
-            HayesError::CmeError(code) => write!(f, "CME ERROR: {code}"),
-            HayesError::CmsError(code) => write!(f, "CMS ERROR: {code}"),
+            HayesError::UnsafeCharacter { byte, offset } => {
+                write!(
+                    f,
+                    "Unsafe character 0x{byte:02X} at byte {offset} of a string"
+                )
+            }
+            HayesError::CmeError(error) => match error.description() {
+                Some(description) => write!(f, "CME ERROR: {} ({description})", error.code()),
+                None => write!(f, "CME ERROR: {}", error.code()),
+            },
+            HayesError::CmsError(error) => match error.description() {
+                Some(description) => write!(f, "CMS ERROR: {} ({description})", error.code()),
+                None => write!(f, "CMS ERROR: {}", error.code()),
+            },
+            HayesError::Vendor(error) => write!(f, "{} error {}", error.family, error.code),
+            HayesError::Located { kind, location } => {
+                write!(f, "{} at byte {}", HayesError::from(*kind), location.offset)?;
+                if let Some(field) = location.field {
+                    write!(f, ", in field {field}")?;
+                }
+                if let Some(name) = location.name {
+                    write!(f, " ({name})")?;
+                }
+                if let Some(expected) = location.expected {
+                    write!(f, ", expected {expected}")?;
+                }
+                Ok(())
+            }
         }
     }
 }
 
 impl core::error::Error for HayesError {}
+
+/// An error displayed with the line of input it happened in, created by
+/// [`HayesError::excerpt`]
+///
+/// ```text
+/// Failed to parse input at byte 9, in field 1 (ber), expected a digit
+/// +CSQ: 15,x
+///          ^
+/// ```
+///
+/// Bytes that aren't printable ASCII are shown as `.`.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Excerpt<'a> {
+    error: &'a HayesError,
+    input: &'a [u8],
+}
+
+impl fmt::Display for Excerpt<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        let Some(location) = self.error.location() else {
+            return Ok(());
+        };
+
+        let offset = location.offset.min(self.input.len());
+        let is_break = |b: &u8| matches!(b, b'\r' | b'\n');
+        let start = self.input[..offset]
+            .iter()
+            .rposition(is_break)
+            .map_or(0, |pos| pos + 1);
+        let end = self.input[offset..]
+            .iter()
+            .position(is_break)
+            .map_or(self.input.len(), |pos| offset + pos);
+
+        f.write_str("\n")?;
+        for &b in &self.input[start..end] {
+            let c = if b.is_ascii_graphic() || b == b' ' {
+                char::from(b)
+            } else {
+                '.'
+            };
+            write!(f, "{c}")?;
+        }
+        write!(f, "\n{:>width$}", "^", width = offset - start + 1)
+    }
+}
+
+/// Defines an enum of numbered errors or causes with their text, and an
+/// `Other(u16)` variant for codes missing from the table
+macro_rules! error_codes {
+    (
+        $(#[$meta:meta])*
+        $name:ident {
+            $($code:literal => $variant:ident, $text:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+        pub enum $name {
+            $(
+                #[doc = concat!("`", stringify!($code), "`: ", $text)]
+                $variant,
+            )*
+            /// Any other code
+            Other(u16),
+        }
+
+        impl $name {
+            /// Every error in the table, for matching verbose text
+            const ALL: &[$name] = &[$($name::$variant),*];
+
+            /// The error with the numeric `code`
+            #[must_use]
+            pub const fn from_code(code: u16) -> Self {
+                match code {
+                    $($code => $name::$variant,)*
+                    code => $name::Other(code),
+                }
+            }
+
+            /// Numeric code of the error
+            #[must_use]
+            pub const fn code(self) -> u16 {
+                match self {
+                    $($name::$variant => $code,)*
+                    $name::Other(code) => code,
+                }
+            }
+
+            /// Verbose text of the error, or `None` for an error outside the
+            /// table
+            #[must_use]
+            pub const fn description(self) -> Option<&'static str> {
+                match self {
+                    $($name::$variant => Some($text),)*
+                    $name::Other(_) => None,
+                }
+            }
+
+            /// The error with the verbose `text`, ignoring ASCII case and
+            /// surrounding whitespace
+            #[must_use]
+            pub fn from_text(text: &[u8]) -> Option<Self> {
+                let text = text.trim_ascii();
+
+                $name::ALL.iter().copied().find(|error| {
+                    error.description().is_some_and(|description| {
+                        description.as_bytes().eq_ignore_ascii_case(text)
+                    })
+                })
+            }
+        }
+
+        impl From<u16> for $name {
+            fn from(code: u16) -> Self {
+                $name::from_code(code)
+            }
+        }
+
+        impl From<$name> for u16 {
+            fn from(error: $name) -> Self {
+                error.code()
+            }
+        }
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                match self.description() {
+                    Some(description) => f.write_str(description),
+                    None => write!(f, "error {}", self.code()),
+                }
+            }
+        }
+    };
+}
+pub(crate) use error_codes;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AtContext, AtReadable, AtResponse,
+        sms::{CmgsResponse, CpmsQueryResponse},
+    };
+
+    /// Formats into a fixed buffer
+    struct Buffer {
+        bytes: [u8; 128],
+        len: usize,
+    }
+
+    impl Buffer {
+        fn format(args: fmt::Arguments<'_>) -> Self {
+            let mut buffer = Buffer {
+                bytes: [0; 128],
+                len: 0,
+            };
+            fmt::write(&mut buffer, args).unwrap();
+            buffer
+        }
+
+        fn as_str(&self) -> &str {
+            core::str::from_utf8(&self.bytes[..self.len]).unwrap()
+        }
+    }
+
+    impl fmt::Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_location() {
+        let error = HayesError::ParseError
+            .expected(Expected::Digit)
+            .at_offset(3)
+            .in_field(1, Some("rssi"))
+            .in_field(4, None)
+            .at_offset(6);
+        assert_ne!(error, HayesError::ParseError);
+        assert_eq!(error.unlocated(), HayesError::ParseError);
+        assert_eq!(
+            error.location(),
+            Some(&ErrorLocation {
+                offset: 9,
+                field: Some(1),
+                name: Some("rssi"),
+                expected: Some(Expected::Digit),
+            })
+        );
+        assert!(matches!(error.unlocated(), HayesError::ParseError));
+
+        // Errors not about the input stay as they are
+        let error = HayesError::CmeError(CmeError::SimNotInserted).at_offset(4);
+        assert!(matches!(
+            error,
+            HayesError::CmeError(CmeError::SimNotInserted)
+        ));
+        assert_eq!(error.location(), None);
+    }
+
+    #[test]
+    fn test_display() {
+        let error = HayesError::ParseError
+            .expected(Expected::Digit)
+            .at_offset(9)
+            .in_field(1, Some("ber"));
+        assert_eq!(
+            Buffer::format(format_args!("{error}")).as_str(),
+            "Failed to parse input at byte 9, in field 1 (ber), expected a digit"
+        );
+
+        let input = b"\r\n+CSQ: 15,x\r\n\r\nOK\r\n";
+        let error = error.at_offset(2);
+        assert_eq!(
+            Buffer::format(format_args!("{}", error.excerpt(input))).as_str(),
+            "Failed to parse input at byte 11, in field 1 (ber), expected a digit\n\
+             +CSQ: 15,x\n         ^"
+        );
+
+        assert_eq!(
+            Buffer::format(format_args!("{}", HayesError::ModemError.excerpt(input))).as_str(),
+            "Modem returned ERROR"
+        );
+    }
+
+    #[test]
+    fn test_located_reads() {
+        let error = u8::read_located(b"x").unwrap_err();
+        assert_eq!(
+            error.location().and_then(|l| l.expected),
+            Some(Expected::Digit)
+        );
+
+        let error = <&str>::read_located(b"\"abc").unwrap_err();
+        assert_eq!(error.unlocated(), HayesError::UnexpectedEnd);
+        assert_eq!(error.location().map(|l| l.offset), Some(4));
+
+        // A field of a response, located in the whole buffer
+        let context = AtContext {
+            locate_errors: true,
+            ..AtContext::default()
+        };
+        let error = CmgsResponse::read_with(b"\r\n+CMGS: x\r\n\r\nOK\r\n", &context).unwrap_err();
+        assert_eq!(
+            error.location(),
+            Some(&ErrorLocation {
+                offset: 9,
+                field: Some(0),
+                name: Some("mr"),
+                expected: Some(Expected::Digit),
+            })
+        );
+
+        let error = CpmsQueryResponse::read_with(b"\r\n+CPMS: \"SM\",1;2\r\n\r\nOK\r\n", &context)
+            .unwrap_err();
+        assert_eq!(
+            error.location().map(|l| (l.offset, l.expected)),
+            Some((15, Some(Expected::Comma)))
+        );
+
+        let error = CmgsResponse::read_with(b"\r\n+CMGR: 1\r\n\r\nOK\r\n", &context).unwrap_err();
+        assert_eq!(
+            error.location().map(|l| (l.offset, l.expected)),
+            Some((2, Some(Expected::Prefix)))
+        );
+
+        let error = <()>::read_with(b"\r\n+CSQ: 1,2\r\n", &context).unwrap_err();
+        assert_eq!(
+            error.location().map(|l| (l.offset, l.expected)),
+            Some((2, Some(Expected::ResultCode)))
+        );
+        let error = <()>::read_with(b"\r\n", &context).unwrap_err();
+        assert_eq!(error.unlocated(), HayesError::UnexpectedEnd);
+    }
+
+    #[test]
+    fn test_unlocated_reads() {
+        // Without `locate_errors`, errors stay plain variants to match on
+        assert!(matches!(u8::read(b"x"), Err(HayesError::ParseError)));
+        assert!(matches!(
+            <&str>::read(b"\"abc"),
+            Err(HayesError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            CmgsResponse::read(b"\r\n+CMGS: x\r\n\r\nOK\r\n"),
+            Err(HayesError::ParseError)
+        ));
+        assert!(matches!(
+            CpmsQueryResponse::read(b"\r\n+CPMS: \"SM\",1;2\r\n\r\nOK\r\n"),
+            Err(HayesError::InvalidFormat)
+        ));
+        assert!(matches!(
+            CmgsResponse::read(b"\r\n+CMGR: 1\r\n\r\nOK\r\n"),
+            Err(HayesError::InvalidFormat)
+        ));
+        assert!(matches!(
+            <()>::read(b"\r\n"),
+            Err(HayesError::UnexpectedEnd)
+        ));
+    }
+}
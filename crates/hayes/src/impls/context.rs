@@ -0,0 +1,99 @@
+//! Context-aware impls for types whose encoding doesn't depend on the character set
+
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::{
+    ActiveTime, AddressMask, AtContext, AtReadable, AtReadableWith, AtWritable, AtWritableWith,
+    BitString, Formatted, HayesError, Hex, HexBytes, ModemDateTime, PeriodicTau, PhoneNumber, Raw,
+    TimeZone,
+    charset::{Charset, Gsm7Str, Ucs2Str},
+};
+
+macro_rules! impl_ignore_context {
+    (read: $($ty:ty),* $(,)?) => {
+        $(
+            impl<'at> AtReadableWith<'at> for $ty {
+                fn read_with(input: &'at [u8], _context: &AtContext) -> Result<(Self, usize), HayesError> {
+                    <$ty as AtReadable<'at>>::read(input)
+                }
+            }
+        )*
+    };
+    (write: $($ty:ty),* $(,)?) => {
+        $(
+            impl AtWritableWith for $ty {
+                fn write_with(&self, output: &mut [u8], _context: &AtContext) -> Result<usize, HayesError> {
+                    AtWritable::write(self, output)
+                }
+            }
+        )*
+    };
+    ($($ty:ty),* $(,)?) => {
+        impl_ignore_context!(read: $($ty),*);
+        impl_ignore_context!(write: $($ty),*);
+    };
+}
+
+impl_ignore_context!(
+    write: bool, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize
+);
+impl_ignore_context!(IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, AddressMask);
+impl_ignore_context!(TimeZone, ModemDateTime, PeriodicTau, ActiveTime, Charset);
+impl_ignore_context!(Hex<u8>, Hex<u16>, Hex<u32>, Hex<u64>, Hex<u128>, Hex<usize>);
+impl_ignore_context!(read: HexBytes<'at>, PhoneNumber<'at>, Gsm7Str<'at>, Ucs2Str<'at>);
+impl_ignore_context!(write: &str, HexBytes<'_>, PhoneNumber<'_>, Gsm7Str<'_>, Ucs2Str<'_>);
+impl_ignore_context!(write: Formatted<Ipv6Addr>, Formatted<IpAddr>, Formatted<AddressMask>);
+impl_ignore_context!(write: Raw<'_>);
+
+impl<'at, const N: usize> AtReadableWith<'at> for BitString<N> {
+    fn read_with(input: &'at [u8], _context: &AtContext) -> Result<(Self, usize), HayesError> {
+        BitString::read(input)
+    }
+}
+
+impl<const N: usize> AtWritableWith for BitString<N> {
+    fn write_with(&self, output: &mut [u8], _context: &AtContext) -> Result<usize, HayesError> {
+        self.write(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ParseOptions, charset::Text};
+
+    #[test]
+    fn test_read_ignores_context() {
+        let ucs2 = AtContext::with_charset(Charset::Ucs2);
+
+        assert_eq!(u8::read_with(b"42,", &ucs2), Ok((42, 2)));
+        assert_eq!(<&str>::read_with(b"\"0041\"", &ucs2), Ok(("0041", 6)));
+        assert_eq!(Option::<u8>::read_with(b"", &ucs2), Ok((None, 0)));
+
+        let (text, consumed) = Option::<Text>::read_with(b"\"0041\"", &ucs2).unwrap();
+        assert_eq!(consumed, 6);
+        assert!(text.unwrap().chars().eq(['A']));
+    }
+
+    #[test]
+    fn test_read_field_spaces() {
+        let tolerant = AtContext::with_parse_options(ParseOptions::TOLERANT);
+
+        assert_eq!(u8::read_with(b" 42 ,1", &tolerant), Ok((42, 4)));
+        assert_eq!(<&str>::read_with(b" \"SM\",", &tolerant), Ok(("SM", 5)));
+        assert_eq!(bool::read_with(b"1  ", &tolerant), Ok((true, 3)));
+        assert!(u8::read_with(b" 42", &AtContext::default()).is_err());
+    }
+
+    #[test]
+    fn test_write_ignores_context() {
+        let mut buf = [0u8; 16];
+        let gsm = AtContext::with_charset(Charset::Gsm);
+
+        assert_eq!(Hex::new(0xABu8).write_with(&mut buf, &gsm), Ok(2));
+        assert_eq!(&buf[..2], b"AB");
+
+        assert_eq!(Some(Text::new("@")).write_with(&mut buf, &gsm), Ok(3));
+        assert_eq!(&buf[..3], b"\"\x00\"");
+    }
+}
@@ -1,3 +1,5 @@
+pub(crate) mod context;
+pub(crate) mod net;
 pub(crate) mod option;
 pub(crate) mod primitive;
 
@@ -28,3 +30,34 @@ impl core::fmt::Write for BufferWriter<'_> {
         Ok(())
     }
 }
+
+/// Helper struct for measuring formatted output without writing it
+struct LengthCounter(usize);
+
+impl core::fmt::Write for LengthCounter {
+    fn write_str(&mut self, s: &str) -> core::fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+/// Write formatted output to a byte buffer, reporting the space required on failure
+pub(crate) fn write_fmt(
+    output: &mut [u8],
+    args: core::fmt::Arguments<'_>,
+) -> Result<usize, crate::HayesError> {
+    let available = output.len();
+
+    let mut writer = BufferWriter::new(output);
+    if core::fmt::write(&mut writer, args).is_ok() {
+        return Ok(writer.written());
+    }
+
+    let mut counter = LengthCounter(0);
+    core::fmt::write(&mut counter, args).map_err(|_| crate::HayesError::InvalidFormat)?;
+
+    Err(crate::HayesError::InsufficientBuffer {
+        required: counter.0,
+        available,
+    })
+}
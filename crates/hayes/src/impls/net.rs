@@ -0,0 +1,323 @@
+use core::fmt;
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+use crate::{AtReadable, AtWritable, Formatted, HayesError, Ipv6Format, impls::write_fmt};
+
+/// Extract the text of a quoted or bare address parameter
+///
+/// Returns the text and the total number of bytes consumed.
+pub(crate) fn read_text(input: &[u8]) -> Result<(&[u8], usize), HayesError> {
+    if input.is_empty() {
+        return Err(HayesError::UnexpectedEnd);
+    }
+
+    if input[0] == b'"' {
+        let len = input[1..]
+            .iter()
+            .position(|&b| b == b'"')
+            .ok_or(HayesError::UnexpectedEnd)?;
+        return Ok((&input[1..=len], len + 2));
+    }
+
+    let len = input
+        .iter()
+        .position(|&b| matches!(b, b',' | b'\r' | b'\n'))
+        .unwrap_or(input.len());
+    if len == 0 {
+        return Err(HayesError::InvalidFormat);
+    }
+
+    Ok((&input[..len], len))
+}
+
+/// Parse dot-separated decimal octets, requiring exactly `out.len()` of them
+pub(crate) fn parse_octets(text: &[u8], out: &mut [u8]) -> Result<(), HayesError> {
+    let mut parts = text.split(|&b| b == b'.');
+
+    for slot in out.iter_mut() {
+        let part = parts.next().ok_or(HayesError::InvalidFormat)?;
+        let (octet, consumed) = u8::read(part)?;
+        if consumed != part.len() {
+            return Err(HayesError::ParseError);
+        }
+        *slot = octet;
+    }
+
+    if parts.next().is_some() {
+        return Err(HayesError::InvalidFormat);
+    }
+
+    Ok(())
+}
+
+/// Parse an IPv4 address, or an IPv6 address in colon or 27.007 dotted notation
+pub(crate) fn parse_ip(text: &[u8]) -> Result<IpAddr, HayesError> {
+    if text.contains(&b':') {
+        let text = core::str::from_utf8(text).map_err(|_| HayesError::InvalidUtf8)?;
+        return text
+            .parse::<Ipv6Addr>()
+            .map(IpAddr::V6)
+            .map_err(|_| HayesError::ParseError);
+    }
+
+    match text.split(|&b| b == b'.').count() {
+        4 => {
+            let mut octets = [0; 4];
+            parse_octets(text, &mut octets)?;
+            Ok(IpAddr::from(octets))
+        }
+        16 => {
+            let mut octets = [0; 16];
+            parse_octets(text, &mut octets)?;
+            Ok(IpAddr::from(octets))
+        }
+        _ => Err(HayesError::InvalidFormat),
+    }
+}
+
+/// Displays an IPv6 address according to an [`Ipv6Format`]
+pub(crate) struct Ipv6Display(pub(crate) Ipv6Addr, pub(crate) Ipv6Format);
+
+impl fmt::Display for Ipv6Display {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Ipv6Display(addr, format) = self;
+
+        if !format.colon {
+            for (i, octet) in addr.octets().iter().enumerate() {
+                if i > 0 {
+                    f.write_str(".")?;
+                }
+                write!(f, "{octet}")?;
+            }
+            return Ok(());
+        }
+
+        let segments = addr.segments();
+
+        // Longest run of two or more zero groups, first one wins on ties
+        let mut zeros = 0..0;
+        if format.compress_zeros {
+            let mut start = 0;
+            for (i, &segment) in segments.iter().enumerate() {
+                if segment != 0 {
+                    start = i + 1;
+                } else if i + 1 - start > zeros.len() {
+                    zeros = start..i + 1;
+                }
+            }
+            if zeros.len() < 2 {
+                zeros = 0..0;
+            }
+        }
+
+        for (i, segment) in segments.iter().enumerate() {
+            if zeros.contains(&i) {
+                if i == zeros.start {
+                    f.write_str("::")?;
+                }
+                continue;
+            }
+
+            if i > 0 && i != zeros.end {
+                f.write_str(":")?;
+            }
+            if format.leading_zeros {
+                write!(f, "{segment:04X}")?;
+            } else {
+                write!(f, "{segment:X}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl<'at> AtReadable<'at> for IpAddr {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (text, consumed) = read_text(input)?;
+        Ok((parse_ip(text)?, consumed))
+    }
+}
+
+impl<'at> AtReadable<'at> for Ipv4Addr {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        match IpAddr::read(input)? {
+            (IpAddr::V4(addr), consumed) => Ok((addr, consumed)),
+            (IpAddr::V6(_), _) => Err(HayesError::InvalidFormat),
+        }
+    }
+}
+
+impl<'at> AtReadable<'at> for Ipv6Addr {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        match IpAddr::read(input)? {
+            (IpAddr::V6(addr), consumed) => Ok((addr, consumed)),
+            (IpAddr::V4(_), _) => Err(HayesError::InvalidFormat),
+        }
+    }
+}
+
+/// Reads and writes `"<address>",<port>`, as used by socket commands
+impl<'at> AtReadable<'at> for SocketAddr {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (ip, mut consumed) = IpAddr::read(input)?;
+
+        match input.get(consumed) {
+            Some(b',') => consumed += 1,
+            Some(_) => return Err(HayesError::InvalidFormat),
+            None => return Err(HayesError::UnexpectedEnd),
+        }
+
+        let (port, len) = u16::read(&input[consumed..])?;
+        Ok((SocketAddr::new(ip, port), consumed + len))
+    }
+}
+
+impl AtWritable for Ipv4Addr {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        write_fmt(output, format_args!("\"{self}\""))
+    }
+}
+
+/// Writes the default [`Ipv6Format`], dotted decimal. Use
+/// [`Formatted`] for another notation.
+impl AtWritable for Ipv6Addr {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        write_fmt(
+            output,
+            format_args!("\"{}\"", Ipv6Display(*self, Ipv6Format::default())),
+        )
+    }
+}
+
+impl AtWritable for IpAddr {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        match self {
+            IpAddr::V4(addr) => addr.write(output),
+            IpAddr::V6(addr) => addr.write(output),
+        }
+    }
+}
+
+/// Writes IPv6 addresses in [`Ipv6Format::COLON`] notation, as socket
+/// commands such as ESP-AT `+CIPSTART` expect
+impl AtWritable for SocketAddr {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let ip = Formatted::new(self.ip(), Ipv6Format::COLON).write(output)?;
+
+        let rest = &mut output[ip..];
+        if rest.is_empty() {
+            return Err(HayesError::InsufficientBuffer {
+                required: ip + 1,
+                available: ip,
+            });
+        }
+        rest[0] = b',';
+
+        let port = self.port().write(&mut rest[1..]).map_err(|e| match e {
+            HayesError::InsufficientBuffer {
+                required,
+                available,
+            } => HayesError::InsufficientBuffer {
+                required: required + ip + 1,
+                available: available + ip + 1,
+            },
+            e => e,
+        })?;
+
+        Ok(ip + 1 + port)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DOTTED_V6: &[u8] = b"\"32.1.13.184.0.0.0.0.0.0.0.0.0.0.0.1\"";
+
+    #[test]
+    fn test_read_ipv4() {
+        assert_eq!(
+            Ipv4Addr::read(b"\"10.0.0.1\","),
+            Ok((Ipv4Addr::new(10, 0, 0, 1), 10))
+        );
+        assert_eq!(
+            Ipv4Addr::read(b"192.168.4.1\r\n"),
+            Ok((Ipv4Addr::new(192, 168, 4, 1), 11))
+        );
+        assert!(Ipv4Addr::read(b"\"10.0.0.256\"").is_err());
+        assert!(Ipv4Addr::read(b"\"10.0.0\"").is_err());
+        assert!(Ipv4Addr::read(b"\"10.0.0.1").is_err());
+        assert!(Ipv4Addr::read(b"\"2001:db8::1\"").is_err());
+    }
+
+    #[test]
+    fn test_read_ipv6() {
+        let expected = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        assert_eq!(Ipv6Addr::read(b"\"2001:DB8::1\""), Ok((expected, 13)));
+        assert_eq!(
+            Ipv6Addr::read(b"\"2001:0DB8:0000:0000:0000:0000:0000:0001\""),
+            Ok((expected, 41))
+        );
+        assert_eq!(Ipv6Addr::read(DOTTED_V6), Ok((expected, DOTTED_V6.len())));
+        assert!(Ipv6Addr::read(b"\"32.1.13.184\"").is_err());
+    }
+
+    #[test]
+    fn test_read_socket_addr() {
+        assert_eq!(
+            SocketAddr::read(b"\"10.0.0.1\",8080\r\n"),
+            Ok((SocketAddr::new(Ipv4Addr::new(10, 0, 0, 1).into(), 8080), 15))
+        );
+        assert!(SocketAddr::read(b"\"10.0.0.1\"").is_err());
+        assert!(SocketAddr::read(b"\"10.0.0.1\",99999").is_err());
+    }
+
+    #[test]
+    fn test_write_ip() {
+        let mut buf = [0u8; 64];
+
+        assert_eq!(Ipv4Addr::new(10, 0, 0, 1).write(&mut buf), Ok(10));
+        assert_eq!(&buf[..10], b"\"10.0.0.1\"");
+
+        let addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 1, 0, 0, 1);
+        assert_eq!(addr.write(&mut buf), Ok(37));
+        assert_eq!(&buf[..37], b"\"32.1.13.184.0.0.0.0.0.1.0.0.0.0.0.1\"");
+
+        // The same as the default format
+        let mut formatted = [0u8; 64];
+        let format = Ipv6Format::default();
+        assert_eq!(Formatted::new(addr, format).write(&mut formatted), Ok(37));
+        assert_eq!(formatted, buf);
+
+        assert_eq!(
+            Ipv4Addr::new(10, 0, 0, 1).write(&mut buf[..4]),
+            Err(HayesError::InsufficientBuffer {
+                required: 10,
+                available: 4,
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_socket_addr() {
+        let mut buf = [0u8; 32];
+        let addr = SocketAddr::new(Ipv4Addr::new(10, 0, 0, 1).into(), 80);
+
+        assert_eq!(addr.write(&mut buf), Ok(13));
+        assert_eq!(&buf[..13], b"\"10.0.0.1\",80");
+
+        assert_eq!(
+            addr.write(&mut buf[..12]),
+            Err(HayesError::InsufficientBuffer {
+                required: 13,
+                available: 12,
+            })
+        );
+
+        let addr = SocketAddr::new(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1).into(), 443);
+        assert_eq!(addr.write(&mut buf), Ok(17));
+        assert_eq!(&buf[..17], b"\"2001:DB8::1\",443");
+    }
+}
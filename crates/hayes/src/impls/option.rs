@@ -1,19 +1,30 @@
-use crate::{AtReadable, AtWritable, HayesError};
+use crate::{AtContext, AtReadable, AtReadableWith, AtWritable, AtWritableWith, HayesError};
 
+/// Whether the field at the start of `input` is empty, being immediately
+/// followed by a comma or the end of the line
+fn is_empty_field(input: &[u8]) -> bool {
+    matches!(input.first(), None | Some(b',' | b'\r' | b'\n'))
+}
+
+/// An empty field reads as `None`, while a field that isn't valid for `T`
+/// is an error. Use [`Lenient`](crate::Lenient) to read it as `None` instead.
 impl<'at, T: AtReadable<'at>> AtReadable<'at> for Option<T> {
     fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
-        // If the input is empty or starts with a delimiter, treat as None
-        if input.is_empty() {
+        if is_empty_field(input) {
             return Ok((None, 0));
         }
 
-        // Try to parse the inner type
-        match T::read(input) {
-            Ok((value, consumed)) => Ok((Some(value), consumed)),
-            // If parsing fails, treat as None with 0 bytes consumed
-            // This allows the parser to continue with the next field
-            Err(_) => Ok((None, 0)),
+        let (value, consumed) = T::read(input)?;
+        Ok((Some(value), consumed))
+    }
+
+    fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        if is_empty_field(input) {
+            return Ok((None, 0));
         }
+
+        let (value, consumed) = T::read_located(input)?;
+        Ok((Some(value), consumed))
     }
 }
 
@@ -26,6 +37,26 @@ impl<T: AtWritable> AtWritable for Option<T> {
     }
 }
 
+impl<'at, T: AtReadableWith<'at>> AtReadableWith<'at> for Option<T> {
+    fn read_with(input: &'at [u8], context: &AtContext) -> Result<(Self, usize), HayesError> {
+        if is_empty_field(input) {
+            return Ok((None, 0));
+        }
+
+        let (value, consumed) = T::read_with(input, context)?;
+        Ok((Some(value), consumed))
+    }
+}
+
+impl<T: AtWritableWith> AtWritableWith for Option<T> {
+    fn write_with(&self, output: &mut [u8], context: &AtContext) -> Result<usize, HayesError> {
+        match self {
+            Some(value) => value.write_with(output, context),
+            None => Ok(0),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -42,11 +73,16 @@ mod tests {
         // Empty input
         assert_eq!(Option::<u8>::read(b""), Ok((None, 0)));
 
-        // Invalid format (not a number)
-        assert_eq!(Option::<u8>::read(b"abc"), Ok((None, 0)));
-
         // Delimiter at start
         assert_eq!(Option::<u8>::read(b",123"), Ok((None, 0)));
+        assert_eq!(Option::<u8>::read(b"\r\nOK"), Ok((None, 0)));
+    }
+
+    #[test]
+    fn test_read_option_invalid() {
+        assert_eq!(Option::<u8>::read(b"abc"), Err(HayesError::ParseError));
+        assert_eq!(Option::<u8>::read(b"300,1"), Err(HayesError::ParseError));
+        assert_eq!(Option::<&str>::read(b"abc"), Err(HayesError::InvalidFormat));
     }
 
     #[test]
@@ -1,3 +1,4 @@
+use super::impl_read_spaced;
 use crate::{AtReadable, AtWritable, HayesError};
 
 impl<'at> AtReadable<'at> for bool {
@@ -14,6 +15,8 @@ impl<'at> AtReadable<'at> for bool {
     }
 }
 
+impl_read_spaced!(bool);
+
 impl AtWritable for bool {
     fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
         if output.is_empty() {
@@ -1,18 +1,23 @@
-use crate::{AtReadable, AtWritable, HayesError, impls::BufferWriter};
+use super::impl_read_spaced;
+use crate::{AtReadable, AtWritable, HayesError, error::Expected, impls::BufferWriter};
 
 macro_rules! impl_int {
     ($($ty:ty),*) => {
         $(
             impl<'at> AtReadable<'at> for $ty {
                 fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+                    Self::read_located(input).map_err(HayesError::unlocated)
+                }
+
+                fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
                     if input.is_empty() {
-                        return Err(HayesError::UnexpectedEnd);
+                        return Err(HayesError::UnexpectedEnd.expected(Expected::Digit));
                     }
 
                     // Handle optional negative sign
                     let is_negative = input[0] == b'-';
                     if is_negative && input.len() < 2 {
-                        return Err(HayesError::UnexpectedEnd);
+                        return Err(HayesError::UnexpectedEnd.expected(Expected::Digit));
                     }
 
                     let digits = if is_negative { &input[1..] } else { input };
@@ -57,7 +62,9 @@ macro_rules! impl_int {
                     }
 
                     if consumed == 0 {
-                        return Err(HayesError::ParseError);
+                        return Err(HayesError::ParseError
+                            .expected(Expected::Digit)
+                            .at_offset(usize::from(is_negative)));
                     }
 
                     if is_negative {
@@ -94,6 +101,7 @@ macro_rules! impl_int {
 }
 
 impl_int!(i8, i16, i32, i64, i128, isize);
+impl_read_spaced!(i8, i16, i32, i64, i128, isize);
 
 #[cfg(test)]
 mod tests {
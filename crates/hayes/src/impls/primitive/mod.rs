@@ -2,3 +2,33 @@ pub(crate) mod bool;
 pub(crate) mod int;
 pub(crate) mod str;
 pub(crate) mod uint;
+
+/// Implement [`AtReadableWith`](crate::AtReadableWith) for primitives, which
+/// skip the spaces around a field that the context's parse options allow
+macro_rules! impl_read_spaced {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl<'at> $crate::AtReadableWith<'at> for $ty {
+                fn read_with(
+                    input: &'at [u8],
+                    context: &$crate::AtContext,
+                ) -> Result<(Self, usize), $crate::HayesError> {
+                    let leading = context.parse.spaces(input);
+                    let (value, consumed) =
+                        <$ty as $crate::AtReadable<'at>>::read_located(&input[leading..])
+                            .map_err(|error| {
+                                if context.locate_errors {
+                                    error.at_offset(leading)
+                                } else {
+                                    error.unlocated()
+                                }
+                            })?;
+                    let trailing = context.parse.spaces(&input[leading + consumed..]);
+                    Ok((value, leading + consumed + trailing))
+                }
+            }
+        )*
+    };
+}
+
+pub(crate) use impl_read_spaced;
@@ -1,33 +1,47 @@
-use crate::{AtReadable, AtWritable, HayesError};
+use super::impl_read_spaced;
+use crate::{AtReadable, AtWritable, HayesError, at::check_quoted, error::Expected};
 
 impl<'at> AtReadable<'at> for &'at str {
     fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        Self::read_located(input).map_err(HayesError::unlocated)
+    }
+
+    fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
         if input.is_empty() {
-            return Err(HayesError::UnexpectedEnd);
+            return Err(HayesError::UnexpectedEnd.expected(Expected::Quote));
         }
 
         // Expect opening quote
         if input[0] != b'"' {
-            return Err(HayesError::InvalidFormat);
+            return Err(HayesError::InvalidFormat.expected(Expected::Quote));
         }
 
         // Find closing quote
-        let closing_quote_pos = input[1..]
-            .iter()
-            .position(|&b| b == b'"')
-            .ok_or(HayesError::UnexpectedEnd)?;
+        let closing_quote_pos = input[1..].iter().position(|&b| b == b'"').ok_or(
+            HayesError::UnexpectedEnd
+                .expected(Expected::Quote)
+                .at_offset(input.len()),
+        )?;
 
         // Extract string between quotes
         let str_bytes = &input[1..=closing_quote_pos];
-        let s = core::str::from_utf8(str_bytes).map_err(|_| HayesError::InvalidUtf8)?;
+        let s = core::str::from_utf8(str_bytes)
+            .map_err(|e| HayesError::InvalidUtf8.at_offset(1 + e.valid_up_to()))?;
 
         // Return string and total bytes consumed (opening quote + string + closing quote)
         Ok((s, closing_quote_pos + 2))
     }
 }
 
+impl_read_spaced!(&'at str);
+
+/// Written in quotes, rejecting characters that could end the string or the
+/// command line with [`HayesError::UnsafeCharacter`]. Use
+/// [`Raw`](crate::Raw) to write trusted strings as they are.
 impl AtWritable for &str {
     fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        check_quoted(self.as_bytes(), false)?;
+
         let required = self.len() + 2; // +2 for quotes
 
         if output.len() < required {
@@ -67,6 +81,23 @@ mod tests {
         assert_eq!("".write(&mut buf), Ok(2));
         assert_eq!(&buf[..2], b"\"\"");
 
+        // Characters that could inject another command
+        assert_eq!(
+            "internet\"\r\nAT+CFUN=0\r\n".write(&mut buf),
+            Err(HayesError::UnsafeCharacter {
+                byte: b'"',
+                offset: 8
+            })
+        );
+        assert_eq!(
+            "a\x1Bb".write(&mut buf),
+            Err(HayesError::UnsafeCharacter {
+                byte: 0x1B,
+                offset: 1
+            })
+        );
+        assert_eq!("a;b".write(&mut buf), Ok(5));
+
         // Test insufficient buffer
         let mut small_buf = [0u8; 3];
         assert!("hello".write(&mut small_buf).is_err());
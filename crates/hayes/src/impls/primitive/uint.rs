@@ -1,4 +1,5 @@
-use crate::{AtReadable, AtWritable, HayesError, impls::BufferWriter};
+use super::impl_read_spaced;
+use crate::{AtReadable, AtWritable, HayesError, error::Expected, impls::BufferWriter};
 use core::fmt::Write;
 
 macro_rules! impl_uint {
@@ -6,8 +7,12 @@ macro_rules! impl_uint {
         $(
             impl<'at> AtReadable<'at> for $ty {
                 fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+                    Self::read_located(input).map_err(HayesError::unlocated)
+                }
+
+                fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
                     if input.is_empty() {
-                        return Err(HayesError::UnexpectedEnd);
+                        return Err(HayesError::UnexpectedEnd.expected(Expected::Digit));
                     }
 
                     let mut value: $ty = 0;
@@ -28,7 +33,7 @@ macro_rules! impl_uint {
                     }
 
                     if consumed == 0 {
-                        return Err(HayesError::ParseError);
+                        return Err(HayesError::ParseError.expected(Expected::Digit));
                     }
 
                     Ok((value, consumed))
@@ -57,6 +62,7 @@ macro_rules! impl_uint {
 }
 
 impl_uint!(u8, u16, u32, u64, u128, usize);
+impl_read_spaced!(u8, u16, u32, u64, u128, usize);
 
 #[cfg(test)]
 mod tests {
@@ -40,10 +40,34 @@
 #![deny(clippy::cargo, missing_docs)]
 #![warn(clippy::all, clippy::pedantic)]
 
+mod at;
+pub mod ceer;
+pub mod charset;
+mod cme;
+mod cms;
+mod context;
 mod error;
 mod impls;
+pub mod sim;
+pub mod sms;
+pub mod stream;
+mod types;
+pub mod ussd;
+mod vendor;
 
-pub use error::HayesError;
+pub use cme::CmeError;
+pub use cms::CmsError;
+pub use context::{AtContext, ParseOptions};
+pub use error::{ErrorKind, ErrorLocation, Excerpt, Expected, HayesError};
+pub use types::bits::BitString;
+pub use types::datetime::{ModemDateTime, TimeZone};
+pub use types::hex::{Hex, HexBytes, HexBytesIter, HexFormat};
+pub use types::ip::{AddressMask, Formatted, Ipv6Format};
+pub use types::lenient::Lenient;
+pub use types::phone::{PhoneNumber, TypeOfAddress};
+pub use types::raw::Raw;
+pub use types::timer::{ActiveTime, PeriodicTau};
+pub use vendor::{VendorError, VendorErrors};
 
 // #[cfg(feature = "derive")]
 // pub use hayes_derive::{Command, Response};
@@ -64,6 +88,19 @@ pub trait AtReadable<'at>: Sized {
     /// - The data format is invalid
     /// - The data cannot be parsed into the target type
     fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError>;
+
+    /// Read a value from the input buffer, reporting where reading failed
+    ///
+    /// Like [`AtReadable::read`], but errors may be [`HayesError::Located`]
+    /// relative to the start of `input`. Used when reading responses with
+    /// [`AtContext::locate_errors`] set. The default doesn't locate errors.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`AtReadable::read`], possibly located.
+    fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        Self::read(input)
+    }
 }
 
 /// Trait for types that can be written to AT command/response buffers
@@ -80,6 +117,38 @@ pub trait AtWritable {
     fn write(&self, output: &mut [u8]) -> Result<usize, HayesError>;
 }
 
+/// Trait for types whose encoding depends on the modem's configuration
+///
+/// Like [`AtReadable`], but given an [`AtContext`] describing the modem, such as
+/// the character set selected with `+CSCS`. Types that read the same way in any
+/// context simply forward to [`AtReadable::read`].
+pub trait AtReadableWith<'at>: Sized {
+    /// Read a value from the input buffer using the given context
+    ///
+    /// Returns the parsed value and the number of bytes consumed.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the data cannot be parsed into the target type in
+    /// the given context.
+    fn read_with(input: &'at [u8], context: &AtContext) -> Result<(Self, usize), HayesError>;
+}
+
+/// Trait for types whose encoding depends on the modem's configuration
+///
+/// Like [`AtWritable`], but given an [`AtContext`] describing the modem.
+pub trait AtWritableWith {
+    /// Write a value to the output buffer using the given context
+    ///
+    /// Returns the number of bytes written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer doesn't have sufficient space, or the
+    /// value cannot be represented in the given context.
+    fn write_with(&self, output: &mut [u8], context: &AtContext) -> Result<usize, HayesError>;
+}
+
 /// Trait for AT commands
 ///
 /// Types implementing this trait represent AT commands that can be sent to a modem.
@@ -95,6 +164,19 @@ pub trait AtCommand {
     ///
     /// Returns an error if the buffer doesn't have sufficient space.
     fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError>;
+
+    /// Write the command to the output buffer using the given context
+    ///
+    /// Commands with string parameters should override this to encode them in
+    /// the context's character set. The default ignores the context.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the buffer doesn't have sufficient space.
+    fn write_with(&self, buffer: &mut [u8], context: &AtContext) -> Result<usize, HayesError> {
+        let _ = context;
+        self.write(buffer)
+    }
 }
 
 /// Trait for AT responses
@@ -114,4 +196,212 @@ pub trait AtResponse<'at>: Sized {
     /// - The response format is invalid
     /// - The modem returned an error
     fn read(buffer: &'at [u8]) -> Result<Self, HayesError>;
+
+    /// Read a response from the input buffer using the given context
+    ///
+    /// Responses should override this to read with the context's
+    /// [`ParseOptions`], and to decode string fields in its character set.
+    /// The default ignores the context.
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`AtResponse::read`].
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let _ = context;
+        Self::read(buffer)
+    }
 }
+
+/// Check that every exported type implements `defmt::Format`, since a derive
+/// whose bounds can't be met still compiles
+#[cfg(feature = "defmt")]
+const _: fn() = || {
+    use crate::{
+        ceer::{Ceer, CeerResponse, EmmCause, EsmCause, RejectCause},
+        charset::{Charset, Gsm7Str, Text, TextChars, Ucs2Str, gsm7, ucs2},
+        sim::{
+            Crsm, CrsmResponse, Csim, CsimResponse, SimCommand, StatusWord,
+            channel::{Cchc, Ccho, CchoResponse, Cgla, CglaResponse, LogicalChannel},
+            ef::{Alpha, AlphaChars, Digits, Iccid, Imsi, Plmn, PlmnIter, PlmnList, Spn},
+            stk::{
+                CommandDetails, CommandType, ComprehensionIter, ComprehensionTlv, Cusate,
+                CusateResponse, Cusatp, Cusatt, Device, DisplayText, LocalInfo, Menu, MenuItem,
+                MenuItemIter, MenuItems, ProactiveCommand, ResultCode, Stgi, StgiResponse, Stgr,
+                Stin, StkCommand, StkText, StkTextChars, TerminalResponse,
+            },
+            tlv::{Fcp, FileDescriptor, FileStructure, Tlv, TlvIter},
+        },
+        sms::{
+            Cmgd, Cmgf, CmgsResponse, Cmti, Cnmi, Cpms, CpmsQuery, CpmsQueryResponse, CpmsResponse,
+            Csca, CscaQuery, CscaResponse, DeleteFlag, IndicationMode, MessageFormat,
+            MessageIndication, MessageStatus, Storage, StorageUsage,
+            concat::{self, Concatenation, Reassembler, Reference, Segment, Segments},
+            pdu::{
+                self, Address, AddressChars, Alphabet, Dcs, Pdu, SmsDeliver, SmsStatusReport,
+                SmsSubmit, UserData, UserDataChars, ValidityPeriod,
+            },
+            text::{self, Cmgl, CmglEntries, CmglEntry, CmglResponse, Cmgr, CmgrResponse, Cmt},
+        },
+        stream::Junk,
+        ussd::{
+            CbsDcs, Cusd, CusdResponse, CusdResult, UssdChars, UssdEncoding, UssdPresentation,
+            UssdStatus, UssdString,
+        },
+    };
+
+    fn format<T: defmt::Format>() {}
+
+    format::<ActiveTime>();
+    format::<AddressMask>();
+    format::<AtContext>();
+    format::<BitString<8>>();
+    format::<CmeError>();
+    format::<CmsError>();
+    format::<ErrorKind>();
+    format::<ErrorLocation>();
+    format::<Excerpt<'static>>();
+    format::<Expected>();
+    format::<Formatted<AddressMask>>();
+    format::<HayesError>();
+    format::<Hex<u8>>();
+    format::<HexBytes<'static>>();
+    format::<HexBytesIter<'static>>();
+    format::<HexFormat>();
+    format::<Ipv6Format>();
+    format::<Lenient<u8>>();
+    format::<ModemDateTime>();
+    format::<ParseOptions>();
+    format::<PeriodicTau>();
+    format::<PhoneNumber<'static>>();
+    format::<Raw<'static>>();
+    format::<TimeZone>();
+    format::<TypeOfAddress>();
+    format::<VendorError>();
+
+    format::<Ceer>();
+    format::<CeerResponse<'static>>();
+    format::<EmmCause>();
+    format::<EsmCause>();
+    format::<RejectCause>();
+
+    format::<Charset>();
+    format::<gsm7::Chars<HexBytesIter<'static>>>();
+    format::<gsm7::Unpacked<HexBytesIter<'static>>>();
+    format::<Gsm7Str<'static>>();
+    format::<Text<'static>>();
+    format::<TextChars<'static>>();
+    format::<Ucs2Str<'static>>();
+    format::<ucs2::Chars<'static>>();
+    format::<ucs2::Units<'static>>();
+
+    format::<Crsm<'static>>();
+    format::<CrsmResponse<'static>>();
+    format::<Csim<'static>>();
+    format::<CsimResponse<'static>>();
+    format::<SimCommand>();
+    format::<StatusWord>();
+    format::<Cchc>();
+    format::<LogicalChannel<'static, (), 64>>();
+    format::<Ccho<'static>>();
+    format::<CchoResponse>();
+    format::<Cgla<'static>>();
+    format::<CglaResponse<'static>>();
+    format::<Alpha<'static>>();
+    format::<AlphaChars<'static>>();
+    format::<Digits<'static>>();
+    format::<Iccid<'static>>();
+    format::<Imsi<'static>>();
+    format::<Plmn>();
+    format::<PlmnIter<'static>>();
+    format::<PlmnList<'static>>();
+    format::<Spn<'static>>();
+    format::<CommandDetails>();
+    format::<CommandType>();
+    format::<ComprehensionIter<'static>>();
+    format::<ComprehensionTlv<'static>>();
+    format::<Cusate<'static>>();
+    format::<CusateResponse<'static>>();
+    format::<Cusatp<'static>>();
+    format::<Cusatt<'static>>();
+    format::<Device>();
+    format::<DisplayText<'static>>();
+    format::<LocalInfo>();
+    format::<Menu<'static>>();
+    format::<MenuItem<'static>>();
+    format::<MenuItemIter<'static>>();
+    format::<MenuItems<'static>>();
+    format::<ProactiveCommand<'static>>();
+    format::<ResultCode>();
+    format::<Stgi>();
+    format::<StgiResponse<'static>>();
+    format::<Stgr<'static>>();
+    format::<Stin>();
+    format::<StkCommand<'static>>();
+    format::<StkText<'static>>();
+    format::<StkTextChars<'static>>();
+    format::<TerminalResponse<'static>>();
+    format::<Fcp<'static>>();
+    format::<FileDescriptor>();
+    format::<FileStructure>();
+    format::<Tlv<'static>>();
+    format::<TlvIter<'static>>();
+
+    format::<Cmgd>();
+    format::<Cmgf>();
+    format::<CmgsResponse>();
+    format::<Cmti>();
+    format::<Cnmi>();
+    format::<Cpms>();
+    format::<CpmsQuery>();
+    format::<CpmsQueryResponse>();
+    format::<CpmsResponse>();
+    format::<Csca<'static>>();
+    format::<CscaQuery>();
+    format::<CscaResponse<'static>>();
+    format::<DeleteFlag>();
+    format::<IndicationMode>();
+    format::<MessageFormat>();
+    format::<MessageIndication>();
+    format::<MessageStatus>();
+    format::<Storage>();
+    format::<StorageUsage>();
+    format::<Concatenation>();
+    format::<concat::Message<'static>>();
+    format::<Reassembler<1, 2, 3>>();
+    format::<Reference>();
+    format::<Segment<'static>>();
+    format::<Segments<'static>>();
+    format::<Address<'static>>();
+    format::<AddressChars<'static>>();
+    format::<Alphabet>();
+    format::<Dcs>();
+    format::<Pdu<'static>>();
+    format::<SmsDeliver<'static>>();
+    format::<SmsStatusReport<'static>>();
+    format::<SmsSubmit<'static>>();
+    format::<UserData<'static>>();
+    format::<UserDataChars<'static>>();
+    format::<ValidityPeriod>();
+    format::<pdu::Cmgs<'static>>();
+    format::<Cmgl>();
+    format::<CmglEntries<'static>>();
+    format::<CmglEntry<'static>>();
+    format::<CmglResponse<'static>>();
+    format::<Cmgr>();
+    format::<CmgrResponse<'static>>();
+    format::<Cmt<'static>>();
+    format::<text::Cmgs<'static>>();
+    format::<text::Message<'static>>();
+
+    format::<Junk<'static>>();
+
+    format::<CbsDcs>();
+    format::<Cusd<'static>>();
+    format::<CusdResponse<'static>>();
+    format::<CusdResult<'static>>();
+    format::<UssdChars<'static>>();
+    format::<UssdEncoding>();
+    format::<UssdPresentation>();
+    format::<UssdStatus>();
+    format::<UssdString<'static>>();
+};
@@ -0,0 +1,486 @@
+//! Logical channels to applications on the card (3GPP TS 27.007 §8.43–8.45)
+//!
+//! A channel is opened to an application by its AID with [`Ccho`], APDUs are
+//! sent on it with [`Cgla`] and it's closed with [`Cchc`]. [`LogicalChannel`]
+//! drives these commands over a [`Transport`] provided by the application, so
+//! that APDUs can be exchanged without handling the AT commands, the hex
+//! encoding or `GET RESPONSE` chaining.
+
+use crate::{
+    AtCommand, AtContext, AtResponse, HayesError, HexBytes,
+    at::{CommandWriter, Fields, ResponseReader},
+    sim::{StatusWord, read_apdu_response},
+    stream::Transport,
+};
+
+/// Instruction byte of `GET RESPONSE`
+const INS_GET_RESPONSE: u8 = 0xC0;
+
+/// Exchanges APDUs with an application on the card
+pub trait ApduTransport {
+    /// Error returned when the exchange fails
+    type Error;
+
+    /// Send `apdu` and return the response data and status word
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the APDU can't be sent or the response is invalid.
+    /// An error from the card is reported in the status word instead.
+    fn transmit(&mut self, apdu: &[u8]) -> Result<(&[u8], StatusWord), Self::Error>;
+}
+
+/// `AT+CCHO=<dfname>`, open a logical channel to an application
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ccho<'a> {
+    /// Application identifier of the application to select
+    pub aid: HexBytes<'a>,
+}
+
+impl<'a> Ccho<'a> {
+    /// Open a channel to the application identified by `aid`
+    #[must_use]
+    pub const fn new(aid: &'a [u8]) -> Self {
+        Ccho {
+            aid: HexBytes::new(aid),
+        }
+    }
+}
+
+impl AtCommand for Ccho<'_> {
+    type Response<'at> = CchoResponse;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CCHO")?;
+        writer.param(&self.aid.quoted())?;
+        writer.finish()
+    }
+}
+
+/// `<sessionid>`, response to [`Ccho`]
+///
+/// The session ID is given on its own line, but many modems prefix it with
+/// `+CCHO:`, so both are accepted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CchoResponse {
+    /// Session ID of the channel, used with [`Cgla`] and [`Cchc`]
+    pub session: u32,
+}
+
+impl AtResponse<'_> for CchoResponse {
+    fn read(buffer: &[u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &[u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let line = reader.line()?;
+        let mut fields = Fields::after_prefix(line, "+CCHO").unwrap_or(Fields::new(line));
+        let session = fields.next()?;
+        reader.finish()?;
+
+        Ok(CchoResponse { session })
+    }
+}
+
+/// `AT+CGLA=<sessionid>,<length>,<command>`, send an APDU on a logical
+/// channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cgla<'a> {
+    /// Session ID of the channel
+    pub session: u32,
+    /// The APDU to send
+    pub command: HexBytes<'a>,
+}
+
+impl AtCommand for Cgla<'_> {
+    type Response<'at> = CglaResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CGLA")?;
+        writer.param(&self.session)?;
+        writer.param(&(self.command.len() * 2))?;
+        writer.param(&self.command.quoted())?;
+        writer.finish()
+    }
+}
+
+/// `+CGLA: <length>,<response>`, response to [`Cgla`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CglaResponse<'at> {
+    /// Data returned by the card, without the status word
+    pub data: HexBytes<'at>,
+    /// Status of the command
+    pub status: StatusWord,
+}
+
+impl<'at> AtResponse<'at> for CglaResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let (data, status) = read_apdu_response(&mut reader.prefixed("+CGLA")?)?;
+        reader.finish()?;
+
+        Ok(CglaResponse { data, status })
+    }
+}
+
+/// `AT+CCHC=<sessionid>`, close a logical channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cchc {
+    /// Session ID of the channel
+    pub session: u32,
+}
+
+impl AtCommand for Cchc {
+    type Response<'at> = ();
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CCHC")?;
+        writer.param(&self.session)?;
+        writer.finish()
+    }
+}
+
+/// An open logical channel to an application on the card
+///
+/// APDUs are sent with [`ApduTransport::transmit`]. A `61xx` status is
+/// followed by `GET RESPONSE` until all of the data has been read, and a
+/// `6Cxx` status repeats the APDU with the correct length, so the caller only
+/// sees the final status word.
+///
+/// The `N`-byte buffer holds the AT commands and the response data. It needs
+/// room for the APDU, the command carrying it as hex digits, which is about
+/// twice as long, and the whole response.
+///
+/// The channel stays open on the card until it's closed with
+/// [`LogicalChannel::close`]. Channels are a limited resource, so one that's
+/// dropped without being closed can't be reused until the card is reset.
+#[derive(Debug)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LogicalChannel<'t, T, const N: usize> {
+    transport: &'t mut T,
+    session: u32,
+    buffer: [u8; N],
+}
+
+impl<'t, T: Transport, const N: usize> LogicalChannel<'t, T, N> {
+    /// Open a channel to the application identified by `aid`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the exchange fails or the modem couldn't open the
+    /// channel, such as [`HayesError::CmeError`] when the application isn't
+    /// found.
+    pub fn open(transport: &'t mut T, aid: &[u8]) -> Result<Self, T::Error> {
+        let mut buffer = [0; N];
+        let len = Ccho::new(aid).write(&mut buffer)?;
+        let session = CchoResponse::read(transport.exchange(&buffer[..len])?)?.session;
+
+        Ok(LogicalChannel {
+            transport,
+            session,
+            buffer,
+        })
+    }
+
+    /// Session ID of the channel
+    #[must_use]
+    pub const fn session(&self) -> u32 {
+        self.session
+    }
+
+    /// Close the channel
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the exchange fails or the modem couldn't close the
+    /// channel.
+    pub fn close(mut self) -> Result<(), T::Error> {
+        let len = Cchc {
+            session: self.session,
+        }
+        .write(&mut self.buffer)?;
+        <()>::read(self.transport.exchange(&self.buffer[..len])?)?;
+
+        Ok(())
+    }
+
+    /// Copy `bytes` into the buffer at `start`
+    fn load(&mut self, start: usize, bytes: &[u8]) -> Result<(), HayesError> {
+        let available = N.saturating_sub(start);
+        let slot = self.buffer.get_mut(start..start + bytes.len()).ok_or(
+            HayesError::InsufficientBuffer {
+                required: bytes.len(),
+                available,
+            },
+        )?;
+        slot.copy_from_slice(bytes);
+
+        Ok(())
+    }
+
+    /// Send the `len`-byte APDU at `start` in the buffer, replacing it with the
+    /// response data
+    ///
+    /// Returns the length of the data and the status word.
+    fn send(&mut self, start: usize, len: usize) -> Result<(usize, StatusWord), T::Error> {
+        let (apdu, rest) = self.buffer[start..].split_at_mut(len);
+        let command_len = Cgla {
+            session: self.session,
+            command: HexBytes::new(apdu),
+        }
+        .write(rest)?;
+
+        let response = CglaResponse::read(self.transport.exchange(&rest[..command_len])?)?;
+        let data = response.data.decode_into(&mut self.buffer[start..])?;
+
+        Ok((data.len(), response.status))
+    }
+}
+
+/// The APDU without its Le byte, if it ends in one
+///
+/// Only case 2 (header and Le) and case 4 (header, Lc, data and Le) short
+/// APDUs end in Le.
+fn without_le(apdu: &[u8]) -> &[u8] {
+    match apdu {
+        [header @ .., _] if header.len() == 4 => header,
+        [header @ .., _] if header.len() > 4 && header.len() == 5 + usize::from(header[4]) => {
+            header
+        }
+        _ => apdu,
+    }
+}
+
+impl<T: Transport, const N: usize> ApduTransport for LogicalChannel<'_, T, N> {
+    type Error = T::Error;
+
+    fn transmit(&mut self, apdu: &[u8]) -> Result<(&[u8], StatusWord), T::Error> {
+        self.load(0, apdu)?;
+        let (mut len, mut status) = self.send(0, apdu.len())?;
+
+        // Repeat the APDU with the length the card asked for
+        if let Some(le) = status.correct_length() {
+            let command = without_le(apdu);
+            self.load(0, command)?;
+            self.load(command.len(), &[le])?;
+            (len, status) = self.send(0, command.len() + 1)?;
+        }
+
+        // Collect the remaining data, appending it to what's been read so far.
+        // GET RESPONSE only has interindustry classes, so a proprietary class
+        // such as STORE DATA's `8X` keeps just its channel bits.
+        while let Some(available) = status.response_available() {
+            let cla = apdu.first().map_or(0, |cla| cla & 0x7F);
+            self.load(len, &[cla, INS_GET_RESPONSE, 0, 0, available])?;
+            let (more, next) = self.send(len, 5)?;
+            len += more;
+            status = next;
+        }
+
+        Ok((&self.buffer[..len], status))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CmeError;
+
+    /// Expects each command in turn and answers it with the given response
+    struct Script<'s> {
+        steps: &'s [(&'s [u8], &'s [u8])],
+        step: usize,
+    }
+
+    impl<'s> Script<'s> {
+        fn new(steps: &'s [(&'s [u8], &'s [u8])]) -> Self {
+            Script { steps, step: 0 }
+        }
+
+        fn is_done(&self) -> bool {
+            self.step == self.steps.len()
+        }
+    }
+
+    impl Transport for Script<'_> {
+        type Error = HayesError;
+
+        fn exchange(&mut self, command: &[u8]) -> Result<&[u8], HayesError> {
+            let (expected, response) = self.steps[self.step];
+            assert_eq!(command, expected);
+            self.step += 1;
+            Ok(response)
+        }
+    }
+
+    const ISD_R: [u8; 16] = [
+        0xA0, 0x00, 0x00, 0x05, 0x59, 0x10, 0x10, 0xFF, 0xFF, 0xFF, 0xFF, 0x89, 0x00, 0x00, 0x01,
+        0x00,
+    ];
+
+    #[test]
+    fn test_commands() {
+        let mut buf = [0u8; 64];
+
+        let len = Ccho::new(&ISD_R).write(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"AT+CCHO=\"A0000005591010FFFFFFFF8900000100\"\r\n"
+        );
+
+        let command = Cgla {
+            session: 2,
+            command: HexBytes::new(&[0x81, 0xCA, 0x00, 0x5A, 0x00]),
+        };
+        let len = command.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CGLA=2,10,\"81CA005A00\"\r\n");
+
+        let len = Cchc { session: 2 }.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CCHC=2\r\n");
+    }
+
+    #[test]
+    fn test_read_responses() {
+        assert_eq!(
+            CchoResponse::read(b"\r\n+CCHO: 1\r\n\r\nOK\r\n"),
+            Ok(CchoResponse { session: 1 })
+        );
+        assert_eq!(
+            CchoResponse::read(b"\r\n3\r\n\r\nOK\r\n"),
+            Ok(CchoResponse { session: 3 })
+        );
+        assert_eq!(
+            CchoResponse::read(b"\r\n+CME ERROR: 3\r\n"),
+            Err(HayesError::CmeError(CmeError::OperationNotAllowed))
+        );
+
+        let response = CglaResponse::read(b"\r\n+CGLA: 8,\"AABB9000\"\r\n\r\nOK\r\n").unwrap();
+        assert_eq!(response.data.get(1), Some(0xBB));
+        assert_eq!(response.status, StatusWord::OK);
+        assert_eq!(
+            CglaResponse::read(b"\r\n+CGLA: 4,\"AABB9000\"\r\n\r\nOK\r\n"),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_transmit() {
+        let mut script = Script::new(&[
+            (
+                b"AT+CCHO=\"A0000005591010FFFFFFFF8900000100\"\r\n",
+                b"\r\n+CCHO: 1\r\n\r\nOK\r\n",
+            ),
+            (
+                b"AT+CGLA=1,14,\"81E2910000BF3E\"\r\n",
+                b"\r\n+CGLA: 4,\"6104\"\r\n\r\nOK\r\n",
+            ),
+            (
+                b"AT+CGLA=1,10,\"01C0000004\"\r\n",
+                b"\r\n+CGLA: 8,\"01026102\"\r\n\r\nOK\r\n",
+            ),
+            (
+                b"AT+CGLA=1,10,\"01C0000002\"\r\n",
+                b"\r\n+CGLA: 8,\"03049000\"\r\n\r\nOK\r\n",
+            ),
+            (b"AT+CCHC=1\r\n", b"\r\nOK\r\n"),
+        ]);
+
+        let mut channel = LogicalChannel::<_, 64>::open(&mut script, &ISD_R).unwrap();
+        assert_eq!(channel.session(), 1);
+        let (data, status) = channel
+            .transmit(&[0x81, 0xE2, 0x91, 0x00, 0x00, 0xBF, 0x3E])
+            .unwrap();
+        assert_eq!(data, [0x01, 0x02, 0x03, 0x04]);
+        assert_eq!(status, StatusWord::OK);
+        channel.close().unwrap();
+        assert!(script.is_done());
+    }
+
+    #[test]
+    fn test_transmit_wrong_length() {
+        let mut script = Script::new(&[
+            (b"AT+CCHO=\"A000\"\r\n", b"\r\n2\r\n\r\nOK\r\n"),
+            (
+                b"AT+CGLA=2,10,\"02B0000000\"\r\n",
+                b"\r\n+CGLA: 4,\"6C03\"\r\n\r\nOK\r\n",
+            ),
+            (
+                b"AT+CGLA=2,10,\"02B0000003\"\r\n",
+                b"\r\n+CGLA: 10,\"AABBCC9000\"\r\n\r\nOK\r\n",
+            ),
+            (
+                b"AT+CGLA=2,10,\"02B0000100\"\r\n",
+                b"\r\n+CGLA: 4,\"6B00\"\r\n\r\nOK\r\n",
+            ),
+            (
+                b"AT+CGLA=2,8,\"02B00002\"\r\n",
+                b"\r\n+CGLA: 4,\"6C02\"\r\n\r\nOK\r\n",
+            ),
+            (
+                b"AT+CGLA=2,10,\"02B0000202\"\r\n",
+                b"\r\n+CGLA: 8,\"DDEE9000\"\r\n\r\nOK\r\n",
+            ),
+        ]);
+
+        let mut channel = LogicalChannel::<_, 64>::open(&mut script, &[0xA0, 0x00]).unwrap();
+        let (data, status) = channel.transmit(&[0x02, 0xB0, 0x00, 0x00, 0x00]).unwrap();
+        assert_eq!(data, [0xAA, 0xBB, 0xCC]);
+        assert_eq!(status, StatusWord::OK);
+
+        // An error from the card is returned as its status word
+        let (data, status) = channel.transmit(&[0x02, 0xB0, 0x00, 0x01, 0x00]).unwrap();
+        assert!(data.is_empty());
+        assert_eq!(status, StatusWord::WRONG_PARAMETERS);
+
+        // Le is appended to an APDU without one, rather than replacing P2
+        let (data, status) = channel.transmit(&[0x02, 0xB0, 0x00, 0x02]).unwrap();
+        assert_eq!(data, [0xDD, 0xEE]);
+        assert_eq!(status, StatusWord::OK);
+        assert!(script.is_done());
+    }
+
+    #[test]
+    fn test_without_le() {
+        assert_eq!(
+            without_le(&[0x00, 0xB0, 0x00, 0x00]),
+            [0x00, 0xB0, 0x00, 0x00]
+        );
+        assert_eq!(
+            without_le(&[0x00, 0xB0, 0x00, 0x00, 0x10]),
+            [0x00, 0xB0, 0x00, 0x00]
+        );
+        assert_eq!(
+            without_le(&[0x00, 0xA4, 0x04, 0x00, 0x02, 0x3F, 0x00]),
+            [0x00, 0xA4, 0x04, 0x00, 0x02, 0x3F, 0x00]
+        );
+        assert_eq!(
+            without_le(&[0x00, 0xA4, 0x04, 0x00, 0x02, 0x3F, 0x00, 0x00]),
+            [0x00, 0xA4, 0x04, 0x00, 0x02, 0x3F, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_transmit_insufficient_buffer() {
+        let mut script = Script::new(&[(b"AT+CCHO=\"A000\"\r\n", b"\r\n+CCHO: 1\r\n\r\nOK\r\n")]);
+
+        let mut channel = LogicalChannel::<_, 32>::open(&mut script, &[0xA0, 0x00]).unwrap();
+        assert!(matches!(
+            channel.transmit(&[0; 16]),
+            Err(HayesError::InsufficientBuffer { .. })
+        ));
+        assert!(matches!(
+            channel.transmit(&[0; 40]),
+            Err(HayesError::InsufficientBuffer { .. })
+        ));
+    }
+}
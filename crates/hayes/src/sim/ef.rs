@@ -0,0 +1,632 @@
+//! Decoders for common elementary files (3GPP TS 31.102 and ETSI TS 102 221)
+//!
+//! The file identifiers are given as constants for use with
+//! [`Crsm`](super::Crsm). Each decoder borrows the [`HexBytes`] of the file's
+//! contents and decodes them on access.
+
+use core::fmt;
+
+use crate::{
+    AtReadable, AtWritable, HayesError, HexBytes, charset::gsm7, sim::decode_chars,
+    types::hex::HexBytesIter,
+};
+
+/// EF ICCID, the card's identification number
+pub const ICCID: u16 = 0x2FE2;
+/// EF IMSI, the subscriber's identity
+pub const IMSI: u16 = 0x6F07;
+/// EF SPN, the service provider name
+pub const SPN: u16 = 0x6F46;
+/// EF FPLMN, the forbidden PLMNs
+pub const FPLMN: u16 = 0x6F7B;
+/// EF `PLMNwAcT`, the user controlled PLMNs with access technologies
+pub const PLMNWACT: u16 = 0x6F60;
+/// EF `OPLMNwAcT`, the operator controlled PLMNs with access technologies
+pub const OPLMNWACT: u16 = 0x6F61;
+/// EF EHPLMN, the equivalent home PLMNs
+pub const EHPLMN: u16 = 0x6FD9;
+
+/// Digits stored as swapped nibbles, low nibble first, ending at an `F` filler
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Digits<'a> {
+    bytes: HexBytesIter<'a>,
+    pending: Option<u8>,
+}
+
+impl<'a> Digits<'a> {
+    fn new(bytes: HexBytesIter<'a>) -> Self {
+        Digits {
+            bytes,
+            pending: None,
+        }
+    }
+}
+
+impl Iterator for Digits<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        let nibble = if let Some(nibble) = self.pending.take() {
+            nibble
+        } else {
+            let byte = self.bytes.next()?;
+            self.pending = Some(byte >> 4);
+            byte & 0xF
+        };
+
+        if nibble > 9 {
+            self.bytes = HexBytes::new(&[]).iter();
+            self.pending = None;
+            return None;
+        }
+
+        Some(char::from(b'0' + nibble))
+    }
+}
+
+/// Contents of EF ICCID, the card's identification number
+///
+/// Stored as up to 20 digits in swapped nibbles, padded with `F`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Iccid<'a>(HexBytes<'a>);
+
+impl<'a> Iccid<'a> {
+    /// Wrap the contents of EF ICCID
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if the file is empty or a digit is
+    /// out of range before the filler.
+    pub fn new(data: HexBytes<'a>) -> Result<Self, HayesError> {
+        let iccid = Iccid(data);
+        let digits = iccid.digits().count();
+        // Anything after the first filler must be filler too
+        if digits == 0
+            || data
+                .iter()
+                .flat_map(|b| [b & 0xF, b >> 4])
+                .skip(digits)
+                .any(|n| n != 0xF)
+        {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        Ok(iccid)
+    }
+
+    /// Iterate over the digits
+    #[must_use]
+    pub fn digits(&self) -> Digits<'a> {
+        Digits::new(self.0.iter())
+    }
+
+    /// Decode the digits into `buffer`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        decode_chars(self.digits(), buffer)
+    }
+}
+
+/// Contents of EF IMSI, the subscriber's identity
+///
+/// Stored as a length byte followed by the digits in swapped nibbles, the
+/// first nibble giving the parity instead of a digit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Imsi<'a>(HexBytes<'a>);
+
+impl<'a> Imsi<'a> {
+    /// Wrap the contents of EF IMSI
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if the length is invalid or there
+    /// are fewer than 6 digits, such as for an unprovisioned IMSI.
+    pub fn new(data: HexBytes<'a>) -> Result<Self, HayesError> {
+        let len = usize::from(data.get(0).ok_or(HayesError::UnexpectedEnd)?);
+        let digits = data.slice(1..1 + len).ok_or(HayesError::InvalidFormat)?;
+
+        let imsi = Imsi(digits);
+        if imsi.digits().count() < 6 {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        Ok(imsi)
+    }
+
+    /// Iterate over the digits
+    #[must_use]
+    pub fn digits(&self) -> Digits<'a> {
+        let mut digits = Digits::new(self.0.iter());
+        // The low nibble of the first byte is the parity, not a digit
+        if let Some(first) = digits.bytes.next() {
+            digits.pending = Some(first >> 4);
+        }
+
+        digits
+    }
+
+    /// The mobile country code, the first three digits
+    #[must_use]
+    pub fn mcc(&self) -> u16 {
+        self.digits()
+            .take(3)
+            .filter_map(|digit| digit.to_digit(10))
+            .fold(0, |mcc, digit| {
+                mcc * 10 + u16::try_from(digit).unwrap_or_default()
+            })
+    }
+
+    /// Decode the digits into `buffer`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        decode_chars(self.digits(), buffer)
+    }
+}
+
+/// Text coded as an alpha identifier (ETSI TS 102 221 Annex A)
+///
+/// Used for the service provider name, phonebook names and SIM Toolkit alpha
+/// identifiers. The text is either in the GSM 7-bit default alphabet, one
+/// septet per byte, or in one of three UCS2 codings marked by a first byte of
+/// `80`, `81` or `82`. Unused bytes are filled with `FF`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Alpha<'a>(HexBytes<'a>);
+
+impl<'a> Alpha<'a> {
+    /// Wrap an alpha identifier
+    #[must_use]
+    pub const fn new(data: HexBytes<'a>) -> Self {
+        Alpha(data)
+    }
+
+    /// The encoded bytes
+    #[must_use]
+    pub const fn bytes(&self) -> HexBytes<'a> {
+        self.0
+    }
+
+    /// Iterate over the decoded characters
+    #[must_use]
+    pub fn chars(&self) -> AlphaChars<'a> {
+        let data = self.0;
+        let byte = |index| data.get(index).unwrap_or_default();
+        let rest = |start: usize, len: usize| {
+            data.slice(start..(start + len).min(data.len()))
+                .unwrap_or(HexBytes::new(&[]))
+                .iter()
+        };
+
+        AlphaChars(match data.get(0) {
+            Some(0x80) => AlphaRepr::Ucs2(rest(1, data.len())),
+            Some(0x81) => AlphaRepr::Offset {
+                base: u32::from(byte(2)) << 7,
+                bytes: rest(3, usize::from(byte(1))),
+            },
+            Some(0x82) => AlphaRepr::Offset {
+                base: u32::from(u16::from_be_bytes([byte(2), byte(3)])),
+                bytes: rest(4, usize::from(byte(1))),
+            },
+            _ => AlphaRepr::Gsm(gsm7::Chars::new(Unfilled(data.iter()))),
+        })
+    }
+
+    /// Decode the text into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        decode_chars(self.chars(), buffer)
+    }
+}
+
+/// Bytes up to the first `FF` filler
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+struct Unfilled<'a>(HexBytesIter<'a>);
+
+impl Iterator for Unfilled<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        self.0.next().filter(|&b| b != 0xFF)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum AlphaRepr<'a> {
+    Gsm(gsm7::Chars<Unfilled<'a>>),
+    Ucs2(HexBytesIter<'a>),
+    Offset { base: u32, bytes: HexBytesIter<'a> },
+}
+
+/// Iterator over the characters of an [`Alpha`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AlphaChars<'a>(AlphaRepr<'a>);
+
+impl Iterator for AlphaChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match &mut self.0 {
+            AlphaRepr::Gsm(chars) => chars.next(),
+            AlphaRepr::Ucs2(bytes) => {
+                let unit = u16::from_be_bytes([bytes.next()?, bytes.next()?]);
+                if unit == 0xFFFF {
+                    return None;
+                }
+                Some(char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER))
+            }
+            AlphaRepr::Offset { base, bytes } => {
+                let byte = bytes.next()?;
+                if byte & 0x80 == 0 {
+                    return Some(gsm7::decode_septet(byte));
+                }
+                Some(
+                    char::from_u32(*base + u32::from(byte & 0x7F))
+                        .unwrap_or(char::REPLACEMENT_CHARACTER),
+                )
+            }
+        }
+    }
+}
+
+/// Contents of EF SPN, the service provider name
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Spn<'a> {
+    /// Display condition byte
+    pub display_condition: u8,
+    /// The name
+    pub name: Alpha<'a>,
+}
+
+impl<'a> Spn<'a> {
+    /// Decode the contents of EF SPN
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::UnexpectedEnd`] if the file is empty.
+    pub fn new(data: HexBytes<'a>) -> Result<Self, HayesError> {
+        Ok(Spn {
+            display_condition: data.get(0).ok_or(HayesError::UnexpectedEnd)?,
+            name: Alpha::new(data.slice(1..data.len()).unwrap_or(data)),
+        })
+    }
+
+    /// Whether the registered PLMN should be displayed alongside the name when
+    /// on the home network
+    #[must_use]
+    pub const fn show_plmn_at_home(&self) -> bool {
+        self.display_condition & 0b01 != 0
+    }
+
+    /// Whether the name should be displayed when roaming
+    #[must_use]
+    pub const fn show_name_when_roaming(&self) -> bool {
+        self.display_condition & 0b10 == 0
+    }
+}
+
+/// Public land mobile network identity, a country and network code
+///
+/// Read and written in the numeric format of `+COPS`, such as `"310410"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Plmn {
+    /// Mobile country code
+    pub mcc: u16,
+    /// Mobile network code
+    pub mnc: u16,
+    /// Whether the network code has three digits rather than two
+    pub three_digit_mnc: bool,
+}
+
+impl Plmn {
+    /// Decode a PLMN from its three-byte form (3GPP TS 24.008 §10.5.1.3)
+    ///
+    /// Returns `None` for an unused entry (`FFFFFF`) or invalid digits.
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; 3]) -> Option<Self> {
+        let digit = |nibble: u8| (nibble <= 9).then_some(u16::from(nibble));
+
+        let mcc =
+            digit(bytes[0] & 0xF)? * 100 + digit(bytes[0] >> 4)? * 10 + digit(bytes[1] & 0xF)?;
+        let mnc = digit(bytes[2] & 0xF)? * 10 + digit(bytes[2] >> 4)?;
+        let (mnc, three_digit_mnc) = match bytes[1] >> 4 {
+            0xF => (mnc, false),
+            third => (mnc * 10 + digit(third)?, true),
+        };
+
+        Some(Plmn {
+            mcc,
+            mnc,
+            three_digit_mnc,
+        })
+    }
+
+    /// Encode the PLMN in its three-byte form
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; 3] {
+        #[allow(clippy::cast_possible_truncation)]
+        let digit = |value: u16, place: u16| ((value / place) % 10) as u8;
+
+        let (mnc1, mnc2, mnc3) = if self.three_digit_mnc {
+            (
+                digit(self.mnc, 100),
+                digit(self.mnc, 10),
+                digit(self.mnc, 1),
+            )
+        } else {
+            (digit(self.mnc, 10), digit(self.mnc, 1), 0xF)
+        };
+
+        [
+            (digit(self.mcc, 10) << 4) | digit(self.mcc, 100),
+            (mnc3 << 4) | digit(self.mcc, 1),
+            (mnc2 << 4) | mnc1,
+        ]
+    }
+}
+
+impl fmt::Display for Plmn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.three_digit_mnc {
+            write!(f, "{:03}{:03}", self.mcc, self.mnc)
+        } else {
+            write!(f, "{:03}{:02}", self.mcc, self.mnc)
+        }
+    }
+}
+
+impl<'at> AtReadable<'at> for Plmn {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (digits, consumed) = <&str>::read(input)?;
+        if !matches!(digits.len(), 5 | 6) || !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        let (mcc, mnc) = digits.split_at(3);
+        let plmn = Plmn {
+            mcc: mcc.parse().map_err(|_| HayesError::ParseError)?,
+            mnc: mnc.parse().map_err(|_| HayesError::ParseError)?,
+            three_digit_mnc: mnc.len() == 3,
+        };
+
+        Ok((plmn, consumed))
+    }
+}
+
+impl AtWritable for Plmn {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let required = if self.three_digit_mnc { 8 } else { 7 };
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+        if self.mcc > 999 || self.mnc >= if self.three_digit_mnc { 1000 } else { 100 } {
+            return Err(HayesError::OutOfRange);
+        }
+
+        let mut digits = [0u8; 6];
+        let mut value = u32::from(self.mcc) * 1000 + u32::from(self.mnc);
+        for slot in digits.iter_mut().rev() {
+            #[allow(clippy::cast_possible_truncation)]
+            let digit = (value % 10) as u8;
+            *slot = b'0' + digit;
+            value /= 10;
+        }
+        if !self.three_digit_mnc {
+            digits[3] = digits[4];
+            digits[4] = digits[5];
+        }
+
+        output[0] = b'"';
+        output[1..required - 1].copy_from_slice(&digits[..required - 2]);
+        output[required - 1] = b'"';
+
+        Ok(required)
+    }
+}
+
+/// A list of PLMNs, such as the contents of EF FPLMN or EF `PLMNwAcT`
+///
+/// Unused entries are skipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PlmnList<'a> {
+    data: HexBytes<'a>,
+    stride: usize,
+}
+
+impl<'a> PlmnList<'a> {
+    /// A list of three-byte PLMNs, as in EF FPLMN and EF EHPLMN
+    #[must_use]
+    pub const fn new(data: HexBytes<'a>) -> Self {
+        PlmnList { data, stride: 3 }
+    }
+
+    /// A list of PLMNs each followed by two bytes of access technologies, as
+    /// in EF `PLMNwAcT` and EF `OPLMNwAcT`
+    #[must_use]
+    pub const fn with_access_technology(data: HexBytes<'a>) -> Self {
+        PlmnList { data, stride: 5 }
+    }
+
+    /// Iterate over the PLMNs, with their access technologies if present
+    #[must_use]
+    pub fn iter(&self) -> PlmnIter<'a> {
+        PlmnIter {
+            list: *self,
+            index: 0,
+        }
+    }
+}
+
+impl<'a> IntoIterator for &PlmnList<'a> {
+    type Item = (Plmn, Option<u16>);
+    type IntoIter = PlmnIter<'a>;
+
+    fn into_iter(self) -> PlmnIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the entries of a [`PlmnList`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PlmnIter<'a> {
+    list: PlmnList<'a>,
+    index: usize,
+}
+
+impl Iterator for PlmnIter<'_> {
+    type Item = (Plmn, Option<u16>);
+
+    fn next(&mut self) -> Option<(Plmn, Option<u16>)> {
+        let PlmnList { data, stride } = self.list;
+        loop {
+            let start = self.index * stride;
+            let entry = data.slice(start..start + stride)?;
+            self.index += 1;
+
+            let byte = |index| entry.get(index).unwrap_or_default();
+            let Some(plmn) = Plmn::from_bytes([byte(0), byte(1), byte(2)]) else {
+                continue;
+            };
+            let access_technology = (stride == 5).then(|| u16::from_be_bytes([byte(3), byte(4)]));
+
+            return Some((plmn, access_technology));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_iccid() {
+        let mut buf = [0u8; 20];
+        let iccid = Iccid::new(HexBytes::from_hex(b"98101430121181157002").unwrap()).unwrap();
+        assert_eq!(iccid.decode_into(&mut buf), Ok("89014103211118510720"));
+
+        let iccid = Iccid::new(HexBytes::from_hex(b"9810143012118115F0FF").unwrap()).unwrap();
+        assert_eq!(iccid.decode_into(&mut buf), Ok("89014103211118510"));
+        assert_eq!(iccid.digits().count(), 17);
+
+        assert_eq!(
+            Iccid::new(HexBytes::from_hex(b"98101430121181F57002").unwrap()),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            Iccid::new(HexBytes::from_hex(b"FFFF").unwrap()),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_imsi() {
+        let mut buf = [0u8; 16];
+        let imsi = Imsi::new(HexBytes::from_hex(b"080910101032540698").unwrap()).unwrap();
+        assert_eq!(imsi.decode_into(&mut buf), Ok("001010123456089"));
+        assert_eq!(imsi.mcc(), 1);
+
+        let imsi = Imsi::new(HexBytes::from_hex(b"0849032143658709F6").unwrap()).unwrap();
+        assert_eq!(imsi.decode_into(&mut buf), Ok("43012345678906"));
+        assert_eq!(imsi.mcc(), 430);
+
+        assert_eq!(
+            Imsi::new(HexBytes::from_hex(b"FFFFFFFFFFFFFFFFFF").unwrap()),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_alpha() {
+        let mut buf = [0u8; 32];
+
+        let spn =
+            Spn::new(HexBytes::from_hex(b"01566F6461666F6E65FFFFFFFFFFFFFFFF").unwrap()).unwrap();
+        assert_eq!(spn.name.decode_into(&mut buf), Ok("Vodafone"));
+        assert!(spn.show_plmn_at_home());
+        assert!(spn.show_name_when_roaming());
+
+        let alpha = Alpha::new(HexBytes::from_hex(b"80041F0430FFFF").unwrap());
+        assert_eq!(alpha.decode_into(&mut buf), Ok("Па"));
+
+        let alpha = Alpha::new(HexBytes::from_hex(b"8103089FB021FF").unwrap());
+        assert_eq!(alpha.decode_into(&mut buf), Ok("Па!"));
+
+        let alpha = Alpha::new(HexBytes::from_hex(b"820204009FB0").unwrap());
+        assert_eq!(alpha.decode_into(&mut buf), Ok("Па"));
+    }
+
+    #[test]
+    fn test_plmn() {
+        let list = PlmnList::new(HexBytes::from_hex(b"130014FFFFFF62F210").unwrap());
+        let mut plmns = list.iter();
+        let (plmn, access_technology) = plmns.next().unwrap();
+        assert_eq!(
+            plmn,
+            Plmn {
+                mcc: 310,
+                mnc: 410,
+                three_digit_mnc: true,
+            }
+        );
+        assert_eq!(access_technology, None);
+        assert_eq!(plmn.to_bytes(), [0x13, 0x00, 0x14]);
+
+        let (plmn, _) = plmns.next().unwrap();
+        assert_eq!((plmn.mcc, plmn.mnc, plmn.three_digit_mnc), (262, 1, false));
+        assert_eq!(plmn.to_bytes(), [0x62, 0xF2, 0x10]);
+        assert!(plmns.next().is_none());
+
+        let list =
+            PlmnList::with_access_technology(HexBytes::from_hex(b"62F2104000FFFFFF0000").unwrap());
+        let mut plmns = list.iter();
+        let (plmn, access_technology) = plmns.next().unwrap();
+        assert_eq!((plmn.mcc, plmn.mnc), (262, 1));
+        assert_eq!(access_technology, Some(0x4000));
+        assert!(plmns.next().is_none());
+    }
+
+    #[test]
+    fn test_plmn_read_write() {
+        let mut buf = [0u8; 8];
+        let plmn = Plmn {
+            mcc: 310,
+            mnc: 410,
+            three_digit_mnc: true,
+        };
+        assert_eq!(plmn.write(&mut buf), Ok(8));
+        assert_eq!(&buf, b"\"310410\"");
+        assert_eq!(Plmn::read(&buf), Ok((plmn, 8)));
+
+        let plmn = Plmn {
+            mcc: 262,
+            mnc: 1,
+            three_digit_mnc: false,
+        };
+        assert_eq!(plmn.write(&mut buf), Ok(7));
+        assert_eq!(&buf[..7], b"\"26201\"");
+        assert_eq!(Plmn::read(&buf[..7]), Ok((plmn, 7)));
+
+        assert_eq!(Plmn::read(b"\"3104\""), Err(HayesError::InvalidFormat));
+    }
+}
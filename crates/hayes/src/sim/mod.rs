@@ -0,0 +1,468 @@
+//! SIM access (3GPP TS 27.007 §8.17–8.18)
+//!
+//! Files on the SIM are read with [`Crsm`], which wraps the common APDUs such
+//! as `READ BINARY`, and any APDU can be sent with [`Csim`]. Both answer with
+//! a [`StatusWord`] and the response data as [`HexBytes`], which can be
+//! decoded with the BER-TLV parser in [`tlv`] and the file decoders in [`ef`].
+//!
+//! Applications on the card, such as an eUICC's ISD-R, are reached through a
+//! logical channel, opened and used with [`channel::LogicalChannel`]. Proactive
+//! commands from SIM Toolkit applications are decoded and answered with
+//! [`stk`].
+
+pub mod channel;
+pub mod ef;
+pub mod stk;
+pub mod tlv;
+
+use core::fmt;
+
+use crate::{
+    AtCommand, AtContext, AtResponse, AtWritable, HayesError, HexBytes,
+    at::{CommandWriter, Fields, ResponseReader},
+};
+
+/// Status word returned by the SIM, `SW1` and `SW2` (ISO/IEC 7816-4 §5.1.3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StatusWord(pub u16);
+
+impl StatusWord {
+    /// Normal ending of the command (`9000`)
+    pub const OK: Self = StatusWord(0x9000);
+    /// Wrong length (`6700`)
+    pub const WRONG_LENGTH: Self = StatusWord(0x6700);
+    /// Security status not satisfied, such as a PIN being required (`6982`)
+    pub const SECURITY_NOT_SATISFIED: Self = StatusWord(0x6982);
+    /// Conditions of use not satisfied (`6985`)
+    pub const CONDITIONS_NOT_SATISFIED: Self = StatusWord(0x6985);
+    /// Incorrect parameters in the data field (`6A80`)
+    pub const INCORRECT_DATA: Self = StatusWord(0x6A80);
+    /// File or application not found (`6A82`)
+    pub const FILE_NOT_FOUND: Self = StatusWord(0x6A82);
+    /// Record not found (`6A83`)
+    pub const RECORD_NOT_FOUND: Self = StatusWord(0x6A83);
+    /// Incorrect parameters `P1` or `P2` (`6B00`)
+    pub const WRONG_PARAMETERS: Self = StatusWord(0x6B00);
+    /// Instruction code not supported (`6D00`)
+    pub const INS_NOT_SUPPORTED: Self = StatusWord(0x6D00);
+    /// Class not supported (`6E00`)
+    pub const CLA_NOT_SUPPORTED: Self = StatusWord(0x6E00);
+
+    /// Combine `SW1` and `SW2`
+    #[must_use]
+    pub const fn new(sw1: u8, sw2: u8) -> Self {
+        StatusWord(u16::from_be_bytes([sw1, sw2]))
+    }
+
+    /// The first byte
+    #[must_use]
+    pub const fn sw1(&self) -> u8 {
+        self.0.to_be_bytes()[0]
+    }
+
+    /// The second byte
+    #[must_use]
+    pub const fn sw2(&self) -> u8 {
+        self.0.to_be_bytes()[1]
+    }
+
+    /// Whether the command completed, possibly with a proactive command
+    /// pending (`91xx`) or after retrying (`92xx`)
+    #[must_use]
+    pub const fn is_success(&self) -> bool {
+        matches!(self.sw1(), 0x90..=0x92)
+    }
+
+    /// The number of response bytes available with `GET RESPONSE`, for `61xx`
+    /// and `9Fxx`
+    #[must_use]
+    pub const fn response_available(&self) -> Option<u8> {
+        match self.sw1() {
+            0x61 | 0x9F => Some(self.sw2()),
+            _ => None,
+        }
+    }
+
+    /// The length the command should be repeated with, for `6Cxx`
+    #[must_use]
+    pub const fn correct_length(&self) -> Option<u8> {
+        match self.sw1() {
+            0x6C => Some(self.sw2()),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StatusWord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04X}", self.0)
+    }
+}
+
+/// Command for [`Crsm`], the `<command>` of `+CRSM`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SimCommand {
+    /// Read bytes from a transparent file
+    ReadBinary = 176,
+    /// Read a record from a linear fixed or cyclic file
+    ReadRecord = 178,
+    /// Read the file's control parameters
+    GetResponse = 192,
+    /// Write bytes to a transparent file
+    UpdateBinary = 214,
+    /// Write a record to a linear fixed or cyclic file
+    UpdateRecord = 220,
+    /// Read the status of the current directory
+    Status = 242,
+}
+
+impl AtWritable for SimCommand {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        (*self as u8).write(output)
+    }
+}
+
+/// `AT+CRSM=<command>[,<fileid>[,<P1>,<P2>,<P3>[,<data>[,<pathid>]]]]`,
+/// restricted SIM access
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Crsm<'a> {
+    /// The command to send
+    pub command: SimCommand,
+    /// The file to access, such as [`ef::ICCID`]
+    pub file_id: Option<u16>,
+    /// The `P1`, `P2` and `P3` parameters of the command
+    pub parameters: Option<[u8; 3]>,
+    /// Data to write
+    pub data: Option<HexBytes<'a>>,
+    /// Path of the file's directory, such as `3F007FFF`
+    pub path: Option<HexBytes<'a>>,
+}
+
+impl<'a> Crsm<'a> {
+    /// Read `len` bytes of a transparent file from `offset`, or the whole
+    /// file if `len` is 0
+    #[must_use]
+    pub const fn read_binary(file_id: u16, offset: u16, len: u8) -> Self {
+        let offset = offset.to_be_bytes();
+        Crsm {
+            command: SimCommand::ReadBinary,
+            file_id: Some(file_id),
+            parameters: Some([offset[0], offset[1], len]),
+            data: None,
+            path: None,
+        }
+    }
+
+    /// Read record number `record`, counted from 1, of `len` bytes
+    #[must_use]
+    pub const fn read_record(file_id: u16, record: u8, len: u8) -> Self {
+        Crsm {
+            command: SimCommand::ReadRecord,
+            file_id: Some(file_id),
+            // P2 of 4 selects the absolute record number in P1
+            parameters: Some([record, 4, len]),
+            data: None,
+            path: None,
+        }
+    }
+
+    /// Read the control parameters of a file, parsed with
+    /// [`Fcp`](tlv::Fcp)
+    #[must_use]
+    pub const fn get_response(file_id: u16) -> Self {
+        Crsm {
+            command: SimCommand::GetResponse,
+            file_id: Some(file_id),
+            parameters: None,
+            data: None,
+            path: None,
+        }
+    }
+
+    /// Write `data` to a transparent file at `offset`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `data` is longer than 255 bytes.
+    pub fn update_binary(file_id: u16, offset: u16, data: &'a [u8]) -> Result<Self, HayesError> {
+        let len = u8::try_from(data.len()).map_err(|_| HayesError::OutOfRange)?;
+        let offset = offset.to_be_bytes();
+
+        Ok(Crsm {
+            command: SimCommand::UpdateBinary,
+            file_id: Some(file_id),
+            parameters: Some([offset[0], offset[1], len]),
+            data: Some(HexBytes::new(data)),
+            path: None,
+        })
+    }
+
+    /// Access the file in the directory at `path` rather than the current one
+    #[must_use]
+    pub const fn with_path(mut self, path: HexBytes<'a>) -> Self {
+        self.path = Some(path);
+        self
+    }
+}
+
+impl AtCommand for Crsm<'_> {
+    type Response<'at> = CrsmResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CRSM")?;
+        writer.param(&self.command)?;
+        writer.param(&self.file_id)?;
+
+        // Later parameters need P1-P3, which default to 0
+        let needs_parameters = self.data.is_some() || self.path.is_some();
+        match self.parameters {
+            Some(parameters) => {
+                for parameter in parameters {
+                    writer.param(&parameter)?;
+                }
+            }
+            None if needs_parameters => {
+                for _ in 0..3 {
+                    writer.param(&0u8)?;
+                }
+            }
+            None => {}
+        }
+
+        writer.param(&self.data.map(HexBytes::quoted))?;
+        writer.param(&self.path.map(HexBytes::quoted))?;
+        writer.finish()
+    }
+}
+
+/// `+CRSM: <sw1>,<sw2>[,<response>]`, response to [`Crsm`]
+///
+/// An error from the SIM is reported in the status word rather than as an
+/// error result code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CrsmResponse<'at> {
+    /// Status of the command
+    pub status: StatusWord,
+    /// Data returned by the SIM, empty if there is none
+    pub data: HexBytes<'at>,
+}
+
+impl<'at> AtResponse<'at> for CrsmResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let mut fields = reader.prefixed("+CRSM")?;
+        let status = StatusWord::new(fields.next()?, fields.next()?);
+        let data = fields.optional()?.unwrap_or(HexBytes::new(&[]));
+        reader.finish()?;
+
+        Ok(CrsmResponse { status, data })
+    }
+}
+
+/// `AT+CSIM=<length>,<command>`, generic SIM access
+///
+/// Sends an APDU to the SIM as is. Unlike [`Crsm`], the modem doesn't handle
+/// `GET RESPONSE` for the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Csim<'a> {
+    /// The APDU to send
+    pub command: HexBytes<'a>,
+}
+
+impl<'a> Csim<'a> {
+    /// Send `apdu`
+    #[must_use]
+    pub const fn new(apdu: &'a [u8]) -> Self {
+        Csim {
+            command: HexBytes::new(apdu),
+        }
+    }
+}
+
+impl AtCommand for Csim<'_> {
+    type Response<'at> = CsimResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CSIM")?;
+        writer.param(&(self.command.len() * 2))?;
+        writer.param(&self.command.quoted())?;
+        writer.finish()
+    }
+}
+
+/// `+CSIM: <length>,<response>`, response to [`Csim`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CsimResponse<'at> {
+    /// Data returned by the SIM, without the status word
+    pub data: HexBytes<'at>,
+    /// Status of the command
+    pub status: StatusWord,
+}
+
+impl<'at> AtResponse<'at> for CsimResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let (data, status) = read_apdu_response(&mut reader.prefixed("+CSIM")?)?;
+        reader.finish()?;
+
+        Ok(CsimResponse { data, status })
+    }
+}
+
+/// Read `<length>,<response>` and split the status word off the response
+fn read_apdu_response<'at>(
+    fields: &mut Fields<'at>,
+) -> Result<(HexBytes<'at>, StatusWord), HayesError> {
+    let length: usize = fields.next()?;
+    let response: HexBytes<'at> = fields.next()?;
+
+    let len = response.len();
+    if length != len * 2 || len < 2 {
+        return Err(HayesError::InvalidFormat);
+    }
+
+    let (Some(data), Some(sw1), Some(sw2)) = (
+        response.slice(0..len - 2),
+        response.get(len - 2),
+        response.get(len - 1),
+    ) else {
+        return Err(HayesError::InvalidFormat);
+    };
+
+    Ok((data, StatusWord::new(sw1, sw2)))
+}
+
+/// Decode `chars` into `buffer` as UTF-8
+fn decode_chars(
+    chars: impl Iterator<Item = char> + Clone,
+    buffer: &mut [u8],
+) -> Result<&str, HayesError> {
+    let required = chars.clone().map(char::len_utf8).sum();
+    if buffer.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: buffer.len(),
+        });
+    }
+
+    let mut pos = 0;
+    for c in chars {
+        pos += c.encode_utf8(&mut buffer[pos..]).len();
+    }
+
+    core::str::from_utf8(&buffer[..pos]).map_err(|_| HayesError::InvalidUtf8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CmeError;
+
+    #[test]
+    fn test_status_word() {
+        let sw = StatusWord::new(0x6A, 0x82);
+        assert_eq!(sw, StatusWord::FILE_NOT_FOUND);
+        assert_eq!((sw.sw1(), sw.sw2()), (0x6A, 0x82));
+        assert!(!sw.is_success());
+
+        assert!(StatusWord::OK.is_success());
+        assert!(StatusWord(0x9110).is_success());
+        assert_eq!(StatusWord(0x6112).response_available(), Some(0x12));
+        assert_eq!(StatusWord(0x9F20).response_available(), Some(0x20));
+        assert_eq!(StatusWord::OK.response_available(), None);
+        assert_eq!(StatusWord(0x6C0A).correct_length(), Some(10));
+    }
+
+    #[test]
+    fn test_write_crsm() {
+        let mut buf = [0u8; 64];
+
+        let len = Crsm::read_binary(ef::ICCID, 0, 10).write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CRSM=176,12258,0,0,10\r\n");
+
+        let len = Crsm::read_record(0x6F3A, 2, 28).write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CRSM=178,28474,2,4,28\r\n");
+
+        let len = Crsm::get_response(ef::IMSI).write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CRSM=192,28423\r\n");
+
+        let len = Crsm::update_binary(ef::FPLMN, 3, &[0x62, 0xF2, 0x10])
+            .unwrap()
+            .write(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], b"AT+CRSM=214,28539,0,3,3,\"62F210\"\r\n");
+
+        let path = HexBytes::new(&[0x3F, 0x00, 0x7F, 0xFF]);
+        let len = Crsm::get_response(ef::SPN)
+            .with_path(path)
+            .write(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], b"AT+CRSM=192,28486,0,0,0,,\"3F007FFF\"\r\n");
+
+        assert_eq!(
+            Crsm::update_binary(ef::SPN, 0, &[0; 256]),
+            Err(HayesError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_read_crsm_response() {
+        let response =
+            CrsmResponse::read(b"\r\n+CRSM: 144,0,\"98101430121181157002\"\r\n\r\nOK\r\n").unwrap();
+        assert_eq!(response.status, StatusWord::OK);
+        let iccid = ef::Iccid::new(response.data).unwrap();
+        let mut buf = [0u8; 20];
+        assert_eq!(iccid.decode_into(&mut buf), Ok("89014103211118510720"));
+
+        let response = CrsmResponse::read(b"\r\n+CRSM: 106,130\r\n\r\nOK\r\n").unwrap();
+        assert_eq!(response.status, StatusWord::FILE_NOT_FOUND);
+        assert!(response.data.is_empty());
+
+        let response = CrsmResponse::read(b"\r\n+CRSM: 144,0,\"\"\r\n\r\nOK\r\n").unwrap();
+        assert!(response.data.is_empty());
+
+        assert_eq!(
+            CrsmResponse::read(b"\r\n+CME ERROR: 10\r\n"),
+            Err(HayesError::CmeError(CmeError::SimNotInserted))
+        );
+    }
+
+    #[test]
+    fn test_csim() {
+        let mut buf = [0u8; 64];
+        let select = [0x00, 0xA4, 0x00, 0x04, 0x02, 0x2F, 0xE2];
+        let len = Csim::new(&select).write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CSIM=14,\"00A40004022FE2\"\r\n");
+
+        let response = CsimResponse::read(b"\r\n+CSIM: 4,\"6120\"\r\n\r\nOK\r\n").unwrap();
+        assert!(response.data.is_empty());
+        assert_eq!(response.status.response_available(), Some(0x20));
+
+        let response = CsimResponse::read(b"\r\n+CSIM: 8,\"AABB9000\"\r\n\r\nOK\r\n").unwrap();
+        assert_eq!(response.data, HexBytes::new(&[0xAA, 0xBB]).quoted());
+        assert_eq!(response.status, StatusWord::OK);
+
+        assert_eq!(
+            CsimResponse::read(b"\r\n+CSIM: 6,\"9000\"\r\n\r\nOK\r\n"),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            CsimResponse::read(b"\r\n+CSIM: 2,\"90\"\r\n\r\nOK\r\n"),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+}
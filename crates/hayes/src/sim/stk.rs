@@ -0,0 +1,1238 @@
+//! SIM Toolkit proactive commands (ETSI TS 102 223 and 3GPP TS 27.007 §12)
+//!
+//! The card asks the terminal to act, such as to show text or a menu, with a
+//! proactive command. Modems report it either as the command's BER-TLV with
+//! the standard `+CUSATP` result code, read as [`Cusatp`], or through vendor
+//! commands, where `+STIN` announces the type of command and `+STGI` reads its
+//! details.
+//!
+//! [`ProactiveCommand`] decodes the BER-TLV form. Once the command has been
+//! handled, the outcome is encoded as a [`TerminalResponse`] and sent with
+//! [`Cusatt`], and a choice from the main menu is sent with [`Cusate`].
+
+use crate::{
+    AtCommand, AtContext, AtReadable, AtResponse, AtWritable, HayesError, HexBytes,
+    at::{CommandWriter, ResponseReader},
+    charset::{Text, gsm7},
+    sim::{decode_chars, ef::Alpha, tlv::Tlv},
+    sms::pdu::{Alphabet, Dcs},
+    types::hex::HexBytesIter,
+};
+
+/// Tag of the proactive command template
+const PROACTIVE_COMMAND: u32 = 0xD0;
+/// Tag of the menu selection envelope
+const MENU_SELECTION: u8 = 0xD3;
+
+/// Command details
+const TAG_COMMAND_DETAILS: u16 = 0x01;
+/// Device identities
+const TAG_DEVICE_IDENTITIES: u16 = 0x02;
+/// Result
+const TAG_RESULT: u16 = 0x03;
+/// Alpha identifier
+const TAG_ALPHA_IDENTIFIER: u16 = 0x05;
+/// Text string
+const TAG_TEXT_STRING: u16 = 0x0D;
+/// Item
+const TAG_ITEM: u16 = 0x0F;
+/// Item identifier
+const TAG_ITEM_IDENTIFIER: u16 = 0x10;
+/// Help request
+const TAG_HELP_REQUEST: u16 = 0x15;
+/// Immediate response
+const TAG_IMMEDIATE_RESPONSE: u16 = 0x2B;
+
+/// Comprehension required flag of a tag
+const COMPREHENSION_REQUIRED: u8 = 0x80;
+
+/// A COMPREHENSION-TLV data object (ETSI TS 101 220 §7.1.1), the simple
+/// objects within a proactive command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ComprehensionTlv<'a> {
+    tag: u16,
+    comprehension_required: bool,
+    value: HexBytes<'a>,
+}
+
+impl<'a> ComprehensionTlv<'a> {
+    /// Parse the data object at the start of `data`, returning it and the
+    /// number of bytes it takes up
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::UnexpectedEnd`] if the object is truncated, or
+    /// [`HayesError::InvalidFormat`] if its tag or length is malformed.
+    pub fn parse(data: HexBytes<'a>) -> Result<(Self, usize), HayesError> {
+        let byte = |index: usize| data.get(index).ok_or(HayesError::UnexpectedEnd);
+
+        let first = byte(0)?;
+        let (tag, comprehension_required, mut pos) = match first {
+            0x00 | 0x80 | 0xFF => return Err(HayesError::InvalidFormat),
+            // A three-byte tag with the flag in the first bit of the value
+            0x7F => {
+                let tag = u16::from_be_bytes([byte(1)?, byte(2)?]);
+                (tag & 0x7FFF, tag & 0x8000 != 0, 3)
+            }
+            _ => (
+                u16::from(first & !COMPREHENSION_REQUIRED),
+                first & COMPREHENSION_REQUIRED != 0,
+                1,
+            ),
+        };
+
+        let len = match byte(pos)? {
+            len @ 0x00..=0x7F => usize::from(len),
+            0x81 => {
+                pos += 1;
+                usize::from(byte(pos)?)
+            }
+            _ => return Err(HayesError::InvalidFormat),
+        };
+        pos += 1;
+
+        let value = data
+            .slice(pos..pos + len)
+            .ok_or(HayesError::UnexpectedEnd)?;
+
+        Ok((
+            ComprehensionTlv {
+                tag,
+                comprehension_required,
+                value,
+            },
+            pos + len,
+        ))
+    }
+
+    /// The tag, without the comprehension required flag
+    #[must_use]
+    pub const fn tag(&self) -> u16 {
+        self.tag
+    }
+
+    /// Whether the terminal must understand the object to carry out the
+    /// command
+    #[must_use]
+    pub const fn is_comprehension_required(&self) -> bool {
+        self.comprehension_required
+    }
+
+    /// The value
+    #[must_use]
+    pub const fn value(&self) -> HexBytes<'a> {
+        self.value
+    }
+}
+
+/// Iterator over consecutive COMPREHENSION-TLV data objects
+///
+/// A malformed object is returned as an error, after which iteration stops.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ComprehensionIter<'a> {
+    data: HexBytes<'a>,
+    pos: usize,
+}
+
+impl<'a> Iterator for ComprehensionIter<'a> {
+    type Item = Result<ComprehensionTlv<'a>, HayesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.data.slice(self.pos..self.data.len())?;
+        if rest.is_empty() {
+            return None;
+        }
+
+        match ComprehensionTlv::parse(rest) {
+            Ok((tlv, len)) => {
+                self.pos += len;
+                Some(Ok(tlv))
+            }
+            Err(error) => {
+                self.pos = self.data.len();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Type of a proactive command (ETSI TS 102 223 §9.4)
+///
+/// Read and written as a number, as in the `<cmd>` of `+STIN`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandType(pub u8);
+
+impl CommandType {
+    /// REFRESH
+    pub const REFRESH: Self = CommandType(0x01);
+    /// SET UP EVENT LIST
+    pub const SET_UP_EVENT_LIST: Self = CommandType(0x05);
+    /// SET UP CALL
+    pub const SET_UP_CALL: Self = CommandType(0x10);
+    /// SEND SS
+    pub const SEND_SS: Self = CommandType(0x11);
+    /// SEND USSD
+    pub const SEND_USSD: Self = CommandType(0x12);
+    /// SEND SHORT MESSAGE
+    pub const SEND_SHORT_MESSAGE: Self = CommandType(0x13);
+    /// LAUNCH BROWSER
+    pub const LAUNCH_BROWSER: Self = CommandType(0x15);
+    /// DISPLAY TEXT
+    pub const DISPLAY_TEXT: Self = CommandType(0x21);
+    /// GET INKEY
+    pub const GET_INKEY: Self = CommandType(0x22);
+    /// GET INPUT
+    pub const GET_INPUT: Self = CommandType(0x23);
+    /// SELECT ITEM
+    pub const SELECT_ITEM: Self = CommandType(0x24);
+    /// SET UP MENU
+    pub const SET_UP_MENU: Self = CommandType(0x25);
+    /// PROVIDE LOCAL INFORMATION
+    pub const PROVIDE_LOCAL_INFO: Self = CommandType(0x26);
+    /// SET UP IDLE MODE TEXT
+    pub const SET_UP_IDLE_MODE_TEXT: Self = CommandType(0x28);
+    /// OPEN CHANNEL
+    pub const OPEN_CHANNEL: Self = CommandType(0x40);
+}
+
+impl<'at> AtReadable<'at> for CommandType {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (value, consumed) = u8::read(input)?;
+        Ok((CommandType(value), consumed))
+    }
+}
+
+impl AtWritable for CommandType {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        self.0.write(output)
+    }
+}
+
+/// Command details of a proactive command, echoed in its terminal response
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CommandDetails {
+    /// Number identifying the command
+    pub number: u8,
+    /// Type of command
+    pub command_type: CommandType,
+    /// Options specific to the type of command
+    pub qualifier: u8,
+}
+
+/// A source or destination of a proactive command (ETSI TS 102 223 §8.7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Device(pub u8);
+
+impl Device {
+    /// The keypad
+    pub const KEYPAD: Self = Device(0x01);
+    /// The display
+    pub const DISPLAY: Self = Device(0x02);
+    /// The earpiece
+    pub const EARPIECE: Self = Device(0x03);
+    /// The card
+    pub const UICC: Self = Device(0x81);
+    /// The terminal
+    pub const TERMINAL: Self = Device(0x82);
+    /// The network
+    pub const NETWORK: Self = Device(0x83);
+}
+
+/// A proactive command from the card (ETSI TS 102 223 §6.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProactiveCommand<'a> {
+    /// Command details, echoed in the terminal response
+    pub details: CommandDetails,
+    /// Device the command comes from, usually [`Device::UICC`]
+    pub source: Device,
+    /// Device the command is for, such as [`Device::DISPLAY`]
+    pub destination: Device,
+    objects: HexBytes<'a>,
+}
+
+impl<'a> ProactiveCommand<'a> {
+    /// Decode the BER-TLV of a proactive command
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if `data` isn't a proactive
+    /// command or lacks its command details or device identities, or an error
+    /// if it's malformed.
+    pub fn decode(data: HexBytes<'a>) -> Result<Self, HayesError> {
+        let (template, _) = Tlv::parse(data)?;
+        if template.tag() != PROACTIVE_COMMAND {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        let mut command = ProactiveCommand {
+            details: CommandDetails {
+                number: 0,
+                command_type: CommandType(0),
+                qualifier: 0,
+            },
+            source: Device::UICC,
+            destination: Device::TERMINAL,
+            objects: template.value(),
+        };
+
+        let details = command
+            .find(TAG_COMMAND_DETAILS)
+            .ok_or(HayesError::InvalidFormat)?
+            .value();
+        let devices = command
+            .find(TAG_DEVICE_IDENTITIES)
+            .ok_or(HayesError::InvalidFormat)?
+            .value();
+        let byte = |value: HexBytes<'_>, index| value.get(index).ok_or(HayesError::InvalidFormat);
+
+        command.details = CommandDetails {
+            number: byte(details, 0)?,
+            command_type: CommandType(byte(details, 1)?),
+            qualifier: byte(details, 2)?,
+        };
+        command.source = Device(byte(devices, 0)?);
+        command.destination = Device(byte(devices, 1)?);
+
+        Ok(command)
+    }
+
+    /// Iterate over the data objects of the command
+    #[must_use]
+    pub const fn objects(&self) -> ComprehensionIter<'a> {
+        ComprehensionIter {
+            data: self.objects,
+            pos: 0,
+        }
+    }
+
+    /// The first data object with `tag`, for objects without an accessor
+    #[must_use]
+    pub fn find(&self, tag: u16) -> Option<ComprehensionTlv<'a>> {
+        self.objects()
+            .map_while(Result::ok)
+            .find(|tlv| tlv.tag == tag)
+    }
+
+    /// Decode the command's parameters, for the commands supported
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if a mandatory data object is
+    /// missing or malformed. The terminal response should then report
+    /// [`ResultCode::COMMAND_DATA_NOT_UNDERSTOOD`] or
+    /// [`ResultCode::REQUIRED_VALUES_MISSING`].
+    pub fn command(&self) -> Result<StkCommand<'a>, HayesError> {
+        let qualifier = self.details.qualifier;
+        let command = match self.details.command_type {
+            CommandType::DISPLAY_TEXT => StkCommand::DisplayText(DisplayText {
+                text: StkText::decode(
+                    self.find(TAG_TEXT_STRING)
+                        .ok_or(HayesError::InvalidFormat)?
+                        .value(),
+                )?,
+                high_priority: qualifier & 0b1 != 0,
+                wait_for_user: qualifier & 0b1000_0000 != 0,
+                immediate_response: self.find(TAG_IMMEDIATE_RESPONSE).is_some(),
+            }),
+            CommandType::SET_UP_MENU => StkCommand::SetUpMenu(self.menu()?),
+            CommandType::SELECT_ITEM => StkCommand::SelectItem(self.menu()?),
+            CommandType::PROVIDE_LOCAL_INFO => StkCommand::ProvideLocalInfo(LocalInfo(qualifier)),
+            command_type => StkCommand::Other(command_type),
+        };
+
+        Ok(command)
+    }
+
+    fn menu(&self) -> Result<Menu<'a>, HayesError> {
+        let default_item = match self.find(TAG_ITEM_IDENTIFIER) {
+            Some(tlv) => Some(tlv.value().get(0).ok_or(HayesError::InvalidFormat)?),
+            None => None,
+        };
+
+        Ok(Menu {
+            title: self
+                .find(TAG_ALPHA_IDENTIFIER)
+                .map(|tlv| Alpha::new(tlv.value())),
+            items: MenuItems {
+                objects: self.objects,
+            },
+            default_item,
+            help_available: self.details.qualifier & 0b1000_0000 != 0,
+        })
+    }
+}
+
+/// Parameters of a decoded proactive command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum StkCommand<'a> {
+    /// Show text on the display
+    DisplayText(DisplayText<'a>),
+    /// Add a menu to the terminal's menu system, answered with
+    /// [`menu_selection`] when the user picks an item
+    SetUpMenu(Menu<'a>),
+    /// Ask the user to choose an item, answered with the item in the terminal
+    /// response
+    SelectItem(Menu<'a>),
+    /// Report information about the terminal or network, answered with the
+    /// information in the terminal response
+    ProvideLocalInfo(LocalInfo),
+    /// Any other command, which should be answered with
+    /// [`ResultCode::BEYOND_TERMINAL_CAPABILITIES`] if it isn't handled
+    Other(CommandType),
+}
+
+/// Parameters of DISPLAY TEXT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DisplayText<'a> {
+    /// The text to show
+    pub text: StkText<'a>,
+    /// Whether the text should be shown over other information
+    pub high_priority: bool,
+    /// Whether the text should stay until the user clears it, rather than
+    /// for a short time
+    pub wait_for_user: bool,
+    /// Whether the terminal response should be sent straight away, leaving the
+    /// text shown
+    pub immediate_response: bool,
+}
+
+/// Parameters of SET UP MENU and SELECT ITEM
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Menu<'a> {
+    /// The menu's title
+    pub title: Option<Alpha<'a>>,
+    /// The items to choose from
+    pub items: MenuItems<'a>,
+    /// The item to preselect
+    pub default_item: Option<u8>,
+    /// Whether help is available for the items
+    pub help_available: bool,
+}
+
+/// The items of a [`Menu`]
+///
+/// A SET UP MENU without items removes the menu.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MenuItems<'a> {
+    objects: HexBytes<'a>,
+}
+
+impl<'a> MenuItems<'a> {
+    /// Iterate over the items
+    #[must_use]
+    pub const fn iter(&self) -> MenuItemIter<'a> {
+        MenuItemIter(ComprehensionIter {
+            data: self.objects,
+            pos: 0,
+        })
+    }
+}
+
+impl<'a> IntoIterator for &MenuItems<'a> {
+    type Item = MenuItem<'a>;
+    type IntoIter = MenuItemIter<'a>;
+
+    fn into_iter(self) -> MenuItemIter<'a> {
+        self.iter()
+    }
+}
+
+/// An item of a [`Menu`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MenuItem<'a> {
+    /// Identifier of the item, sent back when it's chosen
+    pub id: u8,
+    /// The item's text
+    pub text: Alpha<'a>,
+}
+
+/// Iterator over the items of a [`Menu`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MenuItemIter<'a>(ComprehensionIter<'a>);
+
+impl<'a> Iterator for MenuItemIter<'a> {
+    type Item = MenuItem<'a>;
+
+    fn next(&mut self) -> Option<MenuItem<'a>> {
+        loop {
+            let tlv = self.0.next()?.ok()?;
+            if tlv.tag != TAG_ITEM {
+                continue;
+            }
+
+            // An empty item stands for no items
+            let value = tlv.value();
+            let Some(id) = value.get(0) else {
+                continue;
+            };
+
+            return Some(MenuItem {
+                id,
+                text: Alpha::new(value.slice(1..value.len()).unwrap_or(value)),
+            });
+        }
+    }
+}
+
+/// Information requested by PROVIDE LOCAL INFORMATION (ETSI TS 102 223
+/// §8.6)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct LocalInfo(pub u8);
+
+impl LocalInfo {
+    /// Location information: country, network, area and cell
+    pub const LOCATION: Self = LocalInfo(0x00);
+    /// The terminal's IMEI
+    pub const IMEI: Self = LocalInfo(0x01);
+    /// Network measurement results
+    pub const NETWORK_MEASUREMENT: Self = LocalInfo(0x02);
+    /// Date, time and time zone
+    pub const DATE_TIME: Self = LocalInfo(0x03);
+    /// Language setting
+    pub const LANGUAGE: Self = LocalInfo(0x04);
+    /// Access technology
+    pub const ACCESS_TECHNOLOGY: Self = LocalInfo(0x06);
+    /// The terminal's IMEISV
+    pub const IMEISV: Self = LocalInfo(0x08);
+    /// Network search mode
+    pub const NETWORK_SEARCH_MODE: Self = LocalInfo(0x09);
+    /// Battery state
+    pub const BATTERY: Self = LocalInfo(0x0A);
+}
+
+/// Text string of a proactive command (ETSI TS 102 223 §8.15), coded with a
+/// data coding scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StkText<'a> {
+    /// How the text is coded
+    pub dcs: Dcs,
+    /// The coded text
+    pub data: HexBytes<'a>,
+}
+
+impl<'a> StkText<'a> {
+    /// Decode the value of a text string object
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if the value is empty.
+    pub fn decode(value: HexBytes<'a>) -> Result<Self, HayesError> {
+        Ok(StkText {
+            dcs: Dcs(value.get(0).ok_or(HayesError::InvalidFormat)?),
+            data: value.slice(1..value.len()).unwrap_or(value),
+        })
+    }
+
+    /// Iterate over the decoded characters
+    #[must_use]
+    pub fn chars(&self) -> StkTextChars<'a> {
+        let data = self.data;
+        StkTextChars(match self.dcs.alphabet() {
+            Alphabet::Gsm7 => {
+                let mut count = data.len() * 8 / 7;
+                // Seven spare bits at the end are filled with a carriage return
+                if count > 0
+                    && (data.len() * 8).is_multiple_of(7)
+                    && gsm7::unpacked(data.iter(), 0, count).last() == Some(b'\r')
+                {
+                    count -= 1;
+                }
+
+                StkTextRepr::Packed(gsm7::Chars::new(gsm7::unpacked(data.iter(), 0, count)))
+            }
+            Alphabet::EightBit => StkTextRepr::Unpacked(gsm7::Chars::new(data.iter())),
+            Alphabet::Ucs2 => StkTextRepr::Ucs2(data.iter()),
+        })
+    }
+
+    /// Decode the text into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        decode_chars(self.chars(), buffer)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum StkTextRepr<'a> {
+    Packed(gsm7::Chars<gsm7::Unpacked<HexBytesIter<'a>>>),
+    Unpacked(gsm7::Chars<HexBytesIter<'a>>),
+    Ucs2(HexBytesIter<'a>),
+}
+
+/// Iterator over the characters of a [`StkText`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StkTextChars<'a>(StkTextRepr<'a>);
+
+impl Iterator for StkTextChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match &mut self.0 {
+            StkTextRepr::Packed(chars) => chars.next(),
+            StkTextRepr::Unpacked(chars) => chars.next(),
+            StkTextRepr::Ucs2(bytes) => {
+                let unit = u16::from_be_bytes([bytes.next()?, bytes.next()?]);
+                Some(char::from_u32(u32::from(unit)).unwrap_or(char::REPLACEMENT_CHARACTER))
+            }
+        }
+    }
+}
+
+/// General result of a terminal response (ETSI TS 102 223 §8.12)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ResultCode(pub u8);
+
+impl ResultCode {
+    /// Command performed successfully
+    pub const OK: Self = ResultCode(0x00);
+    /// Command performed with partial comprehension
+    pub const PARTIAL_COMPREHENSION: Self = ResultCode(0x01);
+    /// Command performed, with missing information
+    pub const MISSING_INFORMATION: Self = ResultCode(0x02);
+    /// Proactive session terminated by the user
+    pub const TERMINATED_BY_USER: Self = ResultCode(0x10);
+    /// Backward move requested by the user
+    pub const BACKWARD_MOVE: Self = ResultCode(0x11);
+    /// No response from the user
+    pub const NO_RESPONSE: Self = ResultCode(0x12);
+    /// Help information requested by the user
+    pub const HELP_REQUESTED: Self = ResultCode(0x13);
+    /// Terminal currently unable to process the command, with the cause as
+    /// additional information
+    pub const TERMINAL_BUSY: Self = ResultCode(0x20);
+    /// Command beyond the terminal's capabilities
+    pub const BEYOND_TERMINAL_CAPABILITIES: Self = ResultCode(0x30);
+    /// Command type not understood by the terminal
+    pub const COMMAND_TYPE_NOT_UNDERSTOOD: Self = ResultCode(0x31);
+    /// Command data not understood by the terminal
+    pub const COMMAND_DATA_NOT_UNDERSTOOD: Self = ResultCode(0x32);
+    /// Command number not known by the terminal
+    pub const COMMAND_NUMBER_NOT_KNOWN: Self = ResultCode(0x33);
+    /// Error, required values are missing
+    pub const REQUIRED_VALUES_MISSING: Self = ResultCode(0x36);
+}
+
+/// The terminal's answer to a proactive command (ETSI TS 102 223 §6.8)
+///
+/// Encoded with [`TerminalResponse::encode_into`] and sent with [`Cusatt`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TerminalResponse<'a> {
+    /// Details of the command being answered
+    pub details: CommandDetails,
+    /// Outcome of the command
+    pub result: ResultCode,
+    /// Additional information on the result, such as the cause of
+    /// [`ResultCode::TERMINAL_BUSY`]
+    pub additional_info: Option<u8>,
+    /// The item chosen for SELECT ITEM
+    pub item: Option<u8>,
+    /// Further encoded data objects, such as the information for PROVIDE
+    /// LOCAL INFORMATION
+    pub data: &'a [u8],
+}
+
+impl<'a> TerminalResponse<'a> {
+    /// Answer the command with `details` with `result`
+    #[must_use]
+    pub const fn new(details: CommandDetails, result: ResultCode) -> Self {
+        TerminalResponse {
+            details,
+            result,
+            additional_info: None,
+            item: None,
+            data: &[],
+        }
+    }
+
+    /// Report the chosen item, for SELECT ITEM
+    #[must_use]
+    pub const fn with_item(mut self, item: u8) -> Self {
+        self.item = Some(item);
+        self
+    }
+
+    /// Add encoded data objects after the result
+    #[must_use]
+    pub const fn with_data(mut self, data: &'a [u8]) -> Self {
+        self.data = data;
+        self
+    }
+
+    /// Encode the response into `output` as binary octets
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `output` is too small.
+    pub fn encode_into(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let details = self.details;
+        let result_len = 1 + usize::from(self.additional_info.is_some());
+        let item_len = if self.item.is_some() { 3 } else { 0 };
+        let required = 5 + 4 + 2 + result_len + item_len + self.data.len();
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        let mut pos = 0;
+        let mut push = |bytes: &[u8]| {
+            output[pos..pos + bytes.len()].copy_from_slice(bytes);
+            pos += bytes.len();
+        };
+
+        push(&[
+            tag_byte(TAG_COMMAND_DETAILS),
+            3,
+            details.number,
+            details.command_type.0,
+            details.qualifier,
+        ]);
+        push(&[
+            tag_byte(TAG_DEVICE_IDENTITIES),
+            2,
+            Device::TERMINAL.0,
+            Device::UICC.0,
+        ]);
+        #[allow(clippy::cast_possible_truncation)]
+        push(&[tag_byte(TAG_RESULT), result_len as u8, self.result.0]);
+        if let Some(info) = self.additional_info {
+            push(&[info]);
+        }
+        if let Some(item) = self.item {
+            push(&[tag_byte(TAG_ITEM_IDENTIFIER), 1, item]);
+        }
+        push(self.data);
+
+        Ok(pos)
+    }
+}
+
+/// The single-byte form of `tag`, with comprehension required
+#[allow(clippy::cast_possible_truncation)]
+const fn tag_byte(tag: u16) -> u8 {
+    tag as u8 | COMPREHENSION_REQUIRED
+}
+
+/// Encode a MENU SELECTION envelope into `output`, reporting that the user
+/// chose `item` from the menu set up with SET UP MENU, or asked for help on it
+///
+/// Returns the number of bytes written, to be sent with [`Cusate`].
+///
+/// # Errors
+///
+/// Returns [`HayesError::InsufficientBuffer`] if `output` is too small.
+pub fn menu_selection(item: u8, help: bool, output: &mut [u8]) -> Result<usize, HayesError> {
+    let len = if help { 9 } else { 7 };
+    let required = len + 2;
+    if output.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: output.len(),
+        });
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    output[..9].copy_from_slice(&[
+        MENU_SELECTION,
+        len as u8,
+        tag_byte(TAG_DEVICE_IDENTITIES),
+        2,
+        Device::KEYPAD.0,
+        Device::UICC.0,
+        tag_byte(TAG_ITEM_IDENTIFIER),
+        1,
+        item,
+    ]);
+    if help {
+        output[9..11].copy_from_slice(&[tag_byte(TAG_HELP_REQUEST), 0]);
+    }
+
+    Ok(required)
+}
+
+/// `+CUSATP: <UICC_proactive_command>`, a proactive command from the card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cusatp<'at> {
+    /// The command
+    pub command: ProactiveCommand<'at>,
+}
+
+/// Reads the unsolicited result code, which isn't followed by `OK`
+impl<'at> AtResponse<'at> for Cusatp<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut fields = ResponseReader::new(buffer, context).prefixed("+CUSATP")?;
+        let command = ProactiveCommand::decode(fields.next()?)?;
+
+        Ok(Cusatp { command })
+    }
+}
+
+/// `AT+CUSATT=<terminal_response>`, send a terminal response
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cusatt<'a> {
+    /// The encoded terminal response
+    pub response: HexBytes<'a>,
+}
+
+impl<'a> Cusatt<'a> {
+    /// Send the terminal response encoded in `response`
+    #[must_use]
+    pub const fn new(response: &'a [u8]) -> Self {
+        Cusatt {
+            response: HexBytes::new(response),
+        }
+    }
+}
+
+impl AtCommand for Cusatt<'_> {
+    type Response<'at> = ();
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CUSATT")?;
+        writer.param(&self.response.quoted())?;
+        writer.finish()
+    }
+}
+
+/// `AT+CUSATE=<envelope_command>`, send an envelope to the card
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cusate<'a> {
+    /// The encoded envelope
+    pub envelope: HexBytes<'a>,
+}
+
+impl<'a> Cusate<'a> {
+    /// Send the envelope encoded in `envelope`, such as by [`menu_selection`]
+    #[must_use]
+    pub const fn new(envelope: &'a [u8]) -> Self {
+        Cusate {
+            envelope: HexBytes::new(envelope),
+        }
+    }
+}
+
+impl AtCommand for Cusate<'_> {
+    type Response<'at> = CusateResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CUSATE")?;
+        writer.param(&self.envelope.quoted())?;
+        writer.finish()
+    }
+}
+
+/// `+CUSATE: <envelope_response>[,<busy>]`, response to [`Cusate`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CusateResponse<'at> {
+    /// Data returned by the card, if the modem reports it
+    pub response: Option<HexBytes<'at>>,
+    /// Whether the card was busy and didn't process the envelope
+    pub busy: bool,
+}
+
+impl<'at> AtResponse<'at> for CusateResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let (response, busy) = match reader.optional("+CUSATE")? {
+            Some(mut fields) => {
+                let response = fields.next()?;
+                let busy: Option<u8> = fields.optional()?;
+                (Some(response), busy.is_some_and(|busy| busy != 0))
+            }
+            None => (None, false),
+        };
+        // Some modems add the status word as `+CUSATE2: <sw1>,<sw2>`
+        reader.optional("+CUSATE2")?;
+        reader.finish()?;
+
+        Ok(CusateResponse { response, busy })
+    }
+}
+
+/// `+STIN: <cmd>`, a vendor notification that a proactive command is waiting
+/// to be read with [`Stgi`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stin {
+    /// Type of the waiting command
+    pub command_type: CommandType,
+}
+
+/// Reads the unsolicited result code, which isn't followed by `OK`
+impl AtResponse<'_> for Stin {
+    fn read(buffer: &[u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &[u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut fields = ResponseReader::new(buffer, context).prefixed("+STIN")?;
+        Ok(Stin {
+            command_type: fields.next()?,
+        })
+    }
+}
+
+/// `AT+STGI=<cmd>`, read the parameters of the waiting proactive command
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stgi {
+    /// Type of the command, as given by [`Stin`]
+    pub command_type: CommandType,
+}
+
+impl AtCommand for Stgi {
+    type Response<'at> = StgiResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+STGI")?;
+        writer.param(&self.command_type)?;
+        writer.finish()
+    }
+}
+
+/// Response to [`Stgi`]
+///
+/// The parameters are given as `+STGI` lines whose fields depend on the
+/// vendor and the type of command, so they're returned as sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StgiResponse<'at> {
+    /// The `+STGI` lines, separated by line breaks
+    pub lines: &'at [u8],
+}
+
+impl<'at> AtResponse<'at> for StgiResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let lines = reader.body(None);
+        reader.finish()?;
+
+        Ok(StgiResponse { lines })
+    }
+}
+
+/// `AT+STGR=<cmd>[,<result>[,<data>]]`, answer a proactive command read with
+/// [`Stgi`]
+///
+/// The meaning of the result and data depends on the vendor and the type of
+/// command, such as the chosen item for SELECT ITEM.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Stgr<'a> {
+    /// Type of the command being answered
+    pub command_type: CommandType,
+    /// Outcome of the command
+    pub result: Option<u8>,
+    /// Data for the outcome
+    pub data: Option<Text<'a>>,
+}
+
+impl AtCommand for Stgr<'_> {
+    type Response<'at> = ();
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        self.write_with(buffer, &AtContext::default())
+    }
+
+    fn write_with(&self, buffer: &mut [u8], context: &AtContext) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+STGR")?;
+        writer.param(&self.command_type)?;
+        writer.param(&self.result)?;
+        writer.param_with(&self.data, context)?;
+        writer.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_comprehension_tlv() {
+        let (tlv, len) = ComprehensionTlv::parse(HexBytes::new(&[0x81, 0x03, 1, 2, 3])).unwrap();
+        assert_eq!((tlv.tag(), len), (0x01, 5));
+        assert!(tlv.is_comprehension_required());
+
+        let (tlv, len) = ComprehensionTlv::parse(HexBytes::new(&[0x7F, 0x00, 0x2B, 0x00])).unwrap();
+        assert_eq!((tlv.tag(), len), (0x2B, 4));
+        assert!(!tlv.is_comprehension_required());
+
+        let mut data = [0u8; 131];
+        data[..3].copy_from_slice(&[0x0D, 0x81, 0x80]);
+        let (tlv, len) = ComprehensionTlv::parse(HexBytes::new(&data)).unwrap();
+        assert_eq!((tlv.value().len(), len), (128, 131));
+
+        assert_eq!(
+            ComprehensionTlv::parse(HexBytes::new(&[0x0D, 0x05, 0x04])),
+            Err(HayesError::UnexpectedEnd)
+        );
+        assert_eq!(
+            ComprehensionTlv::parse(HexBytes::new(&[0x0D, 0x82, 0x00, 0x01])),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_display_text() {
+        let mut buf = [0u8; 32];
+        // Packed GSM 7-bit "Hello", high priority and wait for the user
+        let cusatp =
+            Cusatp::read(b"\r\n+CUSATP: \"D0138103012181820281028D0600C8329BFD06AB00\"\r\n")
+                .unwrap();
+        let command = cusatp.command;
+        assert_eq!(
+            command.details,
+            CommandDetails {
+                number: 1,
+                command_type: CommandType::DISPLAY_TEXT,
+                qualifier: 0x81,
+            }
+        );
+        assert_eq!(command.destination, Device::DISPLAY);
+
+        let StkCommand::DisplayText(display) = command.command().unwrap() else {
+            panic!("expected DISPLAY TEXT");
+        };
+        assert_eq!(display.text.decode_into(&mut buf), Ok("Hello"));
+        assert!(display.high_priority);
+        assert!(display.wait_for_user);
+        assert!(display.immediate_response);
+
+        // 8-bit and UCS2 text
+        let text = StkText::decode(HexBytes::new(&[0x04, 0x48, 0x69])).unwrap();
+        assert_eq!(text.decode_into(&mut buf), Ok("Hi"));
+        let text = StkText::decode(HexBytes::new(&[0x08, 0x04, 0x1F, 0x04, 0x30])).unwrap();
+        assert_eq!(text.decode_into(&mut buf), Ok("Па"));
+
+        // Missing text string
+        let command =
+            ProactiveCommand::decode(HexBytes::from_hex(b"D009810301210082028102").unwrap())
+                .unwrap();
+        assert_eq!(command.command(), Err(HayesError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_menus() {
+        let mut buf = [0u8; 32];
+        let command = ProactiveCommand::decode(
+            HexBytes::from_hex(
+                b"D01F8103012580820281828505546F6F6C738F0601496E666F738F050248656C70",
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        let StkCommand::SetUpMenu(menu) = command.command().unwrap() else {
+            panic!("expected SET UP MENU");
+        };
+        assert_eq!(menu.title.unwrap().decode_into(&mut buf), Ok("Tools"));
+        assert!(menu.help_available);
+        let mut items = menu.items.iter();
+        let item = items.next().unwrap();
+        assert_eq!(item.id, 1);
+        assert_eq!(item.text.decode_into(&mut buf), Ok("Infos"));
+        assert_eq!(items.next().map(|item| item.id), Some(2));
+        assert!(items.next().is_none());
+
+        let command = ProactiveCommand::decode(
+            HexBytes::from_hex(b"D0148103022400820281828F0201418F020242900102").unwrap(),
+        )
+        .unwrap();
+        let StkCommand::SelectItem(menu) = command.command().unwrap() else {
+            panic!("expected SELECT ITEM");
+        };
+        assert_eq!(menu.title, None);
+        assert_eq!(menu.default_item, Some(2));
+        assert_eq!(menu.items.iter().count(), 2);
+
+        // Removing the menu
+        let command = ProactiveCommand::decode(
+            HexBytes::from_hex(b"D00D81030325008202818285008F00").unwrap(),
+        )
+        .unwrap();
+        let StkCommand::SetUpMenu(menu) = command.command().unwrap() else {
+            panic!("expected SET UP MENU");
+        };
+        assert_eq!(menu.items.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_provide_local_info() {
+        let command =
+            ProactiveCommand::decode(HexBytes::from_hex(b"D009810301260482028182").unwrap())
+                .unwrap();
+        assert_eq!(
+            command.command(),
+            Ok(StkCommand::ProvideLocalInfo(LocalInfo::LANGUAGE))
+        );
+
+        let command =
+            ProactiveCommand::decode(HexBytes::from_hex(b"D009810301130082028183").unwrap())
+                .unwrap();
+        assert_eq!(
+            command.command(),
+            Ok(StkCommand::Other(CommandType::SEND_SHORT_MESSAGE))
+        );
+
+        assert_eq!(
+            ProactiveCommand::decode(HexBytes::from_hex(b"D0058103012600").unwrap()),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            ProactiveCommand::decode(HexBytes::from_hex(b"D1028100").unwrap()),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_terminal_response() {
+        let mut bytes = [0u8; 32];
+        let mut buf = [0u8; 96];
+        let details = CommandDetails {
+            number: 1,
+            command_type: CommandType::SELECT_ITEM,
+            qualifier: 0,
+        };
+
+        let len = TerminalResponse::new(details, ResultCode::OK)
+            .with_item(2)
+            .encode_into(&mut bytes)
+            .unwrap();
+        assert_eq!(
+            &bytes[..len],
+            [
+                0x81, 0x03, 0x01, 0x24, 0x00, 0x82, 0x02, 0x82, 0x81, 0x83, 0x01, 0x00, 0x90, 0x01,
+                0x02
+            ]
+        );
+        let len = Cusatt::new(&bytes[..len]).write(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"AT+CUSATT=\"810301240082028281830100900102\"\r\n"
+        );
+
+        // Language "en" for PROVIDE LOCAL INFORMATION
+        let details = CommandDetails {
+            number: 1,
+            command_type: CommandType::PROVIDE_LOCAL_INFO,
+            qualifier: 0x04,
+        };
+        let response = TerminalResponse {
+            additional_info: Some(0x00),
+            ..TerminalResponse::new(details, ResultCode::TERMINAL_BUSY)
+        };
+        let len = response.encode_into(&mut bytes).unwrap();
+        assert_eq!(&bytes[9..len], [0x83, 0x02, 0x20, 0x00]);
+        let len = TerminalResponse::new(details, ResultCode::OK)
+            .with_data(&[0xAD, 0x02, b'e', b'n'])
+            .encode_into(&mut bytes)
+            .unwrap();
+        assert_eq!(&bytes[9..len], [0x83, 0x01, 0x00, 0xAD, 0x02, b'e', b'n']);
+
+        assert!(matches!(
+            TerminalResponse::new(details, ResultCode::OK).encode_into(&mut bytes[..8]),
+            Err(HayesError::InsufficientBuffer { required: 12, .. })
+        ));
+    }
+
+    #[test]
+    fn test_envelope() {
+        let mut bytes = [0u8; 16];
+        let mut buf = [0u8; 64];
+
+        let len = menu_selection(2, false, &mut bytes).unwrap();
+        let len = Cusate::new(&bytes[..len]).write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CUSATE=\"D30782020181900102\"\r\n");
+
+        let len = menu_selection(1, true, &mut bytes).unwrap();
+        assert_eq!(
+            &bytes[..len],
+            [
+                0xD3, 0x09, 0x82, 0x02, 0x01, 0x81, 0x90, 0x01, 0x01, 0x95, 0x00
+            ]
+        );
+
+        assert_eq!(
+            CusateResponse::read(b"\r\nOK\r\n"),
+            Ok(CusateResponse {
+                response: None,
+                busy: false,
+            })
+        );
+        let response =
+            CusateResponse::read(b"\r\n+CUSATE: \"\",0\r\n+CUSATE2: 144,0\r\n\r\nOK\r\n").unwrap();
+        assert!(response.response.is_some_and(|data| data.is_empty()));
+        assert!(!response.busy);
+    }
+
+    #[test]
+    fn test_vendor_commands() {
+        let mut buf = [0u8; 64];
+
+        assert_eq!(
+            Stin::read(b"\r\n+STIN: 37\r\n"),
+            Ok(Stin {
+                command_type: CommandType::SET_UP_MENU,
+            })
+        );
+
+        let len = Stgi {
+            command_type: CommandType::SET_UP_MENU,
+        }
+        .write(&mut buf)
+        .unwrap();
+        assert_eq!(&buf[..len], b"AT+STGI=37\r\n");
+
+        let response = StgiResponse::read(
+            b"\r\n+STGI: 0,\"Tools\",2\r\n+STGI: 1,2,\"Infos\",0\r\n+STGI: 2,2,\"Help\",0\r\n\r\nOK\r\n",
+        )
+        .unwrap();
+        assert!(response.lines.starts_with(b"+STGI: 0,\"Tools\",2\r\n"));
+        assert!(response.lines.ends_with(b"\"Help\",0"));
+
+        let command = Stgr {
+            command_type: CommandType::SELECT_ITEM,
+            result: Some(0),
+            data: Some(Text::new("2")),
+        };
+        let len = command.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+STGR=36,0,\"2\"\r\n");
+    }
+}
@@ -0,0 +1,370 @@
+//! BER-TLV data objects (ISO/IEC 7816-4 §5.2) and file control parameters
+//! (ETSI TS 102 221 §11.1.1.3)
+//!
+//! Objects are parsed from [`HexBytes`] in place, so the hex string of a
+//! `+CRSM` or `+CSIM` response can be walked without decoding it into a buffer
+//! first.
+
+use crate::{HayesError, HexBytes};
+
+/// A BER-TLV data object
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Tlv<'a> {
+    tag: u32,
+    constructed: bool,
+    value: HexBytes<'a>,
+}
+
+impl<'a> Tlv<'a> {
+    /// Parse the data object at the start of `data`, returning it and the
+    /// number of bytes it takes up
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::UnexpectedEnd`] if the object is truncated, or
+    /// [`HayesError::InvalidFormat`] if its tag or length is malformed.
+    pub fn parse(data: HexBytes<'a>) -> Result<(Self, usize), HayesError> {
+        let byte = |index: usize| data.get(index).ok_or(HayesError::UnexpectedEnd);
+
+        let first = byte(0)?;
+        let mut tag = u32::from(first);
+        let mut pos = 1;
+        // A tag number of 31 continues in the following bytes
+        if first & 0x1F == 0x1F {
+            loop {
+                let next = byte(pos)?;
+                pos += 1;
+                if pos > 4 {
+                    return Err(HayesError::InvalidFormat);
+                }
+                tag = (tag << 8) | u32::from(next);
+                if next & 0x80 == 0 {
+                    break;
+                }
+            }
+        }
+
+        let first_length = byte(pos)?;
+        pos += 1;
+        let len = match first_length {
+            0x00..=0x7F => usize::from(first_length),
+            0x81..=0x83 => {
+                let mut len = 0;
+                for _ in 0..first_length & 0x7F {
+                    len = (len << 8) | usize::from(byte(pos)?);
+                    pos += 1;
+                }
+                len
+            }
+            _ => return Err(HayesError::InvalidFormat),
+        };
+
+        let value = data
+            .slice(pos..pos + len)
+            .ok_or(HayesError::UnexpectedEnd)?;
+
+        Ok((
+            Tlv {
+                tag,
+                constructed: first & 0x20 != 0,
+                value,
+            },
+            pos + len,
+        ))
+    }
+
+    /// The tag, with any subsequent tag bytes following the first, such as
+    /// `0x62` or `0xBF2D`
+    #[must_use]
+    pub const fn tag(&self) -> u32 {
+        self.tag
+    }
+
+    /// Whether the value is made of other data objects
+    #[must_use]
+    pub const fn is_constructed(&self) -> bool {
+        self.constructed
+    }
+
+    /// The value
+    #[must_use]
+    pub const fn value(&self) -> HexBytes<'a> {
+        self.value
+    }
+
+    /// Iterate over the data objects in the value
+    #[must_use]
+    pub fn children(&self) -> TlvIter<'a> {
+        iter(self.value)
+    }
+
+    /// The first data object in the value with `tag`
+    #[must_use]
+    pub fn find(&self, tag: u32) -> Option<Tlv<'a>> {
+        find(self.value, tag)
+    }
+}
+
+/// Iterate over consecutive data objects in `data`
+///
+/// Padding bytes (`00` and `FF`) between objects are skipped.
+#[must_use]
+pub fn iter(data: HexBytes<'_>) -> TlvIter<'_> {
+    TlvIter { data, pos: 0 }
+}
+
+/// The first data object in `data` with `tag`, without descending into
+/// constructed objects
+#[must_use]
+pub fn find(data: HexBytes<'_>, tag: u32) -> Option<Tlv<'_>> {
+    iter(data).map_while(Result::ok).find(|tlv| tlv.tag == tag)
+}
+
+/// Iterator over consecutive data objects, created by [`iter`]
+///
+/// A malformed object is returned as an error, after which iteration stops.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TlvIter<'a> {
+    data: HexBytes<'a>,
+    pos: usize,
+}
+
+impl<'a> Iterator for TlvIter<'a> {
+    type Item = Result<Tlv<'a>, HayesError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while matches!(self.data.get(self.pos), Some(0x00 | 0xFF)) {
+            self.pos += 1;
+        }
+        let rest = self.data.slice(self.pos..self.data.len())?;
+        if rest.is_empty() {
+            return None;
+        }
+
+        match Tlv::parse(rest) {
+            Ok((tlv, len)) => {
+                self.pos += len;
+                Some(Ok(tlv))
+            }
+            Err(error) => {
+                self.pos = self.data.len();
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Tag of the FCP template
+const FCP_TEMPLATE: u32 = 0x62;
+
+/// How the records of an elementary file are organized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FileStructure {
+    /// A dedicated file, such as an application
+    Dedicated,
+    /// A sequence of bytes, read with `READ BINARY`
+    Transparent,
+    /// Records of a fixed length, read with `READ RECORD`
+    LinearFixed,
+    /// Records of a fixed length, the oldest overwritten when full
+    Cyclic,
+    /// Any other structure
+    Other,
+}
+
+/// File descriptor of a file's control parameters (tag `82`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FileDescriptor {
+    /// File descriptor byte
+    pub descriptor: u8,
+    /// Length of each record, for record-based files
+    pub record_length: Option<u16>,
+    /// Number of records, for record-based files
+    pub records: Option<u8>,
+}
+
+impl FileDescriptor {
+    /// How the file is organized
+    #[must_use]
+    pub const fn structure(&self) -> FileStructure {
+        if self.descriptor & 0b0011_1000 == 0b0011_1000 {
+            return FileStructure::Dedicated;
+        }
+
+        match self.descriptor & 0b111 {
+            0b001 => FileStructure::Transparent,
+            0b010 => FileStructure::LinearFixed,
+            0b110 => FileStructure::Cyclic,
+            _ => FileStructure::Other,
+        }
+    }
+}
+
+/// File control parameters, as returned when selecting a file or by
+/// `GET RESPONSE`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Fcp<'a>(Tlv<'a>);
+
+impl<'a> Fcp<'a> {
+    /// Parse an FCP template (tag `62`)
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if `data` isn't an FCP template,
+    /// or an error if it's malformed.
+    pub fn new(data: HexBytes<'a>) -> Result<Self, HayesError> {
+        let (tlv, _) = Tlv::parse(data)?;
+        if tlv.tag != FCP_TEMPLATE {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        Ok(Fcp(tlv))
+    }
+
+    /// The data object with `tag`, for parameters without an accessor
+    #[must_use]
+    pub fn get(&self, tag: u32) -> Option<Tlv<'a>> {
+        self.0.find(tag)
+    }
+
+    /// The file descriptor (tag `82`)
+    #[must_use]
+    pub fn file_descriptor(&self) -> Option<FileDescriptor> {
+        let value = self.get(0x82)?.value();
+        let record_length = match (value.get(2), value.get(3)) {
+            (Some(high), Some(low)) => Some(u16::from_be_bytes([high, low])),
+            _ => None,
+        };
+
+        Some(FileDescriptor {
+            descriptor: value.get(0)?,
+            record_length,
+            records: value.get(4),
+        })
+    }
+
+    /// The file identifier (tag `83`)
+    #[must_use]
+    pub fn file_id(&self) -> Option<u16> {
+        let value = self.get(0x83)?.value();
+        Some(u16::from_be_bytes([value.get(0)?, value.get(1)?]))
+    }
+
+    /// The application identifier of a dedicated file (tag `84`)
+    #[must_use]
+    pub fn df_name(&self) -> Option<HexBytes<'a>> {
+        Some(self.get(0x84)?.value())
+    }
+
+    /// The life cycle status integer (tag `8A`)
+    #[must_use]
+    pub fn life_cycle_status(&self) -> Option<u8> {
+        self.get(0x8A)?.value().get(0)
+    }
+
+    /// Number of data bytes in an elementary file (tag `80`)
+    #[must_use]
+    pub fn file_size(&self) -> Option<u32> {
+        self.number(0x80)
+    }
+
+    /// Number of bytes allocated to the file, including structural information
+    /// (tag `81`)
+    #[must_use]
+    pub fn total_file_size(&self) -> Option<u32> {
+        self.number(0x81)
+    }
+
+    /// A big-endian number of up to four bytes
+    fn number(&self, tag: u32) -> Option<u32> {
+        let value = self.get(tag)?.value();
+        if value.is_empty() || value.len() > 4 {
+            return None;
+        }
+
+        Some(value.iter().fold(0, |n, b| (n << 8) | u32::from(b)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tlv() {
+        let (tlv, len) = Tlv::parse(HexBytes::new(&[0x80, 0x02, 0x12, 0x34, 0x00])).unwrap();
+        assert_eq!(len, 4);
+        assert_eq!(tlv.tag(), 0x80);
+        assert!(!tlv.is_constructed());
+        assert_eq!(tlv.value(), HexBytes::new(&[0x12, 0x34]));
+
+        // Multi-byte tag and long-form length
+        let mut data = [0u8; 133];
+        data[..5].copy_from_slice(&[0xBF, 0x2D, 0x81, 0x80, 0x5A]);
+        let (tlv, len) = Tlv::parse(HexBytes::new(&data)).unwrap();
+        assert_eq!((tlv.tag(), len), (0xBF2D, 132));
+        assert!(tlv.is_constructed());
+        assert_eq!(tlv.value().len(), 128);
+
+        assert_eq!(
+            Tlv::parse(HexBytes::new(&[0x80, 0x03, 0x01])),
+            Err(HayesError::UnexpectedEnd)
+        );
+        assert_eq!(
+            Tlv::parse(HexBytes::new(&[0x80, 0x84, 0, 0, 0, 1])),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_iter() {
+        let data = HexBytes::from_hex(b"8001AA00A1038201BBFFFF").unwrap();
+        let mut tlvs = iter(data);
+        assert_eq!(tlvs.next().map(|t| t.unwrap().tag()), Some(0x80));
+        let constructed = tlvs.next().unwrap().unwrap();
+        assert_eq!(constructed.tag(), 0xA1);
+        assert_eq!(
+            constructed.find(0x82).map(|t| t.value().get(0)),
+            Some(Some(0xBB))
+        );
+        assert!(tlvs.next().is_none());
+
+        assert_eq!(find(data, 0x82), None);
+
+        let mut tlvs = iter(HexBytes::new(&[0x80, 0x05, 0x01]));
+        assert_eq!(tlvs.next(), Some(Err(HayesError::UnexpectedEnd)));
+        assert_eq!(tlvs.next(), None);
+    }
+
+    #[test]
+    fn test_fcp() {
+        // EF ICCID from a USIM
+        let data =
+            HexBytes::from_hex(b"621C8202412183022FE2A5038001718A01058B032F06038002000A880110")
+                .unwrap();
+        let fcp = Fcp::new(data).unwrap();
+        assert_eq!(fcp.file_id(), Some(0x2FE2));
+        assert_eq!(fcp.file_size(), Some(10));
+        assert_eq!(fcp.life_cycle_status(), Some(0x05));
+        let descriptor = fcp.file_descriptor().unwrap();
+        assert_eq!(descriptor.structure(), FileStructure::Transparent);
+        assert_eq!(descriptor.record_length, None);
+
+        // A linear fixed EF with 10 records of 28 bytes
+        let data = HexBytes::from_hex(b"620F82054221001C0A83026F3A80020118").unwrap();
+        let descriptor = Fcp::new(data).unwrap().file_descriptor().unwrap();
+        assert_eq!(descriptor.structure(), FileStructure::LinearFixed);
+        assert_eq!(descriptor.record_length, Some(28));
+        assert_eq!(descriptor.records, Some(10));
+
+        assert_eq!(
+            Fcp::new(HexBytes::new(&[0x6F, 0x00])),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+}
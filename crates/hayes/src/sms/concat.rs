@@ -0,0 +1,886 @@
+//! Concatenated messages (3GPP TS 23.040 §9.2.3.24.1 and §9.2.3.24.8)
+//!
+//! Text too long for a single message is sent in parts. Each part carries a
+//! user data header with a concatenation information element: a reference
+//! number shared by all parts, the number of parts and the part's sequence
+//! number. [`segments`] splits text into parts and [`Reassembler`] joins
+//! received parts back together.
+
+use core::time::Duration;
+
+use crate::{
+    HayesError, HexBytes,
+    charset::gsm7,
+    sms::pdu::{Address, Cmgs, SmsDeliver, SmsSubmit, UserData},
+};
+
+/// Concatenated short messages, 8-bit reference number
+const IEI_CONCAT_8: u8 = 0x00;
+/// Concatenated short messages, 16-bit reference number
+const IEI_CONCAT_16: u8 = 0x08;
+
+/// Septets in a message without a user data header
+const SINGLE_SEPTETS: usize = 160;
+/// UCS2 code units in a message without a user data header
+const SINGLE_UNITS: usize = 70;
+/// Octets of user data in a message
+const USER_DATA_OCTETS: usize = 140;
+
+/// Reference number shared by the parts of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Reference {
+    /// 8-bit reference number, leaving more room for text
+    Eight(u8),
+    /// 16-bit reference number, less likely to be reused while parts are
+    /// outstanding
+    Sixteen(u16),
+}
+
+impl Reference {
+    /// Length of the user data header carrying this reference, including its
+    /// length octet
+    const fn header_octets(self) -> usize {
+        match self {
+            Reference::Eight(_) => 6,
+            Reference::Sixteen(_) => 7,
+        }
+    }
+}
+
+/// Concatenation information element of one part
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Concatenation {
+    /// Reference number shared by all parts
+    pub reference: Reference,
+    /// Number of parts, 1–255
+    pub total: u8,
+    /// Sequence number of this part, starting at 1
+    pub sequence: u8,
+}
+
+impl Concatenation {
+    /// Find the concatenation information element in a user data header
+    ///
+    /// Returns `None` if there isn't one, or if its part numbers are invalid,
+    /// in which case the specification requires it to be ignored.
+    #[must_use]
+    pub fn from_header(header: HexBytes<'_>) -> Option<Self> {
+        let mut pos = 0;
+        while let (Some(iei), Some(len)) = (header.get(pos), header.get(pos + 1)) {
+            let len = usize::from(len);
+            let data = header.slice(pos + 2..pos + 2 + len)?;
+
+            let (reference, rest) = match (iei, len) {
+                (IEI_CONCAT_8, 3) => (Reference::Eight(data.get(0)?), 1),
+                (IEI_CONCAT_16, 4) => (
+                    Reference::Sixteen(u16::from_be_bytes([data.get(0)?, data.get(1)?])),
+                    2,
+                ),
+                _ => {
+                    pos += 2 + len;
+                    continue;
+                }
+            };
+
+            let concatenation = Concatenation {
+                reference,
+                total: data.get(rest)?,
+                sequence: data.get(rest + 1)?,
+            };
+
+            return (concatenation.total > 0
+                && concatenation.sequence > 0
+                && concatenation.sequence <= concatenation.total)
+                .then_some(concatenation);
+        }
+
+        None
+    }
+
+    /// Encode as a user data header, returning the buffer and its used length
+    fn header(self) -> ([u8; 6], usize) {
+        match self.reference {
+            Reference::Eight(reference) => (
+                [IEI_CONCAT_8, 3, reference, self.total, self.sequence, 0],
+                5,
+            ),
+            Reference::Sixteen(reference) => {
+                let [high, low] = reference.to_be_bytes();
+                ([IEI_CONCAT_16, 4, high, low, self.total, self.sequence], 6)
+            }
+        }
+    }
+}
+
+/// Cost of a character in septets or UCS2 code units
+fn cost(c: char, ucs2: bool) -> usize {
+    if ucs2 {
+        c.len_utf16()
+    } else {
+        gsm7::encode_char(c).map_or(0, |(_, len)| len)
+    }
+}
+
+/// Byte index at which to end a part of at most `capacity`
+///
+/// Characters are never split, so an escaped GSM character or a UTF-16
+/// surrogate pair stays within one part.
+fn split(text: &str, ucs2: bool, capacity: usize) -> usize {
+    let mut used = 0;
+    for (index, c) in text.char_indices() {
+        used += cost(c, ucs2);
+        if used > capacity {
+            return index;
+        }
+    }
+
+    text.len()
+}
+
+/// Split `text` into as many messages as needed
+///
+/// The text is sent in the GSM alphabet if every character is in it,
+/// otherwise in UCS2. Text that fits in a single message is sent without a
+/// header; otherwise each part holds 153 septets or 67 UCS2 characters with an
+/// 8-bit reference, or 152 septets or 66 UCS2 characters with a 16-bit one.
+///
+/// # Errors
+///
+/// Returns [`HayesError::OutOfRange`] if the text needs more than 255 parts.
+pub fn segments(text: &str, reference: Reference) -> Result<Segments<'_>, HayesError> {
+    let ucs2 = gsm7::encoded_len(text).is_err();
+    let length: usize = text.chars().map(|c| cost(c, ucs2)).sum();
+
+    let single = if ucs2 { SINGLE_UNITS } else { SINGLE_SEPTETS };
+    if length <= single {
+        return Ok(Segments {
+            rest: text,
+            ucs2,
+            capacity: single,
+            reference: None,
+            total: 1,
+            sequence: 0,
+        });
+    }
+
+    let header = reference.header_octets();
+    let capacity = if ucs2 {
+        (USER_DATA_OCTETS - header) / 2
+    } else {
+        SINGLE_SEPTETS - (header * 8).div_ceil(7)
+    };
+
+    let mut total = 0u8;
+    let mut rest = text;
+    while !rest.is_empty() {
+        total = total.checked_add(1).ok_or(HayesError::OutOfRange)?;
+        rest = &rest[split(rest, ucs2, capacity)..];
+    }
+
+    Ok(Segments {
+        rest: text,
+        ucs2,
+        capacity,
+        reference: Some(reference),
+        total,
+        sequence: 0,
+    })
+}
+
+/// Iterator over the parts of a message, created by [`segments`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Segments<'a> {
+    rest: &'a str,
+    ucs2: bool,
+    capacity: usize,
+    reference: Option<Reference>,
+    total: u8,
+    sequence: u8,
+}
+
+impl<'a> Iterator for Segments<'a> {
+    type Item = Segment<'a>;
+
+    fn next(&mut self) -> Option<Segment<'a>> {
+        if self.sequence == self.total {
+            return None;
+        }
+        self.sequence += 1;
+
+        let end = split(self.rest, self.ucs2, self.capacity);
+        let (text, rest) = self.rest.split_at(end);
+        self.rest = rest;
+
+        let concatenation = self.reference.map(|reference| Concatenation {
+            reference,
+            total: self.total,
+            sequence: self.sequence,
+        });
+        let (header, header_len) = concatenation.map_or(([0; 6], 0), Concatenation::header);
+
+        Some(Segment {
+            text,
+            ucs2: self.ucs2,
+            concatenation,
+            header,
+            header_len,
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = usize::from(self.total - self.sequence);
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for Segments<'_> {}
+
+/// One part of a message, with its user data header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Segment<'a> {
+    text: &'a str,
+    ucs2: bool,
+    concatenation: Option<Concatenation>,
+    header: [u8; 6],
+    header_len: usize,
+}
+
+impl<'a> Segment<'a> {
+    /// The text of this part
+    #[must_use]
+    pub const fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The concatenation information element, or `None` if the text fits in a
+    /// single message
+    #[must_use]
+    pub const fn concatenation(&self) -> Option<Concatenation> {
+        self.concatenation
+    }
+
+    /// The user data of this part, including its header
+    #[must_use]
+    pub fn user_data(&self) -> UserData<'_> {
+        let user_data = if self.ucs2 {
+            UserData::ucs2(self.text)
+        } else {
+            UserData::text(self.text)
+        };
+
+        if self.header_len == 0 {
+            return user_data;
+        }
+        user_data.with_header(HexBytes::new(&self.header[..self.header_len]))
+    }
+
+    /// An SMS-SUBMIT sending this part to `destination`
+    #[must_use]
+    pub fn submit(&self, destination: Address<'a>) -> SmsSubmit<'_> {
+        SmsSubmit::new(destination, self.user_data())
+    }
+
+    /// The `+CMGS` command sending this part to `destination`
+    #[must_use]
+    pub fn command(&self, destination: Address<'a>) -> Cmgs<'_> {
+        Cmgs {
+            pdu: self.submit(destination),
+        }
+    }
+}
+
+/// Maximum length of a sender address, in bytes of UTF-8
+const SENDER_CAPACITY: usize = 48;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SlotState {
+    Free,
+    Pending,
+    Complete,
+}
+
+#[derive(Debug, Clone)]
+struct Slot<const PARTS: usize, const BYTES: usize> {
+    state: SlotState,
+    reference: Option<Reference>,
+    sender: [u8; SENDER_CAPACITY],
+    sender_len: usize,
+    total: u8,
+    received: Duration,
+    /// Byte range of each part in `text`
+    parts: [Option<(usize, usize)>; PARTS],
+    used: usize,
+    text: [u8; BYTES],
+}
+
+impl<const PARTS: usize, const BYTES: usize> Slot<PARTS, BYTES> {
+    const fn new() -> Self {
+        Slot {
+            state: SlotState::Free,
+            reference: None,
+            sender: [0; SENDER_CAPACITY],
+            sender_len: 0,
+            total: 0,
+            received: Duration::ZERO,
+            parts: [None; PARTS],
+            used: 0,
+            text: [0; BYTES],
+        }
+    }
+
+    fn matches(&self, reference: Option<Reference>, sender: &[u8], total: u8) -> bool {
+        self.state == SlotState::Pending
+            && reference.is_some()
+            && self.reference == reference
+            && &self.sender[..self.sender_len] == sender
+            && self.total == total
+    }
+
+    fn is_complete(&self) -> bool {
+        self.parts[..usize::from(self.total)]
+            .iter()
+            .all(Option::is_some)
+    }
+}
+
+/// Fixed-capacity buffer that joins the parts of concatenated messages
+///
+/// Holds up to `MESSAGES` incomplete messages of up to `PARTS` parts, with
+/// `BYTES` of decoded UTF-8 text each. Parts may arrive in any order and are
+/// matched by reference number and sender. Messages that are still incomplete
+/// after the timeout are dropped, as is the oldest incomplete message when a
+/// new one arrives and every slot is in use. Messages of a single part are
+/// returned straight away without taking a slot. `MESSAGES` must be at least
+/// one.
+///
+/// Times are given by the caller as the [`Duration`] since any fixed point,
+/// such as boot.
+#[derive(Debug, Clone)]
+pub struct Reassembler<const MESSAGES: usize, const PARTS: usize, const BYTES: usize> {
+    slots: [Slot<PARTS, BYTES>; MESSAGES],
+    /// Holds the last message of a single part
+    single: Slot<1, BYTES>,
+    timeout: Duration,
+}
+
+#[cfg(feature = "defmt")]
+impl<const MESSAGES: usize, const PARTS: usize, const BYTES: usize> defmt::Format
+    for Reassembler<MESSAGES, PARTS, BYTES>
+{
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "Reassembler {{ pending: {=usize}, timeout_ms: {=u128} }}",
+            self.pending(),
+            self.timeout.as_millis(),
+        );
+    }
+}
+
+impl<const MESSAGES: usize, const PARTS: usize, const BYTES: usize>
+    Reassembler<MESSAGES, PARTS, BYTES>
+{
+    /// Create an empty reassembler that drops incomplete messages after
+    /// `timeout`
+    #[must_use]
+    pub fn new(timeout: Duration) -> Self {
+        const { assert!(MESSAGES > 0, "a reassembler needs at least one slot") };
+
+        Reassembler {
+            slots: core::array::from_fn(|_| Slot::new()),
+            single: Slot::new(),
+            timeout,
+        }
+    }
+
+    /// Number of incomplete messages being held
+    #[must_use]
+    pub fn pending(&self) -> usize {
+        self.slots
+            .iter()
+            .filter(|slot| slot.state == SlotState::Pending)
+            .count()
+    }
+
+    /// Drop incomplete messages whose first part arrived at least the timeout
+    /// before `now`, returning how many were dropped
+    pub fn evict(&mut self, now: Duration) -> usize {
+        let mut evicted = 0;
+        for slot in &mut self.slots {
+            if slot.state == SlotState::Pending && now.saturating_sub(slot.received) >= self.timeout
+            {
+                slot.state = SlotState::Free;
+                evicted += 1;
+            }
+        }
+
+        evicted
+    }
+
+    /// Add a received message, returning the full message once every part has
+    /// arrived
+    ///
+    /// Messages without a concatenation header are returned straight away.
+    /// Repeated parts are ignored. The returned message is held until the next
+    /// call.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if the message has more than `PARTS`
+    /// parts, or [`HayesError::InsufficientBuffer`] if its text or sender
+    /// doesn't fit.
+    pub fn push(
+        &mut self,
+        message: &SmsDeliver<'_>,
+        now: Duration,
+    ) -> Result<Option<Message<'_>>, HayesError> {
+        for slot in &mut self.slots {
+            if slot.state == SlotState::Complete {
+                slot.state = SlotState::Free;
+            }
+        }
+        self.evict(now);
+
+        let concatenation = message
+            .user_data
+            .header()
+            .and_then(Concatenation::from_header);
+        let (reference, total, sequence) =
+            concatenation.map_or((None, 1, 1), |c| (Some(c.reference), c.total, c.sequence));
+        if total == 1 {
+            return self.single(message).map(Some);
+        }
+        if usize::from(total) > PARTS {
+            return Err(HayesError::OutOfRange);
+        }
+
+        let mut sender = [0u8; SENDER_CAPACITY];
+        let sender = message.originator.decode_into(&mut sender)?.as_bytes();
+
+        let index = match self
+            .slots
+            .iter()
+            .position(|slot| slot.matches(reference, sender, total))
+        {
+            Some(index) => index,
+            None => self.allocate(),
+        };
+        let slot = &mut self.slots[index];
+        let is_new = slot.state == SlotState::Free;
+        if is_new {
+            slot.used = 0;
+        }
+
+        let part = usize::from(sequence - 1);
+        if slot.state == SlotState::Pending && slot.parts[part].is_some() {
+            return Ok(None);
+        }
+
+        let start = slot.used;
+        let len = message
+            .user_data
+            .decode_into(&mut slot.text[start..])
+            .map_err(|error| match error {
+                HayesError::InsufficientBuffer { required, .. } => HayesError::InsufficientBuffer {
+                    required: start + required,
+                    available: BYTES,
+                },
+                error => error,
+            })?
+            .len();
+
+        if is_new {
+            slot.state = SlotState::Pending;
+            slot.reference = reference;
+            slot.sender[..sender.len()].copy_from_slice(sender);
+            slot.sender_len = sender.len();
+            slot.total = total;
+            slot.received = now;
+            slot.parts = [None; PARTS];
+        }
+        slot.parts[part] = Some((start, start + len));
+        slot.used += len;
+
+        if !slot.is_complete() {
+            return Ok(None);
+        }
+
+        slot.state = SlotState::Complete;
+        Ok(Some(Message {
+            sender: core::str::from_utf8(&slot.sender[..slot.sender_len]).unwrap_or_default(),
+            text: &slot.text,
+            parts: &slot.parts[..usize::from(slot.total)],
+        }))
+    }
+
+    /// Decode a message of a single part, leaving the slots alone
+    fn single(&mut self, message: &SmsDeliver<'_>) -> Result<Message<'_>, HayesError> {
+        let slot = &mut self.single;
+        let sender_len = message.originator.decode_into(&mut slot.sender)?.len();
+        let len = message.user_data.decode_into(&mut slot.text)?.len();
+        slot.parts = [Some((0, len))];
+
+        Ok(Message {
+            sender: core::str::from_utf8(&slot.sender[..sender_len]).unwrap_or_default(),
+            text: &slot.text,
+            parts: &slot.parts,
+        })
+    }
+
+    /// Find a free slot, dropping the oldest incomplete message if needed
+    fn allocate(&mut self) -> usize {
+        if let Some(index) = self
+            .slots
+            .iter()
+            .position(|slot| slot.state == SlotState::Free)
+        {
+            return index;
+        }
+
+        // `new` checks that there is at least one slot
+        let index = self
+            .slots
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, slot)| slot.received)
+            .map_or(0, |(index, _)| index);
+        self.slots[index].state = SlotState::Free;
+
+        index
+    }
+}
+
+/// A message whose parts have all arrived
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Message<'r> {
+    sender: &'r str,
+    text: &'r [u8],
+    parts: &'r [Option<(usize, usize)>],
+}
+
+impl<'r> Message<'r> {
+    /// The originating address, as returned by [`Address::decode_into`]
+    #[must_use]
+    pub const fn sender(&self) -> &'r str {
+        self.sender
+    }
+
+    /// The text of each part, in order
+    pub fn parts(&self) -> impl Iterator<Item = &'r str> + 'r {
+        let text = self.text;
+        self.parts.iter().flatten().map(move |&(start, end)| {
+            // Each part was stored as UTF-8
+            core::str::from_utf8(&text[start..end]).unwrap_or_default()
+        })
+    }
+
+    /// Length of the text in bytes of UTF-8
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.parts().map(str::len).sum()
+    }
+
+    /// Whether the text is empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Copy the whole text into `buffer`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        let required = self.len();
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        let mut pos = 0;
+        for part in self.parts() {
+            buffer[pos..pos + part.len()].copy_from_slice(part.as_bytes());
+            pos += part.len();
+        }
+
+        core::str::from_utf8(&buffer[..pos]).map_err(|_| HayesError::InvalidUtf8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AtCommand, ModemDateTime};
+
+    fn deliver<'a>(originator: &'a str, user_data: UserData<'a>) -> SmsDeliver<'a> {
+        SmsDeliver {
+            smsc: None,
+            more_messages: false,
+            reply_path: false,
+            status_report_indication: false,
+            originator: Address::new(originator).unwrap(),
+            protocol_identifier: 0,
+            timestamp: ModemDateTime {
+                year: 2024,
+                month: 1,
+                day: 1,
+                hour: 0,
+                minute: 0,
+                second: 0,
+                time_zone: None,
+            },
+            user_data,
+        }
+    }
+
+    #[test]
+    fn test_single_segment() {
+        let text = core::str::from_utf8(&[b'a'; 160]).unwrap();
+        let mut parts = segments(text, Reference::Eight(1)).unwrap();
+        assert_eq!(parts.len(), 1);
+
+        let part = parts.next().unwrap();
+        assert_eq!(part.text(), text);
+        assert_eq!(part.concatenation(), None);
+        assert_eq!(part.user_data().header(), None);
+        assert_eq!(parts.next(), None);
+
+        let mut parts = segments("", Reference::Eight(1)).unwrap();
+        assert_eq!(parts.next().map(|p| p.text()), Some(""));
+        assert_eq!(parts.next(), None);
+    }
+
+    #[test]
+    fn test_gsm7_segments() {
+        let text = core::str::from_utf8(&[b'a'; 161]).unwrap();
+        let parts = segments(text, Reference::Eight(0x2A)).unwrap();
+        assert_eq!(parts.len(), 2);
+
+        let [first, second] = [0, 1].map(|i| parts.clone().nth(i).unwrap());
+        assert_eq!(first.text().len(), 153);
+        assert_eq!(second.text().len(), 8);
+        assert_eq!(
+            second.concatenation(),
+            Some(Concatenation {
+                reference: Reference::Eight(0x2A),
+                total: 2,
+                sequence: 2,
+            })
+        );
+        assert_eq!(
+            first.user_data().header(),
+            Some(HexBytes::new(&[0x00, 0x03, 0x2A, 0x02, 0x01]))
+        );
+        assert_eq!(first.user_data().len(), Ok(160));
+
+        // A 16-bit reference leaves one septet less
+        let mut parts = segments(text, Reference::Sixteen(0x1234)).unwrap();
+        let first = parts.next().unwrap();
+        assert_eq!(first.text().len(), 152);
+        assert_eq!(
+            first.user_data().header(),
+            Some(HexBytes::new(&[0x08, 0x04, 0x12, 0x34, 0x02, 0x01]))
+        );
+        assert_eq!(first.user_data().len(), Ok(160));
+    }
+
+    #[test]
+    fn test_escaped_characters_are_not_split() {
+        let mut text = [b'a'; 200];
+        text[152..155].copy_from_slice("€".as_bytes());
+        let text = core::str::from_utf8(&text).unwrap();
+
+        let mut parts = segments(text, Reference::Eight(1)).unwrap();
+        assert_eq!(parts.next().unwrap().text().len(), 152);
+        assert!(parts.next().unwrap().text().starts_with('€'));
+    }
+
+    #[test]
+    fn test_ucs2_segments() {
+        let mut text = [0u8; 71 * 3];
+        for chunk in text.chunks_exact_mut(3) {
+            chunk.copy_from_slice("你".as_bytes());
+        }
+        let text = core::str::from_utf8(&text).unwrap();
+
+        let parts = segments(&text[..70 * 3], Reference::Eight(1)).unwrap();
+        assert_eq!(parts.len(), 1);
+
+        let mut parts = segments(text, Reference::Eight(1)).unwrap();
+        let first = parts.next().unwrap();
+        assert_eq!(first.text().chars().count(), 67);
+        assert_eq!(first.user_data().len(), Ok(140));
+        assert_eq!(parts.next().unwrap().text().chars().count(), 4);
+
+        let mut parts = segments(text, Reference::Sixteen(1)).unwrap();
+        assert_eq!(parts.next().unwrap().text().chars().count(), 66);
+    }
+
+    #[test]
+    fn test_too_many_segments() {
+        static TEXT: [u8; 153 * 255 + 1] = [b'a'; 153 * 255 + 1];
+        let text = core::str::from_utf8(&TEXT).unwrap();
+        assert_eq!(
+            segments(text, Reference::Eight(1)).map(|s| s.len()),
+            Err(HayesError::OutOfRange)
+        );
+        assert_eq!(
+            segments(&text[..153 * 255], Reference::Eight(1)).map(|s| s.len()),
+            Ok(255)
+        );
+    }
+
+    #[test]
+    fn test_segment_commands() {
+        let mut buf = [0u8; 400];
+        let text = core::str::from_utf8(&[b'a'; 200]).unwrap();
+
+        for part in segments(text, Reference::Eight(7)).unwrap() {
+            let command = part.command(Address::new("+15551234").unwrap());
+            let len = command.write(&mut buf).unwrap();
+            assert!(buf[..len].starts_with(b"AT+CMGS="));
+
+            let len = command.write_pdu(&mut buf).unwrap();
+            assert_eq!(buf[len - 1], crate::sms::CTRL_Z);
+        }
+    }
+
+    #[test]
+    fn test_concatenation_from_header() {
+        // Skips other information elements
+        let header = HexBytes::new(&[0x0A, 0x01, 0xFF, 0x08, 0x04, 0x01, 0x02, 0x03, 0x02]);
+        assert_eq!(
+            Concatenation::from_header(header),
+            Some(Concatenation {
+                reference: Reference::Sixteen(0x0102),
+                total: 3,
+                sequence: 2,
+            })
+        );
+
+        assert_eq!(
+            Concatenation::from_header(HexBytes::new(&[0x00, 0x03, 0x01, 0x02, 0x03])),
+            None
+        );
+        assert_eq!(
+            Concatenation::from_header(HexBytes::new(&[0x00, 0x03, 0x01])),
+            None
+        );
+    }
+
+    #[test]
+    fn test_reassemble() {
+        let mut reassembler = Reassembler::<2, 4, 512>::new(Duration::from_mins(1));
+        let mut buf = [0u8; 512];
+
+        let text = "The quick brown fox jumps over the lazy dog. ";
+        let mut long = [0u8; 45 * 5];
+        for chunk in long.chunks_exact_mut(45) {
+            chunk.copy_from_slice(text.as_bytes());
+        }
+        let long = core::str::from_utf8(&long).unwrap();
+
+        let parts = segments(long, Reference::Eight(9)).unwrap();
+        let [first, second] = [0, 1].map(|i| parts.clone().nth(i).unwrap());
+
+        let now = Duration::from_secs(100);
+        let second = deliver("+15551234", second.user_data());
+        assert_eq!(reassembler.push(&second, now), Ok(None));
+        assert_eq!(reassembler.push(&second, now), Ok(None));
+        assert_eq!(reassembler.pending(), 1);
+
+        // Same reference from another sender is another message
+        let other = deliver("+15559999", first.user_data());
+        assert_eq!(reassembler.push(&other, now), Ok(None));
+        assert_eq!(reassembler.pending(), 2);
+
+        let first = deliver("+15551234", first.user_data());
+        let message = reassembler.push(&first, now).unwrap().unwrap();
+        assert_eq!(message.sender(), "+15551234");
+        assert_eq!(message.parts().count(), 2);
+        assert_eq!(message.decode_into(&mut buf), Ok(long));
+        assert_eq!(reassembler.pending(), 1);
+
+        let single = deliver("+15551234", UserData::text("Hello"));
+        let message = reassembler.push(&single, now).unwrap().unwrap();
+        assert_eq!(message.decode_into(&mut buf), Ok("Hello"));
+    }
+
+    #[test]
+    fn test_reassembler_eviction() {
+        let mut reassembler = Reassembler::<1, 4, 512>::new(Duration::from_mins(1));
+        let text = core::str::from_utf8(&[b'a'; 300]).unwrap();
+        let parts = segments(text, Reference::Eight(1)).unwrap();
+        let [first, second] = [0, 1].map(|i| parts.clone().nth(i).unwrap());
+
+        let first = deliver("+15551234", first.user_data());
+        let second = deliver("+15551234", second.user_data());
+
+        assert_eq!(reassembler.push(&first, Duration::from_secs(0)), Ok(None));
+        assert_eq!(reassembler.evict(Duration::from_secs(59)), 0);
+        assert_eq!(reassembler.evict(Duration::from_mins(1)), 1);
+        assert_eq!(reassembler.pending(), 0);
+
+        // The only slot is reused for a new message when full
+        assert_eq!(reassembler.push(&first, Duration::from_secs(61)), Ok(None));
+        let other = deliver("+15550000", second.user_data);
+        assert_eq!(reassembler.push(&other, Duration::from_secs(62)), Ok(None));
+        assert_eq!(reassembler.push(&second, Duration::from_secs(63)), Ok(None));
+        assert_eq!(reassembler.pending(), 1);
+    }
+
+    #[test]
+    fn test_reassembler_single_part() {
+        let mut reassembler = Reassembler::<1, 4, 512>::new(Duration::from_mins(1));
+        let mut buf = [0u8; 512];
+        let text = core::str::from_utf8(&[b'a'; 300]).unwrap();
+        let parts = segments(text, Reference::Eight(1)).unwrap();
+        let [first, second] = [0, 1].map(|i| parts.clone().nth(i).unwrap());
+        let first = deliver("+15551234", first.user_data());
+        let second = deliver("+15551234", second.user_data());
+
+        assert_eq!(reassembler.push(&first, Duration::ZERO), Ok(None));
+
+        // A single-part message doesn't evict the pending one from the only slot
+        let single = deliver("+15559999", UserData::text("Hello"));
+        let message = reassembler.push(&single, Duration::ZERO).unwrap().unwrap();
+        assert_eq!(message.sender(), "+15559999");
+        assert_eq!(message.decode_into(&mut buf), Ok("Hello"));
+        assert_eq!(reassembler.pending(), 1);
+
+        let message = reassembler.push(&second, Duration::ZERO).unwrap().unwrap();
+        assert_eq!(message.decode_into(&mut buf), Ok(text));
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_reassembler_limits() {
+        let text = core::str::from_utf8(&[b'a'; 800]).unwrap();
+        let first = segments(text, Reference::Eight(1)).unwrap().next().unwrap();
+        let first = deliver("+15551234", first.user_data());
+
+        let mut reassembler = Reassembler::<1, 4, 512>::new(Duration::from_mins(1));
+        assert_eq!(
+            reassembler.push(&first, Duration::ZERO),
+            Err(HayesError::OutOfRange)
+        );
+
+        let mut reassembler = Reassembler::<1, 8, 100>::new(Duration::from_mins(1));
+        assert_eq!(
+            reassembler.push(&first, Duration::ZERO),
+            Err(HayesError::InsufficientBuffer {
+                required: 153,
+                available: 100,
+            })
+        );
+        assert_eq!(reassembler.pending(), 0);
+    }
+}
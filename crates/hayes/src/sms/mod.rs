@@ -0,0 +1,761 @@
+//! Short message service (3GPP TS 23.040 and TS 27.005)
+//!
+//! This module has the commands shared by both message formats, such as
+//! message storage and new message indications. [`pdu`] encodes and decodes the
+//! messages exchanged in PDU mode (`+CMGF=0`), [`text`] has the commands for
+//! text mode (`+CMGF=1`), and [`concat`](mod@concat) splits long messages into
+//! parts and joins them back together.
+//!
+//! Sending a message takes two steps: `AT+CMGS` is written and the modem
+//! answers with a `> ` prompt (see [`is_prompt`]), then the message is written
+//! followed by [`CTRL_Z`]. The modem then responds with [`CmgsResponse`].
+
+pub mod concat;
+pub mod pdu;
+pub mod text;
+
+use crate::{
+    AtCommand, AtContext, AtReadable, AtResponse, AtWritable, HayesError, PhoneNumber,
+    at::{CommandWriter, ResponseReader},
+};
+
+macro_rules! impl_indication {
+    ($($ty:ident { $($value:literal => $variant:ident),* $(,)? }),* $(,)?) => {
+        $(
+            impl<'at> AtReadable<'at> for $ty {
+                fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+                    let (value, consumed) = u8::read(input)?;
+                    let value = match value {
+                        $($value => $ty::$variant,)*
+                        _ => return Err(HayesError::OutOfRange),
+                    };
+
+                    Ok((value, consumed))
+                }
+            }
+
+            impl AtWritable for $ty {
+                fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+                    (*self as u8).write(output)
+                }
+            }
+        )*
+    };
+}
+
+/// Ends the message body written after the `> ` prompt
+pub const CTRL_Z: u8 = 0x1A;
+
+/// Cancels the message written after the `> ` prompt
+pub const ESC: u8 = 0x1B;
+
+/// Whether `input` is the `> ` prompt for a message body
+///
+/// Leading line breaks are ignored.
+#[must_use]
+pub fn is_prompt(input: &[u8]) -> bool {
+    input
+        .iter()
+        .find(|&&b| b != b'\r' && b != b'\n')
+        .is_some_and(|&b| b == b'>')
+}
+
+/// Response to `+CMGS`, the reference assigned to the sent message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CmgsResponse {
+    /// TP-Message-Reference, as used in status reports
+    pub message_reference: u8,
+}
+
+impl<'at> AtResponse<'at> for CmgsResponse {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let mut fields = reader.prefixed("+CMGS")?;
+        let message_reference = fields.next_named("mr")?;
+        reader.finish()?;
+
+        Ok(CmgsResponse { message_reference })
+    }
+}
+
+/// Message format selected with `+CMGF`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MessageFormat {
+    /// Messages are exchanged as hex-encoded PDUs, see [`pdu`]
+    #[default]
+    Pdu = 0,
+    /// Messages are exchanged as text with their header fields, see [`text`]
+    Text = 1,
+}
+
+impl_indication!(MessageFormat {
+    0 => Pdu,
+    1 => Text,
+});
+
+/// `AT+CMGF=<mode>`, selecting the message format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmgf {
+    /// The format to use
+    pub format: MessageFormat,
+}
+
+impl AtCommand for Cmgf {
+    type Response<'at> = ();
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CMGF")?;
+        writer.param(&self.format)?;
+        writer.finish()
+    }
+}
+
+/// `AT+CSCA=<sca>[,<tosca>]`, setting the service centre address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Csca<'a> {
+    /// The service centre address
+    pub address: PhoneNumber<'a>,
+}
+
+impl AtCommand for Csca<'_> {
+    type Response<'at> = ();
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CSCA")?;
+        writer.param(&self.address)?;
+        writer.finish()
+    }
+}
+
+/// `AT+CSCA?`, reading the service centre address
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CscaQuery;
+
+impl AtCommand for CscaQuery {
+    type Response<'at> = CscaResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        CommandWriter::new(buffer, "+CSCA?")?.finish()
+    }
+}
+
+/// Response to `+CSCA?`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CscaResponse<'at> {
+    /// The service centre address
+    pub address: PhoneNumber<'at>,
+}
+
+impl<'at> AtResponse<'at> for CscaResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let address = reader.prefixed("+CSCA")?.next()?;
+        reader.finish()?;
+
+        Ok(CscaResponse { address })
+    }
+}
+
+/// Message storage, as used by `+CPMS` and `+CMTI`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[non_exhaustive]
+pub enum Storage {
+    /// Broadcast message storage (`"BM"`)
+    Broadcast,
+    /// ME message storage (`"ME"`)
+    Me,
+    /// Any of the storages associated with the ME (`"MT"`)
+    Mt,
+    /// SIM message storage (`"SM"`)
+    Sim,
+    /// Status report storage (`"SR"`)
+    StatusReport,
+    /// Terminal adaptor message storage (`"TA"`)
+    Ta,
+}
+
+impl Storage {
+    /// The name used by AT commands, such as `"SM"`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            Storage::Broadcast => "BM",
+            Storage::Me => "ME",
+            Storage::Mt => "MT",
+            Storage::Sim => "SM",
+            Storage::StatusReport => "SR",
+            Storage::Ta => "TA",
+        }
+    }
+}
+
+impl<'at> AtReadable<'at> for Storage {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (name, consumed) = <&str>::read(input)?;
+        let storage = match name {
+            "BM" => Storage::Broadcast,
+            "ME" => Storage::Me,
+            "MT" => Storage::Mt,
+            "SM" => Storage::Sim,
+            "SR" => Storage::StatusReport,
+            "TA" => Storage::Ta,
+            _ => return Err(HayesError::UnsupportedError),
+        };
+
+        Ok((storage, consumed))
+    }
+}
+
+impl AtWritable for Storage {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        self.name().write(output)
+    }
+}
+
+/// Messages stored in a storage and its capacity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StorageUsage {
+    /// Number of messages stored
+    pub used: u16,
+    /// Number of messages that can be stored
+    pub total: u16,
+}
+
+/// `AT+CPMS=<mem1>[,<mem2>[,<mem3>]]`, selecting the storages used for
+/// reading, writing and receiving messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cpms {
+    /// Storage messages are read and deleted from
+    pub read: Storage,
+    /// Storage messages are written and sent from
+    pub write: Option<Storage>,
+    /// Storage received messages are placed in
+    pub receive: Option<Storage>,
+}
+
+impl AtCommand for Cpms {
+    type Response<'at> = CpmsResponse;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CPMS")?;
+        writer.param(&self.read)?;
+        writer.param(&self.write)?;
+        writer.param(&self.receive)?;
+        writer.finish()
+    }
+}
+
+/// Response to [`Cpms`], the usage of each selected storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CpmsResponse {
+    /// Usage of the storage messages are read from
+    pub read: StorageUsage,
+    /// Usage of the storage messages are written to
+    pub write: Option<StorageUsage>,
+    /// Usage of the storage received messages are placed in
+    pub receive: Option<StorageUsage>,
+}
+
+impl<'at> AtResponse<'at> for CpmsResponse {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let mut fields = reader.prefixed("+CPMS")?;
+
+        let read = StorageUsage {
+            used: fields.next_named("used1")?,
+            total: fields.next_named("total1")?,
+        };
+        let mut usage = || -> Result<Option<StorageUsage>, HayesError> {
+            let Some(used) = fields.optional()? else {
+                return Ok(None);
+            };
+            Ok(Some(StorageUsage {
+                used,
+                total: fields.next()?,
+            }))
+        };
+        let write = usage()?;
+        let receive = usage()?;
+        reader.finish()?;
+
+        Ok(CpmsResponse {
+            read,
+            write,
+            receive,
+        })
+    }
+}
+
+/// `AT+CPMS?`, reading the selected storages and their usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CpmsQuery;
+
+impl AtCommand for CpmsQuery {
+    type Response<'at> = CpmsQueryResponse;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        CommandWriter::new(buffer, "+CPMS?")?.finish()
+    }
+}
+
+/// Response to `+CPMS?`, each selected storage with its usage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CpmsQueryResponse {
+    /// Storage messages are read from
+    pub read: (Storage, StorageUsage),
+    /// Storage messages are written to
+    pub write: (Storage, StorageUsage),
+    /// Storage received messages are placed in
+    pub receive: (Storage, StorageUsage),
+}
+
+impl<'at> AtResponse<'at> for CpmsQueryResponse {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let mut fields = reader.prefixed("+CPMS")?;
+
+        let mut storage = || -> Result<(Storage, StorageUsage), HayesError> {
+            Ok((
+                fields.next_named("mem")?,
+                StorageUsage {
+                    used: fields.next_named("used")?,
+                    total: fields.next_named("total")?,
+                },
+            ))
+        };
+        let read = storage()?;
+        let write = storage()?;
+        let receive = storage()?;
+        reader.finish()?;
+
+        Ok(CpmsQueryResponse {
+            read,
+            write,
+            receive,
+        })
+    }
+}
+
+/// How new message indications are buffered, the `<mode>` of `+CNMI`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum IndicationMode {
+    /// Buffer indications in the TA, discarding the oldest when full
+    Buffer = 0,
+    /// Discard indications while the link is reserved, such as in data mode
+    Discard = 1,
+    /// Buffer indications while the link is reserved and flush them afterwards
+    BufferWhenReserved = 2,
+    /// Forward indications directly, even in data mode
+    Forward = 3,
+}
+
+/// How received messages are indicated, the `<mt>` of `+CNMI`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MessageIndication {
+    /// No indication
+    None = 0,
+    /// Store the message and indicate where with [`Cmti`]
+    Stored = 1,
+    /// Route the message directly to the TE with `+CMT`
+    Direct = 2,
+    /// Route class 3 messages directly and indicate others with [`Cmti`]
+    DirectClass3 = 3,
+}
+
+impl_indication!(
+    IndicationMode {
+        0 => Buffer,
+        1 => Discard,
+        2 => BufferWhenReserved,
+        3 => Forward,
+    },
+    MessageIndication {
+        0 => None,
+        1 => Stored,
+        2 => Direct,
+        3 => DirectClass3,
+    },
+);
+
+/// `AT+CNMI=[<mode>[,<mt>[,<bm>[,<ds>[,<bfr>]]]]]`, selecting how new message
+/// indications are sent to the TE
+///
+/// Omitted parameters keep their current value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cnmi {
+    /// How indications are buffered
+    pub mode: Option<IndicationMode>,
+    /// How received messages are indicated
+    pub mt: Option<MessageIndication>,
+    /// How cell broadcast messages are indicated, 0–3
+    pub bm: Option<u8>,
+    /// How status reports are indicated, 0–2
+    pub ds: Option<u8>,
+    /// Whether buffered indications are flushed (0) or cleared (1) when
+    /// `<mode>` 1–3 is entered
+    pub bfr: Option<u8>,
+}
+
+impl AtCommand for Cnmi {
+    type Response<'at> = ();
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CNMI")?;
+        writer.param(&self.mode)?;
+        writer.param(&self.mt)?;
+        writer.param(&self.bm)?;
+        writer.param(&self.ds)?;
+        writer.param(&self.bfr)?;
+        writer.finish()
+    }
+}
+
+/// Which messages `+CMGD` deletes, the `<delflag>`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DeleteFlag {
+    /// Only the message at the given index
+    Index = 0,
+    /// All read messages
+    Read = 1,
+    /// All read and sent messages
+    ReadAndSent = 2,
+    /// All read, sent and unsent messages
+    ReadSentAndUnsent = 3,
+    /// All messages
+    All = 4,
+}
+
+impl_indication!(DeleteFlag {
+    0 => Index,
+    1 => Read,
+    2 => ReadAndSent,
+    3 => ReadSentAndUnsent,
+    4 => All,
+});
+
+/// `AT+CMGD=<index>[,<delflag>]`, deleting messages from the read storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmgd {
+    /// Index of the message, ignored by flags other than
+    /// [`DeleteFlag::Index`]
+    pub index: u16,
+    /// Which messages to delete, the message at `index` if omitted
+    pub flag: Option<DeleteFlag>,
+}
+
+impl AtCommand for Cmgd {
+    type Response<'at> = ();
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CMGD")?;
+        writer.param(&self.index)?;
+        writer.param(&self.flag)?;
+        writer.finish()
+    }
+}
+
+/// Status of a stored message, the `<stat>` of `+CMGL` and `+CMGR`
+///
+/// Read from its text mode name, such as `"REC UNREAD"`, or its PDU mode
+/// number. Written as its text mode name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MessageStatus {
+    /// Received and not yet read
+    ReceivedUnread = 0,
+    /// Received and read
+    ReceivedRead = 1,
+    /// Stored and not yet sent
+    StoredUnsent = 2,
+    /// Stored and sent
+    StoredSent = 3,
+    /// Every message, only used to list messages
+    All = 4,
+}
+
+impl MessageStatus {
+    /// The text mode name, such as `"REC UNREAD"`
+    #[must_use]
+    pub const fn name(self) -> &'static str {
+        match self {
+            MessageStatus::ReceivedUnread => "REC UNREAD",
+            MessageStatus::ReceivedRead => "REC READ",
+            MessageStatus::StoredUnsent => "STO UNSENT",
+            MessageStatus::StoredSent => "STO SENT",
+            MessageStatus::All => "ALL",
+        }
+    }
+
+    /// Whether the message was received, rather than stored to be sent
+    #[must_use]
+    pub const fn is_received(self) -> bool {
+        matches!(
+            self,
+            MessageStatus::ReceivedUnread | MessageStatus::ReceivedRead
+        )
+    }
+}
+
+impl<'at> AtReadable<'at> for MessageStatus {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        if input.first() != Some(&b'"') {
+            let (value, consumed) = u8::read(input)?;
+            let status = match value {
+                0 => MessageStatus::ReceivedUnread,
+                1 => MessageStatus::ReceivedRead,
+                2 => MessageStatus::StoredUnsent,
+                3 => MessageStatus::StoredSent,
+                4 => MessageStatus::All,
+                _ => return Err(HayesError::OutOfRange),
+            };
+            return Ok((status, consumed));
+        }
+
+        let (name, consumed) = <&str>::read(input)?;
+        let status = match name {
+            "REC UNREAD" => MessageStatus::ReceivedUnread,
+            "REC READ" => MessageStatus::ReceivedRead,
+            "STO UNSENT" => MessageStatus::StoredUnsent,
+            "STO SENT" => MessageStatus::StoredSent,
+            "ALL" => MessageStatus::All,
+            _ => return Err(HayesError::UnsupportedError),
+        };
+
+        Ok((status, consumed))
+    }
+}
+
+impl AtWritable for MessageStatus {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        self.name().write(output)
+    }
+}
+
+/// `+CMTI: <mem>,<index>`, indicating a new message was stored
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmti {
+    /// Storage the message was placed in
+    pub storage: Storage,
+    /// Index of the message in the storage
+    pub index: u16,
+}
+
+/// Reads the unsolicited result code, which isn't followed by `OK`
+impl<'at> AtResponse<'at> for Cmti {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut fields = ResponseReader::new(buffer, context).prefixed("+CMTI")?;
+
+        Ok(Cmti {
+            storage: fields.next_named("mem")?,
+            index: fields.next_named("index")?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CmsError;
+
+    fn assert_written(command: &impl AtCommand, expected: &[u8]) {
+        let mut buf = [0u8; 64];
+        let len = command.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], expected);
+    }
+
+    #[test]
+    fn test_write_commands() {
+        assert_written(
+            &Cmgf {
+                format: MessageFormat::Text,
+            },
+            b"AT+CMGF=1\r\n",
+        );
+        assert_written(
+            &Csca {
+                address: PhoneNumber::new("+15551234").unwrap(),
+            },
+            b"AT+CSCA=\"+15551234\",145\r\n",
+        );
+        assert_written(&CscaQuery, b"AT+CSCA?\r\n");
+        assert_written(
+            &Cpms {
+                read: Storage::Sim,
+                write: Some(Storage::Sim),
+                receive: None,
+            },
+            b"AT+CPMS=\"SM\",\"SM\"\r\n",
+        );
+        assert_written(&CpmsQuery, b"AT+CPMS?\r\n");
+        assert_written(
+            &Cnmi {
+                mode: Some(IndicationMode::BufferWhenReserved),
+                mt: Some(MessageIndication::Stored),
+                ds: Some(1),
+                ..Cnmi::default()
+            },
+            b"AT+CNMI=2,1,,1\r\n",
+        );
+        assert_written(
+            &Cmgd {
+                index: 3,
+                flag: None,
+            },
+            b"AT+CMGD=3\r\n",
+        );
+        assert_written(
+            &Cmgd {
+                index: 0,
+                flag: Some(DeleteFlag::All),
+            },
+            b"AT+CMGD=0,4\r\n",
+        );
+    }
+
+    #[test]
+    fn test_read_storage_responses() {
+        assert_eq!(
+            CscaResponse::read(b"\r\n+CSCA: \"+447785016005\",145\r\n\r\nOK\r\n")
+                .map(|r| r.address.number()),
+            Ok("+447785016005")
+        );
+
+        let usage = |used, total| StorageUsage { used, total };
+        assert_eq!(
+            CpmsResponse::read(b"\r\n+CPMS: 2,30,2,30,2,30\r\n\r\nOK\r\n"),
+            Ok(CpmsResponse {
+                read: usage(2, 30),
+                write: Some(usage(2, 30)),
+                receive: Some(usage(2, 30)),
+            })
+        );
+        assert_eq!(
+            CpmsResponse::read(b"\r\n+CPMS: 0,20\r\n\r\nOK\r\n"),
+            Ok(CpmsResponse {
+                read: usage(0, 20),
+                write: None,
+                receive: None,
+            })
+        );
+        assert_eq!(
+            CpmsQueryResponse::read(
+                b"\r\n+CPMS: \"SM\",2,30,\"ME\",0,100,\"MT\",2,130\r\n\r\nOK\r\n"
+            ),
+            Ok(CpmsQueryResponse {
+                read: (Storage::Sim, usage(2, 30)),
+                write: (Storage::Me, usage(0, 100)),
+                receive: (Storage::Mt, usage(2, 130)),
+            })
+        );
+        assert_eq!(
+            CpmsQueryResponse::read(b"\r\n+CPMS: \"XX\",2,30\r\n\r\nOK\r\n"),
+            Err(HayesError::UnsupportedError)
+        );
+    }
+
+    #[test]
+    fn test_message_status() {
+        assert_eq!(
+            MessageStatus::read(b"\"REC UNREAD\","),
+            Ok((MessageStatus::ReceivedUnread, 12))
+        );
+        assert_eq!(
+            MessageStatus::read(b"3,"),
+            Ok((MessageStatus::StoredSent, 1))
+        );
+        assert_eq!(MessageStatus::read(b"5"), Err(HayesError::OutOfRange));
+
+        let mut buf = [0u8; 16];
+        assert_eq!(MessageStatus::All.write(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"\"ALL\"");
+    }
+
+    #[test]
+    fn test_read_flags() {
+        assert_eq!(MessageFormat::read(b"1"), Ok((MessageFormat::Text, 1)));
+        assert_eq!(MessageFormat::read(b"2"), Err(HayesError::OutOfRange));
+        assert_eq!(DeleteFlag::read(b"4,"), Ok((DeleteFlag::All, 1)));
+        assert_eq!(DeleteFlag::read(b"5"), Err(HayesError::OutOfRange));
+    }
+
+    #[test]
+    fn test_read_cmti() {
+        assert_eq!(
+            Cmti::read(b"\r\n+CMTI: \"SM\",7\r\n"),
+            Ok(Cmti {
+                storage: Storage::Sim,
+                index: 7,
+            })
+        );
+        assert_eq!(
+            Cmti::read(b"\r\n+CMT: \"+1555\",,\"24/01/02,03:04:05+00\"\r\n"),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_is_prompt() {
+        assert!(is_prompt(b"\r\n> "));
+        assert!(is_prompt(b">"));
+        assert!(!is_prompt(b"\r\nERROR\r\n"));
+        assert!(!is_prompt(b""));
+    }
+
+    #[test]
+    fn test_read_cmgs_response() {
+        assert_eq!(
+            CmgsResponse::read(b"\r\n+CMGS: 17\r\n\r\nOK\r\n"),
+            Ok(CmgsResponse {
+                message_reference: 17
+            })
+        );
+        // Some modems add the SMSC time stamp or acknowledgement PDU
+        assert_eq!(
+            CmgsResponse::read(b"\r\n+CMGS: 3,\"24/01/02,03:04:05+00\"\r\n\r\nOK\r\n")
+                .map(|r| r.message_reference),
+            Ok(3)
+        );
+        assert_eq!(
+            CmgsResponse::read(b"\r\n+CMS ERROR: 304\r\n"),
+            Err(HayesError::CmsError(CmsError::InvalidPduParameter))
+        );
+    }
+}
@@ -0,0 +1,1776 @@
+//! SMS transfer protocol data units (3GPP TS 23.040 §9.2)
+//!
+//! In PDU mode, `+CMGR`, `+CMGL` and `+CMT` report messages as hex digits and
+//! `+CMGS` takes them the same way. Each PDU is the SMSC address followed by
+//! the TPDU. Received PDUs are decoded in place, borrowing the hex digits from
+//! the input; outgoing PDUs are encoded straight into the output buffer.
+//!
+//! `AT+CMGS=<length>` takes the length of the TPDU alone, which is available
+//! from [`SmsSubmit::tpdu_len`].
+
+use core::time::Duration;
+
+use crate::{
+    AtCommand, AtReadable, AtWritable, HayesError, HexBytes, HexBytesIter, ModemDateTime, TimeZone,
+    TypeOfAddress,
+    at::CommandWriter,
+    charset::gsm7,
+    sms::{CTRL_Z, CmgsResponse},
+    types::hex::DIGITS,
+};
+
+/// Message type indicator of an SMS-DELIVER
+const MTI_DELIVER: u8 = 0b00;
+/// Message type indicator of an SMS-SUBMIT
+const MTI_SUBMIT: u8 = 0b01;
+/// Message type indicator of an SMS-STATUS-REPORT
+const MTI_STATUS_REPORT: u8 = 0b10;
+
+/// First octet flag: more messages to send (inverted), or reject duplicates
+const FLAG_MMS_RD: u8 = 1 << 2;
+/// First octet flag: status report requested, indicated or qualified
+const FLAG_STATUS_REPORT: u8 = 1 << 5;
+/// First octet flag: user data header indicator
+const FLAG_UDHI: u8 = 1 << 6;
+/// First octet flag: reply path
+const FLAG_REPLY_PATH: u8 = 1 << 7;
+
+/// Maximum length of the user data in octets
+const MAX_USER_DATA_OCTETS: usize = 140;
+/// Maximum length of the user data in septets
+const MAX_USER_DATA_SEPTETS: usize = 160;
+
+/// Cursor over the octets of a PDU
+struct Reader<'a> {
+    pdu: HexBytes<'a>,
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(pdu: HexBytes<'a>) -> Self {
+        Reader { pdu, pos: 0 }
+    }
+
+    fn octet(&mut self) -> Result<u8, HayesError> {
+        let octet = self.pdu.get(self.pos).ok_or(HayesError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(octet)
+    }
+
+    fn take(&mut self, len: usize) -> Result<HexBytes<'a>, HayesError> {
+        let octets = self
+            .pdu
+            .slice(self.pos..self.pos + len)
+            .ok_or(HayesError::UnexpectedEnd)?;
+        self.pos += len;
+        Ok(octets)
+    }
+}
+
+/// Octet writer that keeps counting past the end of its buffer
+///
+/// Encoding into an empty buffer measures the encoded length.
+struct Writer<'o> {
+    output: &'o mut [u8],
+    pos: usize,
+}
+
+impl<'o> Writer<'o> {
+    fn new(output: &'o mut [u8]) -> Self {
+        Writer { output, pos: 0 }
+    }
+
+    fn push(&mut self, octet: u8) {
+        if let Some(slot) = self.output.get_mut(self.pos) {
+            *slot = octet;
+        }
+        self.pos += 1;
+    }
+
+    fn extend(&mut self, octets: impl IntoIterator<Item = u8>) {
+        for octet in octets {
+            self.push(octet);
+        }
+    }
+
+    fn finish(self) -> Result<usize, HayesError> {
+        if self.pos > self.output.len() {
+            return Err(HayesError::InsufficientBuffer {
+                required: self.pos,
+                available: self.output.len(),
+            });
+        }
+
+        Ok(self.pos)
+    }
+}
+
+/// Number of octets produced by `encode`
+fn measure(
+    encode: impl Fn(&mut Writer<'_>) -> Result<(), HayesError>,
+) -> Result<usize, HayesError> {
+    let mut writer = Writer::new(&mut []);
+    encode(&mut writer)?;
+    Ok(writer.pos)
+}
+
+/// Encode into `output` as binary octets
+fn encode_binary(
+    output: &mut [u8],
+    encode: impl Fn(&mut Writer<'_>) -> Result<(), HayesError>,
+) -> Result<usize, HayesError> {
+    let mut writer = Writer::new(output);
+    encode(&mut writer)?;
+    writer.finish()
+}
+
+/// Encode into `output` as hex digits
+fn encode_hex(
+    output: &mut [u8],
+    encode: impl Fn(&mut Writer<'_>) -> Result<(), HayesError>,
+) -> Result<usize, HayesError> {
+    let len = measure(&encode)?;
+    let required = len * 2;
+    if output.len() < required {
+        return Err(HayesError::InsufficientBuffer {
+            required,
+            available: output.len(),
+        });
+    }
+
+    encode_binary(&mut output[..len], encode)?;
+
+    // Expand from the back so that no octet is overwritten before it's read
+    for i in (0..len).rev() {
+        let octet = output[i];
+        output[i * 2] = DIGITS[usize::from(octet >> 4)];
+        output[i * 2 + 1] = DIGITS[usize::from(octet & 0xF)];
+    }
+
+    Ok(required)
+}
+
+/// Pack a stream of septets after `padding` fill bits
+fn pack_septets(writer: &mut Writer<'_>, septets: impl Iterator<Item = u8>, padding: u8) {
+    let mut bits = 0u16;
+    let mut available = padding;
+
+    for septet in septets {
+        bits |= u16::from(septet & 0x7F) << available;
+        available += 7;
+
+        while available >= 8 {
+            #[allow(clippy::cast_possible_truncation)]
+            writer.push(bits as u8);
+            bits >>= 8;
+            available -= 8;
+        }
+    }
+
+    if available > 0 {
+        #[allow(clippy::cast_possible_truncation)]
+        writer.push(bits as u8);
+    }
+}
+
+/// Unpacked septets of text already known to be in the GSM alphabet
+fn gsm7_septets(text: &str) -> impl Iterator<Item = u8> + '_ {
+    text.chars()
+        .filter_map(gsm7::encode_char)
+        .flat_map(|(septets, len)| septets.into_iter().take(len))
+}
+
+/// Semi-octet value of a dial character
+const fn semi_octet(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'*' => Some(0xA),
+        b'#' => Some(0xB),
+        b'a' | b'A' => Some(0xC),
+        b'b' | b'B' => Some(0xD),
+        b'c' | b'C' => Some(0xE),
+        _ => None,
+    }
+}
+
+/// Dial character of a semi-octet value, or `None` for the filler
+const fn dial_character(semi_octet: u8) -> Option<char> {
+    match semi_octet {
+        0..=9 => Some((b'0' + semi_octet) as char),
+        0xA => Some('*'),
+        0xB => Some('#'),
+        0xC => Some('a'),
+        0xD => Some('b'),
+        0xE => Some('c'),
+        _ => None,
+    }
+}
+
+/// Decode a semi-octet swapped BCD value, such as `0x21` for 12
+const fn swapped_bcd(octet: u8) -> Result<u8, HayesError> {
+    let (tens, units) = (octet & 0xF, octet >> 4);
+    if tens > 9 || units > 9 {
+        return Err(HayesError::InvalidFormat);
+    }
+
+    Ok(tens * 10 + units)
+}
+
+/// Encode a value below 100 as semi-octet swapped BCD
+const fn to_swapped_bcd(value: u8) -> u8 {
+    ((value % 10) << 4) | (value / 10)
+}
+
+/// Type of address used for alphanumeric addresses
+const ALPHANUMERIC: TypeOfAddress = TypeOfAddress(0b1101_0000);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum AddressRepr<'a> {
+    /// Semi-octets, or packed septets for alphanumeric addresses
+    Encoded {
+        value: HexBytes<'a>,
+        len: usize,
+    },
+    Text(&'a str),
+}
+
+/// Originating, destination or service centre address
+///
+/// Numbers are encoded as semi-octets. Alphanumeric addresses, used by some
+/// senders in place of a number, are encoded in the GSM alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Address<'a> {
+    type_of_address: TypeOfAddress,
+    repr: AddressRepr<'a>,
+}
+
+impl<'a> Address<'a> {
+    /// Create an address from a number, which is international if it starts
+    /// with `+`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if the number contains anything
+    /// other than the dial characters `0-9*#abc`, or
+    /// [`HayesError::OutOfRange`] if it's longer than 20 digits.
+    pub fn new(number: &'a str) -> Result<Self, HayesError> {
+        match number.strip_prefix('+') {
+            Some(number) => Address::with_type(number, TypeOfAddress::INTERNATIONAL),
+            None => Address::with_type(number, TypeOfAddress::UNKNOWN),
+        }
+    }
+
+    /// Create an address with an explicit type of address
+    ///
+    /// # Errors
+    ///
+    /// Returns the same errors as [`Address::new`], or as
+    /// [`Address::alphanumeric`] if the type of number is alphanumeric.
+    pub fn with_type(number: &'a str, type_of_address: TypeOfAddress) -> Result<Self, HayesError> {
+        if type_of_address.type_of_number() == ALPHANUMERIC.type_of_number() {
+            return Address::alphanumeric(number);
+        }
+
+        if !number.bytes().all(|b| semi_octet(b).is_some()) {
+            return Err(HayesError::InvalidFormat);
+        }
+        if number.len() > 20 {
+            return Err(HayesError::OutOfRange);
+        }
+
+        Ok(Address {
+            type_of_address,
+            repr: AddressRepr::Text(number),
+        })
+    }
+
+    /// Create an alphanumeric address, such as a sender name
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `text` isn't in the GSM alphabet
+    /// or is longer than 11 septets.
+    pub fn alphanumeric(text: &'a str) -> Result<Self, HayesError> {
+        if gsm7::encoded_len(text)? > 11 {
+            return Err(HayesError::OutOfRange);
+        }
+
+        Ok(Address {
+            type_of_address: ALPHANUMERIC,
+            repr: AddressRepr::Text(text),
+        })
+    }
+
+    /// The type of address
+    #[must_use]
+    pub const fn type_of_address(&self) -> TypeOfAddress {
+        self.type_of_address
+    }
+
+    /// Whether the address is alphanumeric rather than a number
+    #[must_use]
+    pub const fn is_alphanumeric(&self) -> bool {
+        self.type_of_address.type_of_number() == ALPHANUMERIC.type_of_number()
+    }
+
+    /// Iterate over the characters of the address
+    ///
+    /// International numbers start with `+`.
+    #[must_use]
+    pub fn chars(&self) -> AddressChars<'a> {
+        let plus = !self.is_alphanumeric() && self.type_of_address.is_international();
+
+        let repr = match self.repr {
+            AddressRepr::Encoded { value, len } if self.is_alphanumeric() => {
+                AddressCharsRepr::Alphanumeric(gsm7::Chars::new(gsm7::unpacked(
+                    value,
+                    0,
+                    len * 4 / 7,
+                )))
+            }
+            AddressRepr::Encoded { value, len } => AddressCharsRepr::Digits {
+                value,
+                index: 0,
+                len,
+            },
+            AddressRepr::Text(text) => AddressCharsRepr::Text(text.chars()),
+        };
+
+        AddressChars { plus, repr }
+    }
+
+    /// Decode the address into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        let required = self.chars().map(char::len_utf8).sum();
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        let mut pos = 0;
+        for c in self.chars() {
+            pos += c.encode_utf8(&mut buffer[pos..]).len();
+        }
+
+        core::str::from_utf8(&buffer[..pos]).map_err(|_| HayesError::InvalidUtf8)
+    }
+
+    /// Number of useful semi-octets in the value
+    fn semi_octets(&self) -> usize {
+        match self.repr {
+            AddressRepr::Encoded { len, .. } => len,
+            AddressRepr::Text(text) if self.is_alphanumeric() => {
+                (gsm7::encoded_len(text).unwrap_or_default() * 7).div_ceil(4)
+            }
+            AddressRepr::Text(text) => text.len(),
+        }
+    }
+
+    /// Read a TP-OA, TP-DA or TP-RA address
+    fn read(reader: &mut Reader<'a>) -> Result<Self, HayesError> {
+        let len = usize::from(reader.octet()?);
+        if len > 20 {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        let type_of_address = TypeOfAddress(reader.octet()?);
+        let value = reader.take(len.div_ceil(2))?;
+
+        Ok(Address {
+            type_of_address,
+            repr: AddressRepr::Encoded { value, len },
+        })
+    }
+
+    /// Read the SMSC address, which is absent if its length is zero
+    fn read_smsc(reader: &mut Reader<'a>) -> Result<Option<Self>, HayesError> {
+        let len = usize::from(reader.octet()?);
+        if len == 0 {
+            return Ok(None);
+        }
+
+        let type_of_address = TypeOfAddress(reader.octet()?);
+        let value = reader.take(len - 1)?;
+
+        // An odd number of digits is padded with a filler semi-octet, and a
+        // length of one is the type of address without any digits
+        let filler = len
+            .checked_sub(2)
+            .and_then(|last| value.get(last))
+            .is_some_and(|octet| octet >> 4 == 0xF);
+
+        Ok(Some(Address {
+            type_of_address,
+            repr: AddressRepr::Encoded {
+                value,
+                len: (len - 1) * 2 - usize::from(filler),
+            },
+        }))
+    }
+
+    /// Write the type of address and value
+    fn encode_value(&self, writer: &mut Writer<'_>) {
+        writer.push(self.type_of_address.0);
+
+        match self.repr {
+            AddressRepr::Encoded { value, .. } => writer.extend(value),
+            AddressRepr::Text(text) if self.is_alphanumeric() => {
+                pack_septets(writer, gsm7_septets(text), 0);
+            }
+            AddressRepr::Text(text) => {
+                for pair in text.as_bytes().chunks(2) {
+                    let low = semi_octet(pair[0]).unwrap_or_default();
+                    let high = pair.get(1).and_then(|&b| semi_octet(b)).unwrap_or(0xF);
+                    writer.push((high << 4) | low);
+                }
+            }
+        }
+    }
+
+    /// Write a TP-OA, TP-DA or TP-RA address
+    fn encode(&self, writer: &mut Writer<'_>) {
+        #[allow(clippy::cast_possible_truncation)]
+        writer.push(self.semi_octets() as u8);
+        self.encode_value(writer);
+    }
+
+    /// Write the SMSC address, or a zero length to use the stored one
+    fn encode_smsc(smsc: Option<&Self>, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        let Some(smsc) = smsc else {
+            writer.push(0);
+            return Ok(());
+        };
+
+        if smsc.is_alphanumeric() {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        #[allow(clippy::cast_possible_truncation)]
+        writer.push(1 + smsc.semi_octets().div_ceil(2) as u8);
+        smsc.encode_value(writer);
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+enum AddressCharsRepr<'a> {
+    Digits {
+        value: HexBytes<'a>,
+        index: usize,
+        len: usize,
+    },
+    Alphanumeric(gsm7::Chars<gsm7::Unpacked<HexBytesIter<'a>>>),
+    Text(core::str::Chars<'a>),
+}
+
+/// Iterator over the characters of an [`Address`]
+#[derive(Debug, Clone)]
+pub struct AddressChars<'a> {
+    plus: bool,
+    repr: AddressCharsRepr<'a>,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for AddressChars<'_> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        crate::charset::format_chars(f, "AddressChars", self.clone());
+    }
+}
+
+impl Iterator for AddressChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        if self.plus {
+            self.plus = false;
+            return Some('+');
+        }
+
+        match &mut self.repr {
+            AddressCharsRepr::Digits { value, index, len } => loop {
+                if *index >= *len {
+                    return None;
+                }
+
+                let octet = value.get(*index / 2)?;
+                let semi_octet = if *index % 2 == 0 {
+                    octet & 0xF
+                } else {
+                    octet >> 4
+                };
+                *index += 1;
+
+                if let Some(c) = dial_character(semi_octet) {
+                    return Some(c);
+                }
+            },
+            AddressCharsRepr::Alphanumeric(chars) => chars.next(),
+            AddressCharsRepr::Text(chars) => chars.next(),
+        }
+    }
+}
+
+/// Character set of the user data
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Alphabet {
+    /// GSM 7-bit default alphabet, packed into octets
+    Gsm7,
+    /// 8-bit binary data
+    EightBit,
+    /// UCS2, as big-endian UTF-16
+    Ucs2,
+}
+
+/// TP-Data-Coding-Scheme (3GPP TS 23.038 §4)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Dcs(pub u8);
+
+impl Dcs {
+    /// GSM 7-bit default alphabet, no message class
+    pub const GSM7: Self = Dcs(0x00);
+    /// 8-bit data, no message class
+    pub const EIGHT_BIT: Self = Dcs(0x04);
+    /// UCS2, no message class
+    pub const UCS2: Self = Dcs(0x08);
+
+    /// Whether this is one of the general data coding groups
+    const fn is_general(self) -> bool {
+        self.0 & 0b1000_0000 == 0
+    }
+
+    /// The character set of the user data
+    ///
+    /// Reserved codings are treated as the GSM 7-bit default alphabet, as
+    /// required by the specification.
+    #[must_use]
+    pub const fn alphabet(self) -> Alphabet {
+        match self.0 >> 4 {
+            0b0000..=0b0111 => match (self.0 >> 2) & 0b11 {
+                0b01 => Alphabet::EightBit,
+                0b10 => Alphabet::Ucs2,
+                _ => Alphabet::Gsm7,
+            },
+            0b1110 => Alphabet::Ucs2,
+            0b1111 if self.0 & 0b100 != 0 => Alphabet::EightBit,
+            _ => Alphabet::Gsm7,
+        }
+    }
+
+    /// The message class (0–3), if any
+    ///
+    /// Class 0 messages are displayed immediately and not stored.
+    #[must_use]
+    pub const fn message_class(self) -> Option<u8> {
+        let has_class = match self.0 >> 4 {
+            0b0000..=0b0111 => self.0 & 0b1_0000 != 0,
+            0b1111 => true,
+            _ => false,
+        };
+
+        if has_class { Some(self.0 & 0b11) } else { None }
+    }
+
+    /// Whether the user data is compressed
+    #[must_use]
+    pub const fn is_compressed(self) -> bool {
+        self.is_general() && self.0 & 0b10_0000 != 0
+    }
+}
+
+/// Decode a service centre time stamp (TS 23.040 §9.2.3.11)
+fn read_timestamp(reader: &mut Reader<'_>) -> Result<ModemDateTime, HayesError> {
+    let mut fields = [0u8; 6];
+    for field in &mut fields {
+        *field = swapped_bcd(reader.octet()?)?;
+    }
+
+    // The sign is carried in the third bit of the tens digit
+    let zone = reader.octet()?;
+    let quarters = swapped_bcd(zone & !0x08)?;
+    #[allow(clippy::cast_possible_wrap)]
+    let quarters = if zone & 0x08 == 0 {
+        quarters as i8
+    } else {
+        -(quarters as i8)
+    };
+
+    let [year, month, day, hour, minute, second] = fields;
+    let timestamp = ModemDateTime {
+        year: 2000 + u16::from(year),
+        month,
+        day,
+        hour,
+        minute,
+        second,
+        time_zone: Some(TimeZone::from_quarter_hours(quarters)?),
+    };
+    timestamp.validate()?;
+
+    Ok(timestamp)
+}
+
+/// Encode a service centre time stamp, treating a missing time zone as UTC
+fn encode_timestamp(timestamp: &ModemDateTime, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+    timestamp.validate()?;
+    if !(2000..=2099).contains(&timestamp.year) {
+        return Err(HayesError::OutOfRange);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    let year = (timestamp.year - 2000) as u8;
+    for field in [
+        year,
+        timestamp.month,
+        timestamp.day,
+        timestamp.hour,
+        timestamp.minute,
+        timestamp.second,
+    ] {
+        writer.push(to_swapped_bcd(field));
+    }
+
+    let quarters = timestamp.time_zone.map_or(0, TimeZone::quarter_hours);
+    let sign = if quarters < 0 { 0x08 } else { 0 };
+    writer.push(to_swapped_bcd(quarters.unsigned_abs()) | sign);
+
+    Ok(())
+}
+
+/// TP-Validity-Period of an SMS-SUBMIT
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ValidityPeriod {
+    /// Relative to when the message was received by the SMSC
+    Relative(u8),
+    /// Until a point in time
+    Absolute(ModemDateTime),
+    /// Enhanced format (TS 23.040 §9.2.3.12.3), as raw octets
+    Enhanced([u8; 7]),
+}
+
+impl ValidityPeriod {
+    /// The duration of a relative validity period
+    #[must_use]
+    pub const fn to_duration(&self) -> Option<Duration> {
+        let ValidityPeriod::Relative(value) = *self else {
+            return None;
+        };
+
+        let value = value as u64;
+        let minutes = match value {
+            0..=143 => (value + 1) * 5,
+            144..=167 => 12 * 60 + (value - 143) * 30,
+            168..=196 => (value - 166) * 24 * 60,
+            _ => (value - 192) * 7 * 24 * 60,
+        };
+
+        Some(Duration::from_secs(minutes * 60))
+    }
+
+    /// TP-Validity-Period-Format bits of the first octet
+    const fn format(validity_period: Option<&Self>) -> u8 {
+        let format = match validity_period {
+            None => 0b00,
+            Some(ValidityPeriod::Enhanced(_)) => 0b01,
+            Some(ValidityPeriod::Relative(_)) => 0b10,
+            Some(ValidityPeriod::Absolute(_)) => 0b11,
+        };
+
+        format << 3
+    }
+
+    fn read(reader: &mut Reader<'_>, first_octet: u8) -> Result<Option<Self>, HayesError> {
+        let validity_period = match (first_octet >> 3) & 0b11 {
+            0b00 => return Ok(None),
+            0b01 => {
+                let mut octets = [0u8; 7];
+                for octet in &mut octets {
+                    *octet = reader.octet()?;
+                }
+                ValidityPeriod::Enhanced(octets)
+            }
+            0b10 => ValidityPeriod::Relative(reader.octet()?),
+            _ => ValidityPeriod::Absolute(read_timestamp(reader)?),
+        };
+
+        Ok(Some(validity_period))
+    }
+
+    fn encode(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        match self {
+            ValidityPeriod::Relative(value) => writer.push(*value),
+            ValidityPeriod::Absolute(timestamp) => encode_timestamp(timestamp, writer)?,
+            ValidityPeriod::Enhanced(octets) => writer.extend(*octets),
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum UserDataRepr<'a> {
+    /// TP-UDL and TP-UD, including any header
+    Encoded {
+        len: u8,
+        data: HexBytes<'a>,
+    },
+    Text(&'a str),
+    Bytes(&'a [u8]),
+}
+
+/// TP-User-Data, with an optional user data header
+///
+/// When read, the user data is borrowed from the PDU and decoded with
+/// [`UserData::chars`] or [`UserData::decode_into`]. When created from text,
+/// the text is encoded as the PDU is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UserData<'a> {
+    dcs: Dcs,
+    header: Option<HexBytes<'a>>,
+    repr: UserDataRepr<'a>,
+}
+
+impl<'a> UserData<'a> {
+    /// Text in the GSM alphabet if possible, otherwise UCS2
+    #[must_use]
+    pub fn text(text: &'a str) -> Self {
+        UserData::gsm7(text).unwrap_or_else(|_| UserData::ucs2(text))
+    }
+
+    /// Text in the GSM 7-bit default alphabet
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `text` contains a character that
+    /// isn't in the alphabet.
+    pub fn gsm7(text: &'a str) -> Result<Self, HayesError> {
+        gsm7::encoded_len(text)?;
+
+        Ok(UserData {
+            dcs: Dcs::GSM7,
+            header: None,
+            repr: UserDataRepr::Text(text),
+        })
+    }
+
+    /// Text in UCS2
+    #[must_use]
+    pub const fn ucs2(text: &'a str) -> Self {
+        UserData {
+            dcs: Dcs::UCS2,
+            header: None,
+            repr: UserDataRepr::Text(text),
+        }
+    }
+
+    /// 8-bit binary data
+    #[must_use]
+    pub const fn binary(data: &'a [u8]) -> Self {
+        UserData {
+            dcs: Dcs::EIGHT_BIT,
+            header: None,
+            repr: UserDataRepr::Bytes(data),
+        }
+    }
+
+    /// Add a user data header, given as its information elements without the
+    /// length octet
+    #[must_use]
+    pub const fn with_header(mut self, header: HexBytes<'a>) -> Self {
+        self.header = Some(header);
+        self
+    }
+
+    /// The data coding scheme
+    #[must_use]
+    pub const fn dcs(&self) -> Dcs {
+        self.dcs
+    }
+
+    /// The information elements of the user data header, if present
+    #[must_use]
+    pub const fn header(&self) -> Option<HexBytes<'a>> {
+        self.header
+    }
+
+    /// Length of the user data header in octets, including its length octet
+    fn header_octets(&self) -> usize {
+        self.header.map_or(0, |header| 1 + header.len())
+    }
+
+    /// Number of septets taken up by the user data header in GSM 7-bit data
+    fn header_septets(&self) -> usize {
+        (self.header_octets() * 8).div_ceil(7)
+    }
+
+    /// Fill bits between the user data header and the first septet
+    fn padding(&self) -> u8 {
+        #[allow(clippy::cast_possible_truncation)]
+        let padding = (self.header_septets() * 7 - self.header_octets() * 8) as u8;
+        padding
+    }
+
+    /// The octets following the user data header, as received or given to
+    /// [`UserData::binary`]
+    ///
+    /// GSM 7-bit data is still packed. Returns `None` for text that hasn't been
+    /// encoded yet.
+    #[must_use]
+    pub fn bytes(&self) -> Option<HexBytes<'a>> {
+        match self.repr {
+            UserDataRepr::Encoded { len, data } => {
+                let end = match self.dcs.alphabet() {
+                    Alphabet::Gsm7 => data.len(),
+                    _ => usize::from(len),
+                };
+                data.slice(self.header_octets()..end)
+            }
+            UserDataRepr::Bytes(bytes) => Some(HexBytes::new(bytes)),
+            UserDataRepr::Text(_) => None,
+        }
+    }
+
+    /// Iterate over the decoded characters
+    ///
+    /// 8-bit data isn't text; each byte is returned as the ISO 8859-1
+    /// character with the same value.
+    #[must_use]
+    pub fn chars(&self) -> UserDataChars<'a> {
+        let repr = match (self.repr, self.dcs.alphabet()) {
+            (UserDataRepr::Text(text), _) => UserDataCharsRepr::Text(text.chars()),
+            (UserDataRepr::Encoded { len, .. }, Alphabet::Gsm7) => {
+                let count = usize::from(len).saturating_sub(self.header_septets());
+                let packed = self.bytes().unwrap_or(HexBytes::new(&[]));
+                UserDataCharsRepr::Gsm7(gsm7::Chars::new(gsm7::unpacked(
+                    packed,
+                    self.padding(),
+                    count,
+                )))
+            }
+            (UserDataRepr::Encoded { .. }, Alphabet::Ucs2) => {
+                let data = self.bytes().unwrap_or(HexBytes::new(&[]));
+                UserDataCharsRepr::Ucs2(char::decode_utf16(Ucs2Units(data.iter())))
+            }
+            (UserDataRepr::Encoded { .. } | UserDataRepr::Bytes(_), _) => {
+                UserDataCharsRepr::Bytes(self.bytes().unwrap_or(HexBytes::new(&[])).iter())
+            }
+        };
+
+        UserDataChars(repr)
+    }
+
+    /// Decode the user data into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        let required = self.chars().map(char::len_utf8).sum();
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        let mut pos = 0;
+        for c in self.chars() {
+            pos += c.encode_utf8(&mut buffer[pos..]).len();
+        }
+
+        core::str::from_utf8(&buffer[..pos]).map_err(|_| HayesError::InvalidUtf8)
+    }
+
+    /// TP-User-Data-Length: septets for GSM 7-bit data, otherwise octets
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if the user data doesn't fit in a
+    /// single message.
+    pub fn len(&self) -> Result<usize, HayesError> {
+        let (len, max) = match (self.repr, self.dcs.alphabet()) {
+            (UserDataRepr::Encoded { len, .. }, _) => return Ok(usize::from(len)),
+            (UserDataRepr::Text(text), Alphabet::Gsm7) => (
+                self.header_septets() + gsm7::encoded_len(text)?,
+                MAX_USER_DATA_SEPTETS,
+            ),
+            (UserDataRepr::Text(text), Alphabet::Ucs2) => (
+                self.header_octets() + text.encode_utf16().count() * 2,
+                MAX_USER_DATA_OCTETS,
+            ),
+            (UserDataRepr::Text(text), Alphabet::EightBit) => {
+                (self.header_octets() + text.len(), MAX_USER_DATA_OCTETS)
+            }
+            (UserDataRepr::Bytes(bytes), _) => {
+                (self.header_octets() + bytes.len(), MAX_USER_DATA_OCTETS)
+            }
+        };
+
+        if len > max {
+            return Err(HayesError::OutOfRange);
+        }
+
+        Ok(len)
+    }
+
+    /// Whether there is no user data, not even a header
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == Ok(0)
+    }
+
+    fn read(reader: &mut Reader<'a>, dcs: Dcs, has_header: bool) -> Result<Self, HayesError> {
+        let len = reader.octet()?;
+        let octets = match dcs.alphabet() {
+            Alphabet::Gsm7 => gsm7::packed_len(usize::from(len), 0),
+            _ => usize::from(len),
+        };
+        let data = reader.take(octets)?;
+
+        let header = if has_header {
+            let header_len = usize::from(data.get(0).ok_or(HayesError::InvalidFormat)?);
+            Some(
+                data.slice(1..1 + header_len)
+                    .ok_or(HayesError::InvalidFormat)?,
+            )
+        } else {
+            None
+        };
+
+        let user_data = UserData {
+            dcs,
+            header,
+            repr: UserDataRepr::Encoded { len, data },
+        };
+
+        let header_len = match dcs.alphabet() {
+            Alphabet::Gsm7 => user_data.header_septets(),
+            _ => user_data.header_octets(),
+        };
+        if header_len > usize::from(len) {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        Ok(user_data)
+    }
+
+    fn encode(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        #[allow(clippy::cast_possible_truncation)]
+        let len = self.len()? as u8;
+
+        if let UserDataRepr::Encoded { data, .. } = self.repr {
+            writer.push(len);
+            writer.extend(data);
+            return Ok(());
+        }
+
+        writer.push(len);
+        if let Some(header) = self.header {
+            #[allow(clippy::cast_possible_truncation)]
+            writer.push(header.len() as u8);
+            writer.extend(header);
+        }
+
+        match (self.repr, self.dcs.alphabet()) {
+            (UserDataRepr::Text(text), Alphabet::Gsm7) => {
+                pack_septets(writer, gsm7_septets(text), self.padding());
+            }
+            (UserDataRepr::Text(text), Alphabet::Ucs2) => {
+                writer.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+            }
+            (UserDataRepr::Text(text), Alphabet::EightBit) => writer.extend(text.bytes()),
+            (UserDataRepr::Bytes(bytes), _) => writer.extend(bytes.iter().copied()),
+            (UserDataRepr::Encoded { .. }, _) => {}
+        }
+
+        Ok(())
+    }
+}
+
+/// UTF-16 code units from big-endian octet pairs
+#[derive(Debug, Clone)]
+struct Ucs2Units<'a>(HexBytesIter<'a>);
+
+impl Iterator for Ucs2Units<'_> {
+    type Item = u16;
+
+    fn next(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes([self.0.next()?, self.0.next()?]))
+    }
+}
+
+#[derive(Debug, Clone)]
+enum UserDataCharsRepr<'a> {
+    Gsm7(gsm7::Chars<gsm7::Unpacked<HexBytesIter<'a>>>),
+    Ucs2(core::char::DecodeUtf16<Ucs2Units<'a>>),
+    Bytes(HexBytesIter<'a>),
+    Text(core::str::Chars<'a>),
+}
+
+/// Iterator over the characters of [`UserData`]
+#[derive(Debug, Clone)]
+pub struct UserDataChars<'a>(UserDataCharsRepr<'a>);
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for UserDataChars<'_> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        crate::charset::format_chars(f, "UserDataChars", self.clone());
+    }
+}
+
+impl Iterator for UserDataChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match &mut self.0 {
+            UserDataCharsRepr::Gsm7(chars) => chars.next(),
+            UserDataCharsRepr::Ucs2(chars) => chars
+                .next()
+                .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER)),
+            UserDataCharsRepr::Bytes(bytes) => bytes.next().map(char::from),
+            UserDataCharsRepr::Text(chars) => chars.next(),
+        }
+    }
+}
+
+/// First octet of a TPDU, checking its message type
+fn read_first_octet(reader: &mut Reader<'_>, message_type: u8) -> Result<u8, HayesError> {
+    let first_octet = reader.octet()?;
+    if first_octet & 0b11 != message_type {
+        return Err(HayesError::InvalidFormat);
+    }
+
+    Ok(first_octet)
+}
+
+/// Set `flag` in an octet if `set`
+const fn flag(set: bool, flag: u8) -> u8 {
+    if set { flag } else { 0 }
+}
+
+/// SMS-DELIVER, a message received from the service centre
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsDeliver<'a> {
+    /// Service centre address, if included in the PDU
+    pub smsc: Option<Address<'a>>,
+    /// More messages are waiting at the service centre
+    pub more_messages: bool,
+    /// A reply path was set up by the sender
+    pub reply_path: bool,
+    /// The sender requested a status report
+    pub status_report_indication: bool,
+    /// Originating address
+    pub originator: Address<'a>,
+    /// TP-Protocol-Identifier
+    pub protocol_identifier: u8,
+    /// Time the service centre received the message
+    pub timestamp: ModemDateTime,
+    /// Message contents
+    pub user_data: UserData<'a>,
+}
+
+impl<'a> SmsDeliver<'a> {
+    /// Decode a PDU, starting with the SMSC address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU is truncated, isn't an SMS-DELIVER or has
+    /// an invalid field.
+    pub fn decode(pdu: HexBytes<'a>) -> Result<Self, HayesError> {
+        let mut reader = Reader::new(pdu);
+        let smsc = Address::read_smsc(&mut reader)?;
+        let first_octet = read_first_octet(&mut reader, MTI_DELIVER)?;
+        let originator = Address::read(&mut reader)?;
+        let protocol_identifier = reader.octet()?;
+        let dcs = Dcs(reader.octet()?);
+        let timestamp = read_timestamp(&mut reader)?;
+        let user_data = UserData::read(&mut reader, dcs, first_octet & FLAG_UDHI != 0)?;
+
+        Ok(SmsDeliver {
+            smsc,
+            more_messages: first_octet & FLAG_MMS_RD == 0,
+            reply_path: first_octet & FLAG_REPLY_PATH != 0,
+            status_report_indication: first_octet & FLAG_STATUS_REPORT != 0,
+            originator,
+            protocol_identifier,
+            timestamp,
+            user_data,
+        })
+    }
+
+    fn encode_tpdu(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        writer.push(
+            MTI_DELIVER
+                | flag(!self.more_messages, FLAG_MMS_RD)
+                | flag(self.status_report_indication, FLAG_STATUS_REPORT)
+                | flag(self.user_data.header.is_some(), FLAG_UDHI)
+                | flag(self.reply_path, FLAG_REPLY_PATH),
+        );
+        self.originator.encode(writer);
+        writer.push(self.protocol_identifier);
+        writer.push(self.user_data.dcs.0);
+        encode_timestamp(&self.timestamp, writer)?;
+        self.user_data.encode(writer)
+    }
+
+    fn encode(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        Address::encode_smsc(self.smsc.as_ref(), writer)?;
+        self.encode_tpdu(writer)
+    }
+
+    /// Length of the TPDU in octets, excluding the SMSC address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU can't be encoded.
+    pub fn tpdu_len(&self) -> Result<usize, HayesError> {
+        measure(|writer| self.encode_tpdu(writer))
+    }
+
+    /// Encode the PDU into `output` as binary octets
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU can't be encoded, or
+    /// [`HayesError::InsufficientBuffer`] if `output` is too small.
+    pub fn encode_into(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        encode_binary(output, |writer| self.encode(writer))
+    }
+}
+
+/// SMS-SUBMIT, a message sent to the service centre
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsSubmit<'a> {
+    /// Service centre address, or `None` to use the one set with `+CSCA`
+    pub smsc: Option<Address<'a>>,
+    /// Reject a message with the same reference and destination as one
+    /// still held by the service centre
+    pub reject_duplicates: bool,
+    /// Request a reply path
+    pub reply_path: bool,
+    /// Request a status report
+    pub status_report_request: bool,
+    /// TP-Message-Reference, usually assigned by the modem
+    pub message_reference: u8,
+    /// Destination address
+    pub destination: Address<'a>,
+    /// TP-Protocol-Identifier
+    pub protocol_identifier: u8,
+    /// How long the service centre should keep trying to deliver the message
+    pub validity_period: Option<ValidityPeriod>,
+    /// Message contents
+    pub user_data: UserData<'a>,
+}
+
+impl<'a> SmsSubmit<'a> {
+    /// A message to `destination` using the stored service centre address
+    #[must_use]
+    pub const fn new(destination: Address<'a>, user_data: UserData<'a>) -> Self {
+        SmsSubmit {
+            smsc: None,
+            reject_duplicates: false,
+            reply_path: false,
+            status_report_request: false,
+            message_reference: 0,
+            destination,
+            protocol_identifier: 0,
+            validity_period: None,
+            user_data,
+        }
+    }
+
+    /// Decode a PDU, starting with the SMSC address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU is truncated, isn't an SMS-SUBMIT or has
+    /// an invalid field.
+    pub fn decode(pdu: HexBytes<'a>) -> Result<Self, HayesError> {
+        let mut reader = Reader::new(pdu);
+        let smsc = Address::read_smsc(&mut reader)?;
+        let first_octet = read_first_octet(&mut reader, MTI_SUBMIT)?;
+        let message_reference = reader.octet()?;
+        let destination = Address::read(&mut reader)?;
+        let protocol_identifier = reader.octet()?;
+        let dcs = Dcs(reader.octet()?);
+        let validity_period = ValidityPeriod::read(&mut reader, first_octet)?;
+        let user_data = UserData::read(&mut reader, dcs, first_octet & FLAG_UDHI != 0)?;
+
+        Ok(SmsSubmit {
+            smsc,
+            reject_duplicates: first_octet & FLAG_MMS_RD != 0,
+            reply_path: first_octet & FLAG_REPLY_PATH != 0,
+            status_report_request: first_octet & FLAG_STATUS_REPORT != 0,
+            message_reference,
+            destination,
+            protocol_identifier,
+            validity_period,
+            user_data,
+        })
+    }
+
+    fn encode_tpdu(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        writer.push(
+            MTI_SUBMIT
+                | flag(self.reject_duplicates, FLAG_MMS_RD)
+                | ValidityPeriod::format(self.validity_period.as_ref())
+                | flag(self.status_report_request, FLAG_STATUS_REPORT)
+                | flag(self.user_data.header.is_some(), FLAG_UDHI)
+                | flag(self.reply_path, FLAG_REPLY_PATH),
+        );
+        writer.push(self.message_reference);
+        self.destination.encode(writer);
+        writer.push(self.protocol_identifier);
+        writer.push(self.user_data.dcs.0);
+        if let Some(validity_period) = &self.validity_period {
+            validity_period.encode(writer)?;
+        }
+        self.user_data.encode(writer)
+    }
+
+    fn encode(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        Address::encode_smsc(self.smsc.as_ref(), writer)?;
+        self.encode_tpdu(writer)
+    }
+
+    /// Length of the TPDU in octets, excluding the SMSC address, as passed to
+    /// `AT+CMGS=<length>`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU can't be encoded.
+    pub fn tpdu_len(&self) -> Result<usize, HayesError> {
+        measure(|writer| self.encode_tpdu(writer))
+    }
+
+    /// Encode the PDU into `output` as binary octets
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU can't be encoded, or
+    /// [`HayesError::InsufficientBuffer`] if `output` is too small.
+    pub fn encode_into(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        encode_binary(output, |writer| self.encode(writer))
+    }
+}
+
+/// SMS-STATUS-REPORT, the outcome of delivering a sent message
+///
+/// Optional parameters following the status are ignored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SmsStatusReport<'a> {
+    /// Service centre address, if included in the PDU
+    pub smsc: Option<Address<'a>>,
+    /// More messages are waiting at the service centre
+    pub more_messages: bool,
+    /// The report is for an SMS-COMMAND rather than an SMS-SUBMIT
+    pub status_report_qualifier: bool,
+    /// Reference of the message this report is for
+    pub message_reference: u8,
+    /// Recipient of the message
+    pub recipient: Address<'a>,
+    /// Time the service centre received the message
+    pub timestamp: ModemDateTime,
+    /// Time the message was delivered, or the status last changed
+    pub discharge_time: ModemDateTime,
+    /// TP-Status (TS 23.040 §9.2.3.15)
+    pub status: u8,
+}
+
+impl<'a> SmsStatusReport<'a> {
+    /// Whether the message was delivered
+    #[must_use]
+    pub const fn is_delivered(&self) -> bool {
+        self.status <= 0x02
+    }
+
+    /// Decode a PDU, starting with the SMSC address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU is truncated, isn't an SMS-STATUS-REPORT or
+    /// has an invalid field.
+    pub fn decode(pdu: HexBytes<'a>) -> Result<Self, HayesError> {
+        let mut reader = Reader::new(pdu);
+        let smsc = Address::read_smsc(&mut reader)?;
+        let first_octet = read_first_octet(&mut reader, MTI_STATUS_REPORT)?;
+        let message_reference = reader.octet()?;
+        let recipient = Address::read(&mut reader)?;
+        let timestamp = read_timestamp(&mut reader)?;
+        let discharge_time = read_timestamp(&mut reader)?;
+        let status = reader.octet()?;
+
+        Ok(SmsStatusReport {
+            smsc,
+            more_messages: first_octet & FLAG_MMS_RD == 0,
+            status_report_qualifier: first_octet & FLAG_STATUS_REPORT != 0,
+            message_reference,
+            recipient,
+            timestamp,
+            discharge_time,
+            status,
+        })
+    }
+
+    fn encode_tpdu(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        writer.push(
+            MTI_STATUS_REPORT
+                | flag(!self.more_messages, FLAG_MMS_RD)
+                | flag(self.status_report_qualifier, FLAG_STATUS_REPORT),
+        );
+        writer.push(self.message_reference);
+        self.recipient.encode(writer);
+        encode_timestamp(&self.timestamp, writer)?;
+        encode_timestamp(&self.discharge_time, writer)?;
+        writer.push(self.status);
+
+        Ok(())
+    }
+
+    fn encode(&self, writer: &mut Writer<'_>) -> Result<(), HayesError> {
+        Address::encode_smsc(self.smsc.as_ref(), writer)?;
+        self.encode_tpdu(writer)
+    }
+
+    /// Length of the TPDU in octets, excluding the SMSC address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU can't be encoded.
+    pub fn tpdu_len(&self) -> Result<usize, HayesError> {
+        measure(|writer| self.encode_tpdu(writer))
+    }
+
+    /// Encode the PDU into `output` as binary octets
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU can't be encoded, or
+    /// [`HayesError::InsufficientBuffer`] if `output` is too small.
+    pub fn encode_into(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        encode_binary(output, |writer| self.encode(writer))
+    }
+}
+
+/// Any PDU that a modem may report, told apart by its message type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Pdu<'a> {
+    /// A received message
+    Deliver(SmsDeliver<'a>),
+    /// A stored outgoing message
+    Submit(SmsSubmit<'a>),
+    /// A delivery report
+    StatusReport(SmsStatusReport<'a>),
+}
+
+impl<'a> Pdu<'a> {
+    /// Decode a PDU, starting with the SMSC address
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU is truncated, has a reserved message type or
+    /// has an invalid field.
+    pub fn decode(pdu: HexBytes<'a>) -> Result<Self, HayesError> {
+        let smsc_len = usize::from(pdu.get(0).ok_or(HayesError::UnexpectedEnd)?);
+        let first_octet = pdu.get(1 + smsc_len).ok_or(HayesError::UnexpectedEnd)?;
+
+        match first_octet & 0b11 {
+            MTI_DELIVER => SmsDeliver::decode(pdu).map(Pdu::Deliver),
+            MTI_SUBMIT => SmsSubmit::decode(pdu).map(Pdu::Submit),
+            MTI_STATUS_REPORT => SmsStatusReport::decode(pdu).map(Pdu::StatusReport),
+            _ => Err(HayesError::InvalidFormat),
+        }
+    }
+}
+
+macro_rules! impl_pdu {
+    ($($ty:ident),*) => {
+        $(
+            impl<'at> AtReadable<'at> for $ty<'at> {
+                fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+                    let (pdu, consumed) = HexBytes::read(input)?;
+                    Ok(($ty::decode(pdu)?, consumed))
+                }
+            }
+        )*
+    };
+}
+
+impl_pdu!(SmsDeliver, SmsSubmit, SmsStatusReport, Pdu);
+
+macro_rules! impl_pdu_write {
+    ($($ty:ident),*) => {
+        $(
+            impl AtWritable for $ty<'_> {
+                fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+                    encode_hex(output, |writer| self.encode(writer))
+                }
+            }
+        )*
+    };
+}
+
+impl_pdu_write!(SmsDeliver, SmsSubmit, SmsStatusReport);
+
+impl AtWritable for Pdu<'_> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        match self {
+            Pdu::Deliver(pdu) => pdu.write(output),
+            Pdu::Submit(pdu) => pdu.write(output),
+            Pdu::StatusReport(pdu) => pdu.write(output),
+        }
+    }
+}
+
+/// `AT+CMGS=<length>`, sending a message in PDU mode
+///
+/// [`AtCommand::write`] writes the command line. Once the modem shows the
+/// `> ` prompt, [`Cmgs::write_pdu`] writes the PDU and the terminating
+/// [`CTRL_Z`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmgs<'a> {
+    /// The message to send
+    pub pdu: SmsSubmit<'a>,
+}
+
+impl Cmgs<'_> {
+    /// Write the PDU as hex digits followed by [`CTRL_Z`]
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the PDU can't be encoded, or
+    /// [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn write_pdu(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let len = self.pdu.write(buffer).map_err(|error| match error {
+            HayesError::InsufficientBuffer { required, .. } => HayesError::InsufficientBuffer {
+                required: required + 1,
+                available: buffer.len(),
+            },
+            error => error,
+        })?;
+
+        let Some(slot) = buffer.get_mut(len) else {
+            return Err(HayesError::InsufficientBuffer {
+                required: len + 1,
+                available: len,
+            });
+        };
+        *slot = CTRL_Z;
+
+        Ok(len + 1)
+    }
+}
+
+impl AtCommand for Cmgs<'_> {
+    type Response<'at> = CmgsResponse;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CMGS")?;
+        writer.param(&self.pdu.tpdu_len()?)?;
+        // The modem answers with a prompt rather than waiting for a line feed
+        writer.finish_with(b"\r")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamp(fields: [u8; 6], quarters: i8) -> ModemDateTime {
+        let [year, month, day, hour, minute, second] = fields;
+        ModemDateTime {
+            year: 2000 + u16::from(year),
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            time_zone: Some(TimeZone::from_quarter_hours(quarters).unwrap()),
+        }
+    }
+
+    #[test]
+    fn test_read_deliver() {
+        let input =
+            b"07911326040000F0040B911346610089F60000208062917314080CC8F71D14969741F977FD07\r\n";
+        let (pdu, consumed) = SmsDeliver::read(input).unwrap();
+        assert_eq!(consumed, input.len() - 2);
+
+        let mut buf = [0u8; 32];
+        let smsc = pdu.smsc.unwrap();
+        assert_eq!(smsc.decode_into(&mut buf), Ok("+31624000000"));
+        assert_eq!(pdu.originator.decode_into(&mut buf), Ok("+31641600986"));
+        assert!(!pdu.more_messages);
+        assert!(!pdu.reply_path);
+        assert_eq!(pdu.protocol_identifier, 0);
+        assert_eq!(pdu.timestamp, timestamp([2, 8, 26, 19, 37, 41], 0));
+
+        assert_eq!(pdu.user_data.dcs().alphabet(), Alphabet::Gsm7);
+        assert_eq!(pdu.user_data.header(), None);
+        assert_eq!(pdu.user_data.len(), Ok(12));
+        assert_eq!(pdu.user_data.decode_into(&mut buf), Ok("How are you?"));
+    }
+
+    #[test]
+    fn test_read_deliver_alphanumeric_ucs2() {
+        // From "Info" with a time zone of -05:00
+        let input = b"000407D049B7F90D00082011216122220A0800480069002100D8";
+        let (pdu, consumed) = SmsDeliver::read(input).unwrap();
+        assert_eq!(consumed, input.len());
+
+        let mut buf = [0u8; 32];
+        assert_eq!(pdu.smsc, None);
+        assert!(pdu.originator.is_alphanumeric());
+        assert_eq!(pdu.originator.decode_into(&mut buf), Ok("Info"));
+        assert_eq!(pdu.timestamp, timestamp([2, 11, 12, 16, 22, 22], -20));
+        assert_eq!(pdu.user_data.dcs(), Dcs::UCS2);
+        assert!(pdu.user_data.chars().eq("Hi!Ø".chars()));
+
+        // Decoded PDUs are written back unchanged
+        let mut out = [0u8; 64];
+        assert_eq!(pdu.write(&mut out), Ok(consumed));
+        assert_eq!(&out[..consumed], input);
+
+        let alphanumeric = SmsDeliver {
+            originator: Address::alphanumeric("Info").unwrap(),
+            user_data: UserData::ucs2("Hi!Ø"),
+            ..pdu
+        };
+        assert_eq!(alphanumeric.write(&mut out), Ok(consumed));
+        assert_eq!(&out[..consumed], input);
+    }
+
+    #[test]
+    fn test_write_submit() {
+        let mut buf = [0u8; 64];
+
+        let mut pdu = SmsSubmit::new(
+            Address::new("+46708251358").unwrap(),
+            UserData::text("hellohello"),
+        );
+        pdu.validity_period = Some(ValidityPeriod::Relative(0xAA));
+
+        assert_eq!(pdu.tpdu_len(), Ok(23));
+        assert_eq!(pdu.write(&mut buf), Ok(48));
+        assert_eq!(
+            &buf[..48],
+            b"0011000B916407281553F80000AA0AE8329BFD4697D9EC37"
+        );
+
+        assert_eq!(
+            pdu.write(&mut buf[..47]),
+            Err(HayesError::InsufficientBuffer {
+                required: 48,
+                available: 47,
+            })
+        );
+
+        let mut octets = [0u8; 24];
+        assert_eq!(pdu.encode_into(&mut octets), Ok(24));
+        assert_eq!(&octets[..4], &[0x00, 0x11, 0x00, 0x0B]);
+    }
+
+    #[test]
+    fn test_write_cmgs() {
+        let mut buf = [0u8; 64];
+
+        let mut pdu = SmsSubmit::new(
+            Address::new("+46708251358").unwrap(),
+            UserData::text("hellohello"),
+        );
+        pdu.validity_period = Some(ValidityPeriod::Relative(0xAA));
+        let cmgs = Cmgs { pdu };
+
+        assert_eq!(cmgs.write(&mut buf), Ok(11));
+        assert_eq!(&buf[..11], b"AT+CMGS=23\r");
+
+        assert_eq!(cmgs.write_pdu(&mut buf), Ok(49));
+        assert_eq!(
+            &buf[..49],
+            b"0011000B916407281553F80000AA0AE8329BFD4697D9EC37\x1A"
+        );
+        assert_eq!(
+            cmgs.write_pdu(&mut buf[..48]),
+            Err(HayesError::InsufficientBuffer {
+                required: 49,
+                available: 48,
+            })
+        );
+    }
+
+    #[test]
+    fn test_submit_round_trip() {
+        let mut buf = [0u8; 128];
+        let mut text = [0u8; 64];
+
+        let mut pdu = SmsSubmit::new(Address::new("5551234").unwrap(), UserData::text("你好 👋"));
+        pdu.smsc = Some(Address::new("+447785016005").unwrap());
+        pdu.status_report_request = true;
+        pdu.validity_period = Some(ValidityPeriod::Absolute(timestamp(
+            [24, 2, 29, 23, 59, 0],
+            4,
+        )));
+
+        let len = pdu.write(&mut buf).unwrap();
+        let (read, consumed) = SmsSubmit::read(&buf[..len]).unwrap();
+        assert_eq!(consumed, len);
+
+        assert_eq!(
+            read.smsc.unwrap().decode_into(&mut text),
+            Ok("+447785016005")
+        );
+        assert_eq!(read.destination.decode_into(&mut text), Ok("5551234"));
+        assert!(read.status_report_request);
+        assert_eq!(read.validity_period, pdu.validity_period);
+        assert_eq!(read.user_data.dcs(), Dcs::UCS2);
+        assert_eq!(read.user_data.len(), Ok(10));
+        assert_eq!(read.user_data.decode_into(&mut text), Ok("你好 👋"));
+        assert_eq!(read.tpdu_len(), pdu.tpdu_len());
+    }
+
+    #[test]
+    fn test_user_data_header() {
+        let mut buf = [0u8; 128];
+        let mut text = [0u8; 64];
+
+        // Concatenation header: 6 octets, so one fill bit before the text
+        let header = [0x00, 0x03, 0x2A, 0x02, 0x01];
+        let pdu = SmsSubmit::new(
+            Address::new("+15551234").unwrap(),
+            UserData::text("Hi").with_header(HexBytes::new(&header)),
+        );
+        assert_eq!(pdu.user_data.len(), Ok(9));
+
+        let len = pdu.write(&mut buf).unwrap();
+        assert_eq!(&buf[len - 18..len], b"090500032A02019069");
+
+        let (read, _) = SmsSubmit::read(&buf[..len]).unwrap();
+        assert_eq!(read.user_data.header(), Some(HexBytes::new(&header)));
+        assert_eq!(read.user_data.decode_into(&mut text), Ok("Hi"));
+
+        let binary = UserData::binary(&[0xDE, 0xAD]).with_header(HexBytes::new(&header));
+        assert_eq!(binary.len(), Ok(8));
+    }
+
+    #[test]
+    fn test_status_report_round_trip() {
+        let mut buf = [0u8; 128];
+        let mut text = [0u8; 32];
+
+        let report = SmsStatusReport {
+            smsc: None,
+            more_messages: false,
+            status_report_qualifier: false,
+            message_reference: 0x2A,
+            recipient: Address::new("+31641600986").unwrap(),
+            timestamp: timestamp([24, 6, 1, 12, 0, 0], 8),
+            discharge_time: timestamp([24, 6, 1, 12, 0, 5], 8),
+            status: 0x00,
+        };
+
+        let len = report.write(&mut buf).unwrap();
+        let (read, _) = Pdu::read(&buf[..len]).unwrap();
+        let Pdu::StatusReport(read) = read else {
+            panic!("expected a status report");
+        };
+
+        assert!(read.is_delivered());
+        assert_eq!(read.message_reference, 0x2A);
+        assert_eq!(read.recipient.decode_into(&mut text), Ok("+31641600986"));
+        assert_eq!(read.discharge_time, report.discharge_time);
+        assert_eq!(read.tpdu_len(), Ok(25));
+    }
+
+    #[test]
+    fn test_read_invalid() {
+        // Truncated user data
+        assert_eq!(
+            SmsDeliver::read(b"00040B911346610089F60000208062917314080CC8F71D"),
+            Err(HayesError::UnexpectedEnd)
+        );
+        // SMS-SUBMIT read as SMS-DELIVER
+        assert_eq!(
+            SmsDeliver::read(b"0011000B916407281553F80000AA0AE8329BFD4697D9EC37"),
+            Err(HayesError::InvalidFormat)
+        );
+        // Invalid month in the timestamp
+        assert!(SmsDeliver::read(b"00040B911346610089F60000203162917314080100").is_err());
+        assert_eq!(Pdu::read(b"0003"), Err(HayesError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_read_smsc_without_digits() {
+        let (pdu, _) =
+            SmsDeliver::read(b"0191040B911346610089F60000208062917314080CC8F71D14969741F977FD07")
+                .unwrap();
+        let mut buf = [0u8; 4];
+        assert_eq!(pdu.smsc.unwrap().decode_into(&mut buf), Ok("+"));
+    }
+
+    #[test]
+    fn test_dcs() {
+        assert_eq!(Dcs(0x00).alphabet(), Alphabet::Gsm7);
+        assert_eq!(Dcs(0x04).alphabet(), Alphabet::EightBit);
+        assert_eq!(Dcs(0x18).alphabet(), Alphabet::Ucs2);
+        assert_eq!(Dcs(0x18).message_class(), Some(0));
+        assert_eq!(Dcs(0x0C).alphabet(), Alphabet::Gsm7);
+        assert_eq!(Dcs(0xF5).alphabet(), Alphabet::EightBit);
+        assert_eq!(Dcs(0xF5).message_class(), Some(1));
+        assert_eq!(Dcs(0xE0).alphabet(), Alphabet::Ucs2);
+        assert_eq!(Dcs(0x00).message_class(), None);
+        assert!(Dcs(0x20).is_compressed());
+    }
+
+    #[test]
+    fn test_validity_period() {
+        let minutes = |m: u64| Some(Duration::from_secs(m * 60));
+        assert_eq!(ValidityPeriod::Relative(0).to_duration(), minutes(5));
+        assert_eq!(
+            ValidityPeriod::Relative(143).to_duration(),
+            minutes(12 * 60)
+        );
+        assert_eq!(
+            ValidityPeriod::Relative(167).to_duration(),
+            minutes(24 * 60)
+        );
+        assert_eq!(
+            ValidityPeriod::Relative(0xAA).to_duration(),
+            minutes(4 * 24 * 60)
+        );
+        assert_eq!(
+            ValidityPeriod::Relative(255).to_duration(),
+            minutes(63 * 7 * 24 * 60)
+        );
+        assert_eq!(ValidityPeriod::Enhanced([0; 7]).to_duration(), None);
+    }
+
+    #[test]
+    fn test_address() {
+        assert!(Address::new("+1 555").is_err());
+        assert!(Address::new("123456789012345678901").is_err());
+        assert!(Address::alphanumeric("TwelveChars!").is_err());
+
+        let mut buf = [0u8; 16];
+        let address = Address::new("*21#").unwrap();
+        assert_eq!(address.decode_into(&mut buf), Ok("*21#"));
+        assert_eq!(address.type_of_address(), TypeOfAddress::UNKNOWN);
+    }
+}
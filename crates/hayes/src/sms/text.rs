@@ -0,0 +1,538 @@
+//! Text mode commands (3GPP TS 27.005 §3 and §4, `+CMGF=1`)
+//!
+//! In text mode, the modem encodes and decodes the message itself. Addresses,
+//! names and message bodies are encoded in the character set selected with
+//! `+CSCS`, so they're read and written as [`Text`] using the
+//! [`AtContext::charset`]. [`AtCommand::write`] and [`AtResponse::read`] assume
+//! the default IRA character set.
+
+use crate::{
+    AtCommand, AtContext, AtResponse, HayesError, ModemDateTime, PhoneNumber,
+    at::{CommandWriter, Fields, ResponseReader},
+    charset::{Charset, Text},
+    sms::{CTRL_Z, CmgsResponse, ESC, MessageStatus},
+};
+
+/// `AT+CMGS=<da>[,<toda>]`, sending a message in text mode
+///
+/// [`AtCommand::write`] writes the command line. Once the modem shows the
+/// `> ` prompt, [`Cmgs::write_text`] writes the message and the terminating
+/// [`CTRL_Z`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmgs<'a> {
+    /// The recipient
+    pub destination: PhoneNumber<'a>,
+    /// The message
+    pub text: Text<'a>,
+}
+
+impl Cmgs<'_> {
+    /// Write the message followed by [`CTRL_Z`], in the IRA character set
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if the message can't be represented,
+    /// [`HayesError::UnsafeCharacter`] if it contains [`CTRL_Z`] or [`ESC`],
+    /// or [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn write_text(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        self.write_text_with(buffer, &AtContext::default())
+    }
+
+    /// Write the message followed by [`CTRL_Z`], in the context's character
+    /// set
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if the message can't be represented,
+    /// [`HayesError::UnsafeCharacter`] if it contains [`CTRL_Z`] or [`ESC`],
+    /// or [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn write_text_with(
+        &self,
+        buffer: &mut [u8],
+        context: &AtContext,
+    ) -> Result<usize, HayesError> {
+        let required = self.text.encoded_len(context.charset)? + 1;
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        let encoded = self.text.encode_into(buffer, context.charset)?;
+        // Either would end the message early, and what follows would be read
+        // as commands
+        let septets = context.charset == Charset::Gsm;
+        if let Some(offset) = encoded
+            .iter()
+            .position(|&b| b == CTRL_Z || (b == ESC && !septets))
+        {
+            return Err(HayesError::UnsafeCharacter {
+                byte: encoded[offset],
+                offset,
+            });
+        }
+        buffer[required - 1] = CTRL_Z;
+
+        Ok(required)
+    }
+}
+
+impl AtCommand for Cmgs<'_> {
+    type Response<'at> = CmgsResponse;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        self.write_with(buffer, &AtContext::default())
+    }
+
+    fn write_with(&self, buffer: &mut [u8], context: &AtContext) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CMGS")?;
+        writer.param_with(&Text::new(self.destination.number()), context)?;
+        writer.param(&self.destination.type_of_address().0)?;
+        // The modem answers with a prompt rather than waiting for a line feed
+        writer.finish_with(b"\r")
+    }
+}
+
+/// `AT+CMGR=<index>`, reading a message from the read storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmgr {
+    /// Index of the message
+    pub index: u16,
+}
+
+impl AtCommand for Cmgr {
+    type Response<'at> = CmgrResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CMGR")?;
+        writer.param(&self.index)?;
+        writer.finish()
+    }
+}
+
+/// A stored message, as read by [`Cmgr`] or listed by [`Cmgl`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Message<'at> {
+    /// Status of the message
+    pub status: MessageStatus,
+    /// The originator of a received message or the recipient of a stored one
+    pub address: Text<'at>,
+    /// The phonebook entry matching the address, if any
+    pub name: Option<Text<'at>>,
+    /// When the service centre received the message, for received messages
+    pub timestamp: Option<ModemDateTime>,
+    /// The message
+    pub text: Text<'at>,
+}
+
+impl<'at> Message<'at> {
+    /// Read the fields following the status and the body of a message
+    ///
+    /// Fields shown with `+CSDH=1` are ignored.
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn read(
+        status: MessageStatus,
+        fields: &mut Fields<'at>,
+        reader: &mut ResponseReader<'at>,
+        next: Option<&str>,
+        context: &AtContext,
+    ) -> Result<Self, HayesError> {
+        let address = fields.next_with(context)?;
+        let name = fields.next_with(context)?;
+        // Stored messages have <toda> rather than <scts> here
+        let timestamp = if status.is_received() {
+            fields.optional::<Option<ModemDateTime>>()?.flatten()
+        } else {
+            None
+        };
+        let text = Text::from_encoded(reader.body(next), context.charset)?;
+
+        Ok(Message {
+            status,
+            address,
+            name,
+            timestamp,
+            text,
+        })
+    }
+}
+
+/// Response to [`Cmgr`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CmgrResponse<'at> {
+    /// The message
+    pub message: Message<'at>,
+}
+
+impl<'at> AtResponse<'at> for CmgrResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let mut fields = reader.prefixed("+CMGR")?;
+        let status = fields.next()?;
+        let message = Message::read(status, &mut fields, &mut reader, None, context)?;
+        reader.finish()?;
+
+        Ok(CmgrResponse { message })
+    }
+}
+
+/// `AT+CMGL[=<stat>]`, listing messages in the read storage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmgl {
+    /// Which messages to list, unread messages if omitted
+    pub status: Option<MessageStatus>,
+}
+
+impl AtCommand for Cmgl {
+    type Response<'at> = CmglResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        let mut writer = CommandWriter::new(buffer, "+CMGL")?;
+        writer.param(&self.status)?;
+        writer.finish()
+    }
+}
+
+/// Response to [`Cmgl`], the listed messages
+///
+/// The messages are checked when the response is read and borrowed from the
+/// input as they're iterated over.
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CmglResponse<'at> {
+    reader: ResponseReader<'at>,
+    context: AtContext,
+    len: usize,
+}
+
+impl<'at> CmglResponse<'at> {
+    /// Number of messages listed
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether no messages were listed
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterate over the listed messages
+    #[must_use]
+    pub fn iter(&self) -> CmglEntries<'at> {
+        CmglEntries {
+            reader: self.reader.clone(),
+            context: self.context,
+        }
+    }
+}
+
+impl<'at> IntoIterator for &CmglResponse<'at> {
+    type Item = CmglEntry<'at>;
+    type IntoIter = CmglEntries<'at>;
+
+    fn into_iter(self) -> CmglEntries<'at> {
+        self.iter()
+    }
+}
+
+impl<'at> AtResponse<'at> for CmglResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let reader = ResponseReader::new(buffer, context);
+
+        let mut entries = CmglEntries {
+            reader: reader.clone(),
+            context: *context,
+        };
+        let mut len = 0;
+        while entries.read_entry()?.is_some() {
+            len += 1;
+        }
+        entries.reader.finish()?;
+
+        Ok(CmglResponse {
+            reader,
+            context: *context,
+            len,
+        })
+    }
+}
+
+/// A message listed by [`Cmgl`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CmglEntry<'at> {
+    /// Index of the message in the read storage
+    pub index: u16,
+    /// The message
+    pub message: Message<'at>,
+}
+
+/// Iterator over the messages of a [`CmglResponse`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CmglEntries<'at> {
+    reader: ResponseReader<'at>,
+    context: AtContext,
+}
+
+impl<'at> CmglEntries<'at> {
+    fn read_entry(&mut self) -> Result<Option<CmglEntry<'at>>, HayesError> {
+        let Some(mut fields) = self.reader.optional("+CMGL")? else {
+            return Ok(None);
+        };
+
+        let index = fields.next()?;
+        let status = fields.next()?;
+        let message = Message::read(
+            status,
+            &mut fields,
+            &mut self.reader,
+            Some("+CMGL"),
+            &self.context,
+        )?;
+
+        Ok(Some(CmglEntry { index, message }))
+    }
+}
+
+impl<'at> Iterator for CmglEntries<'at> {
+    type Item = CmglEntry<'at>;
+
+    fn next(&mut self) -> Option<CmglEntry<'at>> {
+        // Every entry was checked when the response was read
+        self.read_entry().ok().flatten()
+    }
+}
+
+/// `+CMT: <oa>,[<alpha>],<scts>`, a message routed directly to the TE
+///
+/// Sent when received messages are indicated with
+/// [`MessageIndication::Direct`](super::MessageIndication::Direct). The message
+/// follows on the next line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cmt<'at> {
+    /// The originator
+    pub address: Text<'at>,
+    /// The phonebook entry matching the originator, if any
+    pub name: Option<Text<'at>>,
+    /// When the service centre received the message
+    pub timestamp: ModemDateTime,
+    /// The message
+    pub text: Text<'at>,
+}
+
+/// Reads the unsolicited result code, which isn't followed by `OK`
+impl<'at> AtResponse<'at> for Cmt<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let mut fields = reader.prefixed("+CMT")?;
+
+        Ok(Cmt {
+            address: fields.next_with(context)?,
+            name: fields.next_with(context)?,
+            timestamp: fields.next()?,
+            text: Text::from_encoded(reader.body(None), context.charset)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CmsError;
+    use crate::{TimeZone, charset::Charset};
+
+    fn decoded<'b>(text: &Text<'_>, buffer: &'b mut [u8]) -> &'b str {
+        text.decode_into(buffer).unwrap()
+    }
+
+    #[test]
+    fn test_write_cmgs() {
+        let mut buf = [0u8; 64];
+        let command = Cmgs {
+            destination: PhoneNumber::new("+15551234").unwrap(),
+            text: Text::new("Hello"),
+        };
+
+        let len = command.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CMGS=\"+15551234\",145\r");
+        let len = command.write_text(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"Hello\x1A");
+
+        let context = AtContext::with_charset(Charset::Ucs2);
+        let len = command.write_with(&mut buf, &context).unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"AT+CMGS=\"002B00310035003500350031003200330034\",145\r"
+        );
+        let len = command.write_text_with(&mut buf, &context).unwrap();
+        assert_eq!(&buf[..len], b"00480065006C006C006F\x1A");
+
+        assert_eq!(
+            command.write_text(&mut buf[..5]),
+            Err(HayesError::InsufficientBuffer {
+                required: 6,
+                available: 5,
+            })
+        );
+
+        // The message can't end itself and smuggle in a command
+        let command = Cmgs {
+            text: Text::new("Hi\x1AAT+CFUN=0\r"),
+            ..command
+        };
+        assert_eq!(
+            command.write_text(&mut buf),
+            Err(HayesError::UnsafeCharacter {
+                byte: 0x1A,
+                offset: 2
+            })
+        );
+    }
+
+    #[test]
+    fn test_write_commands() {
+        let mut buf = [0u8; 32];
+
+        let len = Cmgr { index: 12 }.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CMGR=12\r\n");
+
+        let len = Cmgl {
+            status: Some(MessageStatus::All),
+        }
+        .write(&mut buf)
+        .unwrap();
+        assert_eq!(&buf[..len], b"AT+CMGL=\"ALL\"\r\n");
+
+        let len = Cmgl { status: None }.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CMGL\r\n");
+    }
+
+    #[test]
+    fn test_read_cmgr() {
+        let mut buf = [0u8; 64];
+
+        let response = CmgrResponse::read(
+            b"AT+CMGR=1\r\r\n+CMGR: \"REC READ\",\"+8613800138000\",,\"02/01/30,20:40:31+32\"\r\nLine one\r\nLine two\r\n\r\nOK\r\n",
+        )
+        .unwrap();
+        let message = response.message;
+        assert_eq!(message.status, MessageStatus::ReceivedRead);
+        assert_eq!(decoded(&message.address, &mut buf), "+8613800138000");
+        assert_eq!(message.name, None);
+        assert_eq!(
+            message.timestamp,
+            Some(ModemDateTime {
+                year: 2002,
+                month: 1,
+                day: 30,
+                hour: 20,
+                minute: 40,
+                second: 31,
+                time_zone: Some(TimeZone::from_quarter_hours(32).unwrap()),
+            })
+        );
+        assert_eq!(decoded(&message.text, &mut buf), "Line one\r\nLine two");
+
+        // With +CSDH=1, a stored message has <toda> where <scts> would be
+        let response = CmgrResponse::read_with(
+            b"\r\n+CMGR: \"STO UNSENT\",\"0031\",\"0042006F0062\",129,17,0,0,167,\"\",129,2\r\n0048\r\n\r\nOK\r\n",
+            &AtContext::with_charset(Charset::Ucs2),
+        )
+        .unwrap();
+        let message = response.message;
+        assert_eq!(message.status, MessageStatus::StoredUnsent);
+        assert_eq!(decoded(&message.address, &mut buf), "1");
+        assert_eq!(
+            message.name.map(|name| decoded(&name, &mut buf).len()),
+            Some(3)
+        );
+        assert_eq!(message.timestamp, None);
+        assert_eq!(decoded(&message.text, &mut buf), "H");
+
+        assert_eq!(
+            CmgrResponse::read(b"\r\n+CMS ERROR: 321\r\n"),
+            Err(HayesError::CmsError(CmsError::InvalidMemoryIndex))
+        );
+    }
+
+    #[test]
+    fn test_read_cmgl() {
+        let mut buf = [0u8; 64];
+
+        let response = CmglResponse::read(
+            b"\r\n+CMGL: 1,\"REC UNREAD\",\"+15551234\",\"Alice\",\"24/01/02,03:04:05+00\"\r\nHi\r\n+CMGL: 4,\"STO SENT\",\"+15559876\",,\r\nSee you\r\nsoon\r\n\r\nOK\r\n",
+        )
+        .unwrap();
+        assert_eq!(response.len(), 2);
+
+        let mut entries = response.iter();
+        let entry = entries.next().unwrap();
+        assert_eq!(entry.index, 1);
+        assert_eq!(entry.message.status, MessageStatus::ReceivedUnread);
+        assert_eq!(
+            entry
+                .message
+                .name
+                .map(|name| decoded(&name, &mut buf).len()),
+            Some(5)
+        );
+        assert_eq!(decoded(&entry.message.text, &mut buf), "Hi");
+
+        let entry = entries.next().unwrap();
+        assert_eq!(entry.index, 4);
+        assert_eq!(decoded(&entry.message.address, &mut buf), "+15559876");
+        assert_eq!(entry.message.timestamp, None);
+        assert_eq!(decoded(&entry.message.text, &mut buf), "See you\r\nsoon");
+        assert!(entries.next().is_none());
+
+        let response = CmglResponse::read(b"\r\nOK\r\n").unwrap();
+        assert!(response.is_empty());
+        assert_eq!((&response).into_iter().count(), 0);
+
+        assert!(CmglResponse::read(b"\r\n+CMGL: x,\"REC READ\"\r\nHi\r\n\r\nOK\r\n").is_err());
+    }
+
+    #[test]
+    fn test_read_cmt() {
+        let mut buf = [0u8; 64];
+
+        let cmt =
+            Cmt::read(b"\r\n+CMT: \"+15551234\",,\"24/01/02,03:04:05-08\"\r\nHello there\r\n")
+                .unwrap();
+        assert_eq!(decoded(&cmt.address, &mut buf), "+15551234");
+        assert_eq!(cmt.name, None);
+        assert_eq!(cmt.timestamp.hour, 3);
+        assert_eq!(decoded(&cmt.text, &mut buf), "Hello there");
+
+        let cmt = Cmt::read_with(
+            b"\r\n+CMT: \"002B0031\",\"\",\"24/01/02,03:04:05+00\"\r\n00480069\r\n",
+            &AtContext::with_charset(Charset::Ucs2),
+        )
+        .unwrap();
+        assert_eq!(cmt.name.map(|name| name.is_empty()), Some(true));
+        assert_eq!(decoded(&cmt.address, &mut buf), "+1");
+        assert_eq!(decoded(&cmt.text, &mut buf), "Hi");
+    }
+}
@@ -0,0 +1,296 @@
+//! Exchanging commands with the modem and recovering the response stream
+//!
+//! After a reset or a baud rate glitch, the received bytes contain noise,
+//! partial lines and boot messages such as `RDY` or `+CPIN: READY`.
+//! [`resync`] finds where the clean lines start again, [`read_resynced`]
+//! reads a response after skipping them, and [`sync`] sends
+//! `AT` over a [`Transport`] until the modem answers with a clean `OK`,
+//! reporting everything else as [`Junk`].
+
+use crate::{AtContext, AtResponse, HayesError, ParseOptions, at::final_result};
+
+/// Sends commands to the modem and returns its responses
+///
+/// Implemented by the application over its connection to the modem.
+pub trait Transport {
+    /// Error returned when the exchange fails
+    type Error: From<HayesError>;
+
+    /// Send `command` and return the modem's response, up to and including
+    /// the final result code
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the command can't be sent or no response arrives.
+    fn exchange(&mut self, command: &[u8]) -> Result<&[u8], Self::Error>;
+}
+
+/// Whether `line` contains control bytes other than tabs, or isn't UTF-8
+///
+/// Text in any character set the modem may use, such as UTF-8 passed through
+/// IRA, is not noise.
+fn is_noise(line: &[u8]) -> bool {
+    line.iter().any(|&b| (b < b' ' && b != b'\t') || b == 0x7F)
+        || core::str::from_utf8(line).is_err()
+}
+
+/// The lines of `input`, skipping empty ones
+fn lines(input: &[u8]) -> impl Iterator<Item = &[u8]> {
+    input
+        .split(|&b| b == b'\r' || b == b'\n')
+        .filter(|line| !line.is_empty())
+}
+
+/// Number of bytes at the start of `input` to discard to reach the next clean
+/// line
+///
+/// Lines containing control bytes or invalid UTF-8, such as line noise or a
+/// line cut short by a glitch, are discarded up to the line break that ends
+/// them. A noisy line without a line break is discarded entirely.
+#[must_use]
+pub fn resync(input: &[u8]) -> usize {
+    let mut discarded = 0;
+    loop {
+        let start = discarded
+            + input[discarded..]
+                .iter()
+                .take_while(|&&b| b == b'\r' || b == b'\n')
+                .count();
+        let rest = &input[start..];
+        let len = rest
+            .iter()
+            .position(|&b| b == b'\r' || b == b'\n')
+            .unwrap_or(rest.len());
+
+        if len == 0 || !is_noise(&rest[..len]) {
+            return discarded;
+        }
+        discarded = start + len;
+    }
+}
+
+/// Read a response that may be preceded by noise, such as the first response
+/// after a reset
+///
+/// Returns the response and the number of bytes discarded before it, as
+/// found by [`resync`].
+///
+/// # Errors
+///
+/// Returns the same errors as [`AtResponse::read_with`].
+#[allow(clippy::trivially_copy_pass_by_ref)]
+pub fn read_resynced<'at, R: AtResponse<'at>>(
+    buffer: &'at [u8],
+    context: &AtContext,
+) -> Result<(R, usize), HayesError> {
+    let discarded = resync(buffer);
+    let response = R::read_with(&buffer[discarded..], context)?;
+    Ok((response, discarded))
+}
+
+/// A line received while synchronizing that isn't the expected `OK`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Junk<'a> {
+    /// Control bytes or invalid UTF-8, such as line noise
+    Noise(&'a [u8]),
+    /// An unsolicited result code, such as `+CPIN: READY`
+    Unsolicited(&'a [u8]),
+    /// A final result code other than `OK`, such as `ERROR` for a garbled
+    /// command
+    ResultCode(&'a [u8]),
+    /// Any other line, such as a boot banner like `RDY`
+    Text(&'a [u8]),
+}
+
+impl<'a> Junk<'a> {
+    /// Classify a line that isn't the expected `OK`
+    #[must_use]
+    pub fn classify(line: &'a [u8]) -> Self {
+        if is_noise(line) {
+            Junk::Noise(line)
+        } else if final_result(line, ParseOptions::STRICT).is_some() {
+            Junk::ResultCode(line)
+        } else if matches!(line.first(), Some(b'+' | b'^' | b'*' | b'#' | b'%' | b'$')) {
+            Junk::Unsolicited(line)
+        } else {
+            Junk::Text(line)
+        }
+    }
+
+    /// The line as received
+    #[must_use]
+    pub const fn line(self) -> &'a [u8] {
+        match self {
+            Junk::Noise(line)
+            | Junk::Unsolicited(line)
+            | Junk::ResultCode(line)
+            | Junk::Text(line) => line,
+        }
+    }
+}
+
+/// Send `AT` until the modem answers with a clean `OK`, trying at most
+/// `attempts` times
+///
+/// An answer is clean when `OK` is its only line, apart from the echoed
+/// command. Every other line received is passed to `junk`, for logging.
+/// Failed exchanges, such as timeouts while the modem boots, are retried.
+///
+/// Returns the number of attempts it took.
+///
+/// # Errors
+///
+/// Returns the error of the last exchange if it failed, or
+/// [`HayesError::InvalidFormat`] if the modem never answered cleanly.
+pub fn sync<T: Transport>(
+    transport: &mut T,
+    attempts: usize,
+    mut junk: impl FnMut(Junk<'_>),
+) -> Result<usize, T::Error> {
+    let mut last = HayesError::InvalidFormat.into();
+
+    for attempt in 1..=attempts {
+        let response = match transport.exchange(b"AT\r\n") {
+            Ok(response) => response,
+            Err(error) => {
+                last = error;
+                continue;
+            }
+        };
+
+        let mut clean = true;
+        let mut ok = false;
+        for line in lines(response) {
+            if line.eq_ignore_ascii_case(b"AT") {
+                continue;
+            }
+            if line == b"OK" && !ok {
+                ok = true;
+                continue;
+            }
+
+            clean = false;
+            junk(Junk::classify(line));
+        }
+
+        if clean && ok {
+            return Ok(attempt);
+        }
+        last = HayesError::InvalidFormat.into();
+    }
+
+    Err(last)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers `AT` with each response in turn, or fails for `None`
+    struct Modem<'s> {
+        responses: &'s [Option<&'s [u8]>],
+        step: usize,
+    }
+
+    impl Transport for Modem<'_> {
+        type Error = HayesError;
+
+        fn exchange(&mut self, command: &[u8]) -> Result<&[u8], HayesError> {
+            assert_eq!(command, b"AT\r\n");
+            let response = self.responses[self.step];
+            self.step += 1;
+            response.ok_or(HayesError::UnexpectedEnd)
+        }
+    }
+
+    #[test]
+    fn test_resync() {
+        assert_eq!(resync(b"\r\nOK\r\n"), 0);
+        assert_eq!(resync(b"\x00\xff\r\nOK\r\n"), 2);
+        assert_eq!(resync(b"\r\n\x00\xffRDY\r\n\xfe\r\n+CPIN: READY\r\n"), 10);
+        assert_eq!(resync(b"\x00\xff"), 2);
+        assert_eq!(resync(b""), 0);
+    }
+
+    #[test]
+    fn test_resync_text() {
+        assert_eq!(resync("\r\n+CUSD: 0,\"Solde: 5€\",15\r\n".as_bytes()), 0);
+        assert_eq!(resync("\x1b[0m\r\nRDY €\r\n".as_bytes()), 4);
+    }
+
+    #[test]
+    fn test_read_resynced() {
+        let context = AtContext::default();
+        assert_eq!(
+            read_resynced::<()>(b"\x00\xff\r\nOK\r\n", &context),
+            Ok(((), 2))
+        );
+        assert_eq!(
+            read_resynced::<crate::sms::CmgsResponse>(
+                b"\xff\xff\r\n+CMGS: 4\r\n\r\nOK\r\n",
+                &context
+            )
+            .map(|(response, discarded)| (response.message_reference, discarded)),
+            Ok((4, 2))
+        );
+
+        // Typed reads don't skip noise, so it can't hide a response
+        assert!(<()>::read(b"\x00\xff\r\nOK\r\n").is_err());
+    }
+
+    #[test]
+    fn test_classify() {
+        assert_eq!(Junk::classify(b"\x00\xffRDY"), Junk::Noise(b"\x00\xffRDY"));
+        assert_eq!(Junk::classify(b"+CFUN: 1"), Junk::Unsolicited(b"+CFUN: 1"));
+        assert_eq!(Junk::classify(b"ERROR"), Junk::ResultCode(b"ERROR"));
+        assert_eq!(
+            Junk::classify(b"+CME ERROR: 10"),
+            Junk::ResultCode(b"+CME ERROR: 10")
+        );
+        assert_eq!(Junk::classify(b"RDY"), Junk::Text(b"RDY"));
+        assert_eq!(Junk::classify(b"RDY").line(), b"RDY");
+    }
+
+    #[test]
+    fn test_sync() {
+        let mut modem = Modem {
+            responses: &[
+                None,
+                Some(b"\x00\xff\r\nRDY\r\n\r\n+CPIN: READY\r\n"),
+                Some(b"\r\nERROR\r\n"),
+                Some(b"AT\r\r\nOK\r\n"),
+            ],
+            step: 0,
+        };
+        let expected = [
+            Junk::Noise(b"\x00\xff"),
+            Junk::Text(b"RDY"),
+            Junk::Unsolicited(b"+CPIN: READY"),
+            Junk::ResultCode(b"ERROR"),
+        ];
+        let mut count = 0;
+        let result = sync(&mut modem, 5, |junk| {
+            assert_eq!(junk, expected[count]);
+            count += 1;
+        });
+
+        assert_eq!(result, Ok(4));
+        assert_eq!(count, expected.len());
+    }
+
+    #[test]
+    fn test_sync_fails() {
+        let mut modem = Modem {
+            responses: &[Some(b"\r\nRDY\r\n\r\nOK\r\n"), None],
+            step: 0,
+        };
+        assert_eq!(sync(&mut modem, 2, |_| {}), Err(HayesError::UnexpectedEnd));
+
+        let mut modem = Modem {
+            responses: &[Some(b"\r\nOK\r\n\r\nOK\r\n")],
+            step: 0,
+        };
+        assert_eq!(sync(&mut modem, 1, |_| {}), Err(HayesError::InvalidFormat));
+    }
+}
@@ -0,0 +1,173 @@
+use crate::{AtReadable, AtWritable, HayesError};
+
+/// Fixed-width string of binary digits, such as `"00100001"`
+///
+/// Used by `+CPSMS` and `+CEDRXS` to carry 3GPP information elements bit by bit.
+/// The most significant bit is written first. `N` must be at most 32.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BitString<const N: usize> {
+    value: u32,
+}
+
+impl<const N: usize> BitString<N> {
+    const WIDTH: () = assert!(N > 0 && N <= 32, "BitString width must be 1..=32");
+
+    /// Mask covering the `N` low bits
+    const MASK: u32 = if N >= 32 { u32::MAX } else { (1 << N) - 1 };
+
+    /// Create a bit string from the `N` low bits of `value`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `value` has bits set above bit `N - 1`.
+    pub const fn new(value: u32) -> Result<Self, HayesError> {
+        let () = Self::WIDTH;
+
+        if value & !Self::MASK != 0 {
+            return Err(HayesError::OutOfRange);
+        }
+
+        Ok(BitString { value })
+    }
+
+    /// The bits as an integer
+    #[must_use]
+    pub const fn value(self) -> u32 {
+        self.value
+    }
+
+    /// Whether bit `index` (0 = least significant) is set
+    #[must_use]
+    pub const fn bit(self, index: usize) -> bool {
+        index < N && self.value & (1 << index) != 0
+    }
+}
+
+impl<'at, const N: usize> AtReadable<'at> for BitString<N> {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let () = Self::WIDTH;
+
+        if input.is_empty() {
+            return Err(HayesError::UnexpectedEnd);
+        }
+
+        let quoted = input[0] == b'"';
+        let start = usize::from(quoted);
+
+        let digits = input[start..]
+            .iter()
+            .take_while(|&&b| b == b'0' || b == b'1')
+            .count();
+        let end = start + digits;
+
+        if digits != N {
+            return Err(if digits < N && end == input.len() {
+                HayesError::UnexpectedEnd
+            } else {
+                HayesError::InvalidFormat
+            });
+        }
+
+        let value = input[start..end]
+            .iter()
+            .fold(0, |value, &b| (value << 1) | u32::from(b - b'0'));
+
+        if !quoted {
+            return Ok((BitString { value }, end));
+        }
+
+        match input.get(end) {
+            Some(b'"') => Ok((BitString { value }, end + 1)),
+            Some(_) => Err(HayesError::InvalidFormat),
+            None => Err(HayesError::UnexpectedEnd),
+        }
+    }
+}
+
+impl<const N: usize> AtWritable for BitString<N> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let required = N + 2;
+
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        output[0] = b'"';
+        for (i, slot) in output[1..=N].iter_mut().enumerate() {
+            *slot = if self.bit(N - 1 - i) { b'1' } else { b'0' };
+        }
+        output[N + 1] = b'"';
+
+        Ok(required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_bit_string() {
+        assert_eq!(
+            BitString::<8>::read(b"\"00100001\","),
+            Ok((BitString::new(0b0010_0001).unwrap(), 10))
+        );
+        assert_eq!(
+            BitString::<4>::read(b"0101\r\n"),
+            Ok((BitString::new(0b0101).unwrap(), 4))
+        );
+
+        assert_eq!(
+            BitString::<8>::read(b"\"0010\""),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            BitString::<4>::read(b"\"00100\""),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            BitString::<4>::read(b"01012"),
+            Ok((BitString::new(5).unwrap(), 4))
+        );
+        assert_eq!(
+            BitString::<4>::read(b"01010"),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            BitString::<4>::read(b"\"01"),
+            Err(HayesError::UnexpectedEnd)
+        );
+        assert_eq!(
+            BitString::<4>::read(b"\"01x1\""),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(BitString::<4>::read(b""), Err(HayesError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_write_bit_string() {
+        let mut buf = [0u8; 12];
+        assert_eq!(BitString::<8>::new(0b11).unwrap().write(&mut buf), Ok(10));
+        assert_eq!(&buf[..10], b"\"00000011\"");
+
+        assert!(BitString::<4>::new(0b1_0000).is_err());
+        assert!(
+            BitString::<4>::new(0)
+                .unwrap()
+                .write(&mut buf[..5])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bit_string_bits() {
+        let bits = BitString::<32>::new(u32::MAX).unwrap();
+        assert!(bits.bit(31));
+        assert!(!bits.bit(32));
+        assert!(BitString::<3>::new(0b100).unwrap().bit(2));
+    }
+}
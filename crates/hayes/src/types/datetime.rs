@@ -0,0 +1,572 @@
+use crate::{AtReadable, AtWritable, HayesError, impls::write_fmt};
+
+/// Time zone offset from UTC in quarter hours, as reported by `+CCLK`, `+CTZE` and `+CTZV`
+///
+/// Reads and writes a signed two-digit value such as `"+08"` (UTC+2) or `"-20"`
+/// (UTC-5), quoted or bare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TimeZone(i8);
+
+impl TimeZone {
+    /// Coordinated Universal Time
+    pub const UTC: Self = TimeZone(0);
+
+    /// Create a time zone from an offset in quarter hours
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if the offset is more than 24 hours.
+    pub const fn from_quarter_hours(quarters: i8) -> Result<Self, HayesError> {
+        if quarters < -96 || quarters > 96 {
+            return Err(HayesError::OutOfRange);
+        }
+
+        Ok(TimeZone(quarters))
+    }
+
+    /// The offset from UTC in quarter hours
+    #[must_use]
+    pub const fn quarter_hours(self) -> i8 {
+        self.0
+    }
+
+    /// The offset from UTC in seconds
+    #[must_use]
+    pub const fn offset_seconds(self) -> i32 {
+        self.0 as i32 * 15 * 60
+    }
+
+    /// Read the unquoted `±zz` form
+    fn parse(input: &[u8]) -> Result<(Self, usize), HayesError> {
+        // Integer parsing doesn't accept an explicit '+'
+        let (quarters, consumed) = match input.first() {
+            Some(b'+') => {
+                let (quarters, consumed) = u8::read(&input[1..])?;
+                let quarters = i8::try_from(quarters).map_err(|_| HayesError::OutOfRange)?;
+                (quarters, consumed + 1)
+            }
+            _ => i8::read(input)?,
+        };
+
+        Ok((TimeZone::from_quarter_hours(quarters)?, consumed))
+    }
+}
+
+impl<'at> AtReadable<'at> for TimeZone {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        if input.is_empty() {
+            return Err(HayesError::UnexpectedEnd);
+        }
+
+        if input[0] != b'"' {
+            return TimeZone::parse(input);
+        }
+
+        let (zone, consumed) = TimeZone::parse(&input[1..])?;
+        match input.get(consumed + 1) {
+            Some(b'"') => Ok((zone, consumed + 2)),
+            Some(_) => Err(HayesError::InvalidFormat),
+            None => Err(HayesError::UnexpectedEnd),
+        }
+    }
+}
+
+impl AtWritable for TimeZone {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let sign = if self.0 < 0 { '-' } else { '+' };
+        write_fmt(
+            output,
+            format_args!("\"{sign}{:02}\"", self.0.unsigned_abs()),
+        )
+    }
+}
+
+/// Calendar date and time reported by the modem
+///
+/// Reads the `+CCLK` format `"yy/MM/dd,hh:mm:ss±zz"` and the four-digit year
+/// variant `"yyyy/MM/dd,hh:mm:ss"` used by `+CTZE`. The time zone is optional.
+/// Two-digit years are taken to be in 2000–2099.
+///
+/// Writes the `+CCLK` format, which requires the year to be in 2000–2099.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ModemDateTime {
+    /// Year, such as 2024
+    pub year: u16,
+    /// Month, 1–12
+    pub month: u8,
+    /// Day of the month, 1–31
+    pub day: u8,
+    /// Hour, 0–23
+    pub hour: u8,
+    /// Minute, 0–59
+    pub minute: u8,
+    /// Second, 0–59
+    pub second: u8,
+    /// Offset of the local time from UTC, if reported
+    pub time_zone: Option<TimeZone>,
+}
+
+const fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+const fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 for a proleptic Gregorian date
+fn days_from_civil(year: i64, month: u8, day: u8) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Proleptic Gregorian date for a number of days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u8, u8) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    (year, month as u8, day as u8)
+}
+
+impl ModemDateTime {
+    /// Check that every field is within its calendar range
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if any field is out of range.
+    pub const fn validate(&self) -> Result<(), HayesError> {
+        if self.month < 1
+            || self.month > 12
+            || self.day < 1
+            || self.day > days_in_month(self.year, self.month)
+            || self.hour > 23
+            || self.minute > 59
+            || self.second > 59
+        {
+            return Err(HayesError::OutOfRange);
+        }
+
+        Ok(())
+    }
+
+    /// Seconds since the Unix epoch
+    ///
+    /// The time is converted to UTC using the reported time zone, or treated as
+    /// UTC if none was reported.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if the timestamp doesn't fit in an
+    /// `i64`.
+    pub fn to_unix_timestamp(&self) -> Result<i64, HayesError> {
+        let days = days_from_civil(i64::from(self.year), self.month, self.day);
+        let seconds =
+            i64::from(self.hour) * 3600 + i64::from(self.minute) * 60 + i64::from(self.second);
+        let offset = self.time_zone.map_or(0, TimeZone::offset_seconds);
+
+        days.checked_mul(86_400)
+            .and_then(|days| days.checked_add(seconds))
+            .and_then(|timestamp| timestamp.checked_sub(i64::from(offset)))
+            .ok_or(HayesError::OutOfRange)
+    }
+
+    /// Local date and time for a Unix timestamp in the given time zone
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if the year falls outside 0–65535.
+    pub fn from_unix_timestamp(
+        timestamp: i64,
+        time_zone: Option<TimeZone>,
+    ) -> Result<Self, HayesError> {
+        let local = timestamp
+            .checked_add(i64::from(time_zone.map_or(0, TimeZone::offset_seconds)))
+            .ok_or(HayesError::OutOfRange)?;
+        let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+        let seconds = local.rem_euclid(86_400);
+
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        Ok(ModemDateTime {
+            year: u16::try_from(year).map_err(|_| HayesError::OutOfRange)?,
+            month,
+            day,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+            time_zone,
+        })
+    }
+}
+
+/// Read exactly `len` decimal digits at `pos`
+fn fixed_digits(input: &[u8], pos: usize, len: usize) -> Result<u16, HayesError> {
+    let digits = input.get(pos..pos + len).ok_or(HayesError::UnexpectedEnd)?;
+    let (value, consumed) = u16::read(digits)?;
+    if consumed != len {
+        return Err(HayesError::ParseError);
+    }
+
+    Ok(value)
+}
+
+fn expect(input: &[u8], pos: usize, byte: u8) -> Result<(), HayesError> {
+    match input.get(pos) {
+        Some(&b) if b == byte => Ok(()),
+        Some(_) => Err(HayesError::InvalidFormat),
+        None => Err(HayesError::UnexpectedEnd),
+    }
+}
+
+impl<'at> AtReadable<'at> for ModemDateTime {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        expect(input, 0, b'"')?;
+
+        let year_digits = input[1..].iter().take_while(|b| b.is_ascii_digit()).count();
+        let year = match year_digits {
+            2 => 2000 + fixed_digits(input, 1, 2)?,
+            4 => fixed_digits(input, 1, 4)?,
+            _ => return Err(HayesError::InvalidFormat),
+        };
+
+        let mut pos = 1 + year_digits;
+        let mut fields = [0u8; 5];
+        for (field, separator) in fields.iter_mut().zip([b'/', b'/', b',', b':', b':']) {
+            expect(input, pos, separator)?;
+            #[allow(clippy::cast_possible_truncation)]
+            let value = fixed_digits(input, pos + 1, 2)? as u8;
+            *field = value;
+            pos += 3;
+        }
+
+        let time_zone = match input.get(pos) {
+            Some(b'+' | b'-') => {
+                let (zone, consumed) = TimeZone::parse(&input[pos..])?;
+                pos += consumed;
+                Some(zone)
+            }
+            _ => None,
+        };
+
+        expect(input, pos, b'"')?;
+
+        let [month, day, hour, minute, second] = fields;
+        let datetime = ModemDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            time_zone,
+        };
+        datetime.validate()?;
+
+        Ok((datetime, pos + 1))
+    }
+}
+
+impl AtWritable for ModemDateTime {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        self.validate()?;
+        if !(2000..=2099).contains(&self.year) {
+            return Err(HayesError::OutOfRange);
+        }
+
+        let ModemDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            time_zone,
+        } = *self;
+        let year = year - 2000;
+
+        match time_zone {
+            Some(zone) => {
+                let sign = if zone.0 < 0 { '-' } else { '+' };
+                let quarters = zone.0.unsigned_abs();
+                write_fmt(
+                    output,
+                    format_args!(
+                        "\"{year:02}/{month:02}/{day:02},{hour:02}:{minute:02}:{second:02}{sign}{quarters:02}\""
+                    ),
+                )
+            }
+            None => write_fmt(
+                output,
+                format_args!("\"{year:02}/{month:02}/{day:02},{hour:02}:{minute:02}:{second:02}\""),
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<ModemDateTime> for chrono::DateTime<chrono::FixedOffset> {
+    type Error = HayesError;
+
+    fn try_from(datetime: ModemDateTime) -> Result<Self, Self::Error> {
+        let offset =
+            chrono::FixedOffset::east_opt(datetime.time_zone.map_or(0, TimeZone::offset_seconds))
+                .ok_or(HayesError::OutOfRange)?;
+
+        chrono::NaiveDate::from_ymd_opt(
+            i32::from(datetime.year),
+            u32::from(datetime.month),
+            u32::from(datetime.day),
+        )
+        .and_then(|date| {
+            date.and_hms_opt(
+                u32::from(datetime.hour),
+                u32::from(datetime.minute),
+                u32::from(datetime.second),
+            )
+        })
+        .and_then(|naive| naive.and_local_timezone(offset).single())
+        .ok_or(HayesError::OutOfRange)
+    }
+}
+
+#[cfg(feature = "chrono")]
+impl TryFrom<chrono::DateTime<chrono::FixedOffset>> for ModemDateTime {
+    type Error = HayesError;
+
+    fn try_from(datetime: chrono::DateTime<chrono::FixedOffset>) -> Result<Self, Self::Error> {
+        use chrono::{Datelike, Timelike};
+
+        let offset = datetime.offset().local_minus_utc();
+        if offset % (15 * 60) != 0 {
+            return Err(HayesError::OutOfRange);
+        }
+        let quarters = i8::try_from(offset / (15 * 60)).map_err(|_| HayesError::OutOfRange)?;
+
+        #[allow(clippy::cast_possible_truncation)]
+        Ok(ModemDateTime {
+            year: u16::try_from(datetime.year()).map_err(|_| HayesError::OutOfRange)?,
+            month: datetime.month() as u8,
+            day: datetime.day() as u8,
+            hour: datetime.hour() as u8,
+            minute: datetime.minute() as u8,
+            // Leap seconds are folded into the preceding second
+            second: datetime.second().min(59) as u8,
+            time_zone: Some(TimeZone::from_quarter_hours(quarters)?),
+        })
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<ModemDateTime> for time::OffsetDateTime {
+    type Error = HayesError;
+
+    fn try_from(datetime: ModemDateTime) -> Result<Self, Self::Error> {
+        let month = time::Month::try_from(datetime.month).map_err(|_| HayesError::OutOfRange)?;
+        let date = time::Date::from_calendar_date(i32::from(datetime.year), month, datetime.day)
+            .map_err(|_| HayesError::OutOfRange)?;
+        let time = time::Time::from_hms(datetime.hour, datetime.minute, datetime.second)
+            .map_err(|_| HayesError::OutOfRange)?;
+        let offset = time::UtcOffset::from_whole_seconds(
+            datetime.time_zone.map_or(0, TimeZone::offset_seconds),
+        )
+        .map_err(|_| HayesError::OutOfRange)?;
+
+        Ok(time::PrimitiveDateTime::new(date, time).assume_offset(offset))
+    }
+}
+
+#[cfg(feature = "time")]
+impl TryFrom<time::OffsetDateTime> for ModemDateTime {
+    type Error = HayesError;
+
+    fn try_from(datetime: time::OffsetDateTime) -> Result<Self, Self::Error> {
+        let offset = datetime.offset().whole_seconds();
+        if offset % (15 * 60) != 0 {
+            return Err(HayesError::OutOfRange);
+        }
+        let quarters = i8::try_from(offset / (15 * 60)).map_err(|_| HayesError::OutOfRange)?;
+
+        Ok(ModemDateTime {
+            year: u16::try_from(datetime.year()).map_err(|_| HayesError::OutOfRange)?,
+            month: u8::from(datetime.month()),
+            day: datetime.day(),
+            hour: datetime.hour(),
+            minute: datetime.minute(),
+            second: datetime.second(),
+            time_zone: Some(TimeZone::from_quarter_hours(quarters)?),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CCLK: ModemDateTime = ModemDateTime {
+        year: 2024,
+        month: 10,
+        day: 17,
+        hour: 12,
+        minute: 34,
+        second: 56,
+        time_zone: Some(TimeZone(8)),
+    };
+
+    #[test]
+    fn test_read_datetime() {
+        assert_eq!(
+            ModemDateTime::read(b"\"24/10/17,12:34:56+08\"\r\n"),
+            Ok((CCLK, 22))
+        );
+
+        let (datetime, consumed) = ModemDateTime::read(b"\"2024/10/17,12:34:56\",").unwrap();
+        assert_eq!(consumed, 21);
+        assert_eq!(datetime.time_zone, None);
+        assert_eq!(datetime.year, 2024);
+
+        let (datetime, _) = ModemDateTime::read(b"\"24/10/17,12:34:56-20\"").unwrap();
+        assert_eq!(datetime.time_zone, Some(TimeZone(-20)));
+    }
+
+    #[test]
+    fn test_read_datetime_invalid() {
+        assert_eq!(
+            ModemDateTime::read(b"\"24/13/17,12:34:56+08\""),
+            Err(HayesError::OutOfRange)
+        );
+        assert_eq!(
+            ModemDateTime::read(b"\"23/02/29,12:34:56\""),
+            Err(HayesError::OutOfRange)
+        );
+        assert!(ModemDateTime::read(b"\"24/02/29,12:34:56\"").is_ok());
+        assert_eq!(
+            ModemDateTime::read(b"\"24/10/17,24:00:00\""),
+            Err(HayesError::OutOfRange)
+        );
+        assert_eq!(
+            ModemDateTime::read(b"\"24/10/17 12:34:56\""),
+            Err(HayesError::InvalidFormat)
+        );
+        assert_eq!(
+            ModemDateTime::read(b"\"24/10/17,12:34:56+99\""),
+            Err(HayesError::OutOfRange)
+        );
+        assert_eq!(
+            ModemDateTime::read(b"\"24/10/17,12:34"),
+            Err(HayesError::UnexpectedEnd)
+        );
+        assert_eq!(
+            ModemDateTime::read(b"24/10/17,12:34:56"),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_write_datetime() {
+        let mut buf = [0u8; 32];
+        assert_eq!(CCLK.write(&mut buf), Ok(22));
+        assert_eq!(&buf[..22], b"\"24/10/17,12:34:56+08\"");
+
+        let west = ModemDateTime {
+            time_zone: Some(TimeZone(-20)),
+            ..CCLK
+        };
+        assert_eq!(west.write(&mut buf), Ok(22));
+        assert_eq!(&buf[..22], b"\"24/10/17,12:34:56-20\"");
+
+        let local = ModemDateTime {
+            time_zone: None,
+            ..CCLK
+        };
+        assert_eq!(local.write(&mut buf), Ok(19));
+
+        let old = ModemDateTime { year: 1999, ..CCLK };
+        assert_eq!(old.write(&mut buf), Err(HayesError::OutOfRange));
+    }
+
+    #[test]
+    fn test_read_write_time_zone() {
+        assert_eq!(TimeZone::read(b"\"+08\""), Ok((TimeZone(8), 5)));
+        assert_eq!(TimeZone::read(b"-20,1"), Ok((TimeZone(-20), 3)));
+        assert_eq!(TimeZone::read(b"\"32\""), Ok((TimeZone(32), 4)));
+        assert!(TimeZone::read(b"\"+97\"").is_err());
+
+        let mut buf = [0u8; 8];
+        assert_eq!(TimeZone(-4).write(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"\"-04\"");
+    }
+
+    #[test]
+    fn test_unix_timestamp() {
+        // 2024-10-17 12:34:56 at UTC+2 is 10:34:56 UTC
+        assert_eq!(CCLK.to_unix_timestamp(), Ok(1_729_161_296));
+
+        let epoch = ModemDateTime::from_unix_timestamp(0, None).unwrap();
+        assert_eq!((epoch.year, epoch.month, epoch.day), (1970, 1, 1));
+
+        assert_eq!(
+            ModemDateTime::from_unix_timestamp(1_729_161_296, Some(TimeZone(8))),
+            Ok(CCLK)
+        );
+
+        // Crossing midnight backwards into a leap day
+        let datetime =
+            ModemDateTime::from_unix_timestamp(1_709_251_200, Some(TimeZone(-4))).unwrap();
+        assert_eq!((datetime.month, datetime.day, datetime.hour), (2, 29, 23));
+
+        assert_eq!(
+            ModemDateTime::from_unix_timestamp(i64::MAX, Some(TimeZone(8))),
+            Err(HayesError::OutOfRange)
+        );
+        assert_eq!(
+            ModemDateTime::from_unix_timestamp(i64::MIN, Some(TimeZone(-8))),
+            Err(HayesError::OutOfRange)
+        );
+        assert_eq!(
+            ModemDateTime::from_unix_timestamp(i64::MAX, None),
+            Err(HayesError::OutOfRange)
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn test_chrono_conversion() {
+        let datetime = chrono::DateTime::<chrono::FixedOffset>::try_from(CCLK).unwrap();
+        assert_eq!(Ok(datetime.timestamp()), CCLK.to_unix_timestamp());
+        assert_eq!(ModemDateTime::try_from(datetime), Ok(CCLK));
+    }
+
+    #[cfg(feature = "time")]
+    #[test]
+    fn test_time_conversion() {
+        let datetime = time::OffsetDateTime::try_from(CCLK).unwrap();
+        assert_eq!(Ok(datetime.unix_timestamp()), CCLK.to_unix_timestamp());
+        assert_eq!(ModemDateTime::try_from(datetime), Ok(CCLK));
+    }
+}
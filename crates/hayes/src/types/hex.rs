@@ -0,0 +1,647 @@
+use crate::{AtReadable, AtWritable, HayesError, error::Expected};
+
+pub(crate) const DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+
+/// Formatting options used when writing a [`Hex`] value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HexFormat {
+    /// Wrap the digits in double quotes (`"1A2B"`)
+    pub quoted: bool,
+    /// Prefix the digits with `0x` (`0x1A2B`)
+    pub prefix: bool,
+    /// Minimum number of digits, zero-padded on the left
+    pub width: u8,
+}
+
+/// Unsigned integer parameter encoded in hexadecimal
+///
+/// Reading accepts quoted (`"1A2B"`) and bare (`1A2B`) digits of either case,
+/// with or without a `0x` prefix, and records the observed format so the value
+/// is written back the same way. Values constructed with [`Hex::new`] are
+/// written bare and unpadded unless configured otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Hex<T> {
+    /// The wrapped value
+    pub value: T,
+    /// How the value is written
+    pub format: HexFormat,
+}
+
+impl<T> Hex<T> {
+    /// Wrap a value, written as bare, unpadded uppercase digits
+    pub const fn new(value: T) -> Self {
+        Hex {
+            value,
+            format: HexFormat {
+                quoted: false,
+                prefix: false,
+                width: 0,
+            },
+        }
+    }
+
+    /// Write the value wrapped in double quotes
+    #[must_use]
+    pub const fn quoted(mut self) -> Self {
+        self.format.quoted = true;
+        self
+    }
+
+    /// Write the value with a `0x` prefix
+    #[must_use]
+    pub const fn prefixed(mut self) -> Self {
+        self.format.prefix = true;
+        self
+    }
+
+    /// Zero-pad the value to at least `width` digits
+    #[must_use]
+    pub const fn width(mut self, width: u8) -> Self {
+        self.format.width = width;
+        self
+    }
+}
+
+impl<T> From<T> for Hex<T> {
+    fn from(value: T) -> Self {
+        Hex::new(value)
+    }
+}
+
+pub(crate) fn hex_digit(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+macro_rules! impl_hex {
+    ($($ty:ty),*) => {
+        $(
+            impl<'at> AtReadable<'at> for Hex<$ty> {
+                fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+                    Self::read_located(input).map_err(HayesError::unlocated)
+                }
+
+                fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+                    if input.is_empty() {
+                        return Err(HayesError::UnexpectedEnd.expected(Expected::HexDigit));
+                    }
+
+                    let quoted = input[0] == b'"';
+                    let mut consumed = usize::from(quoted);
+
+                    let prefix = matches!(input[consumed..], [b'0', b'x' | b'X', ..]);
+                    if prefix {
+                        consumed += 2;
+                    }
+
+                    let mut value: $ty = 0;
+                    let mut digits = 0;
+
+                    for &byte in &input[consumed..] {
+                        let Some(digit) = hex_digit(byte) else {
+                            break;
+                        };
+
+                        value = value
+                            .checked_mul(16)
+                            .and_then(|v| v.checked_add(<$ty>::from(digit)))
+                            .ok_or(HayesError::ParseError.at_offset(consumed + digits))?;
+                        digits += 1;
+                    }
+
+                    if digits == 0 {
+                        return Err(HayesError::ParseError
+                            .expected(Expected::HexDigit)
+                            .at_offset(consumed));
+                    }
+                    consumed += digits;
+
+                    if quoted {
+                        match input.get(consumed) {
+                            Some(b'"') => consumed += 1,
+                            Some(_) => {
+                                return Err(HayesError::InvalidFormat
+                                    .expected(Expected::Quote)
+                                    .at_offset(consumed));
+                            }
+                            None => {
+                                return Err(HayesError::UnexpectedEnd
+                                    .expected(Expected::Quote)
+                                    .at_offset(consumed));
+                            }
+                        }
+                    }
+
+                    Ok((
+                        Hex {
+                            value,
+                            format: HexFormat {
+                                quoted,
+                                prefix,
+                                width: u8::try_from(digits).unwrap_or(u8::MAX),
+                            },
+                        },
+                        consumed,
+                    ))
+                }
+            }
+
+            impl AtWritable for Hex<$ty> {
+                fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+                    let needed = (<$ty>::BITS - self.value.leading_zeros()).div_ceil(4).max(1) as usize;
+                    let digits = needed.max(usize::from(self.format.width));
+
+                    let quotes = if self.format.quoted { 2 } else { 0 };
+                    let prefix = if self.format.prefix { 2 } else { 0 };
+                    let required = digits + quotes + prefix;
+
+                    if output.len() < required {
+                        return Err(HayesError::InsufficientBuffer {
+                            required,
+                            available: output.len(),
+                        });
+                    }
+
+                    let mut pos = 0;
+                    if self.format.quoted {
+                        output[pos] = b'"';
+                        pos += 1;
+                    }
+                    if self.format.prefix {
+                        output[pos..pos + 2].copy_from_slice(b"0x");
+                        pos += 2;
+                    }
+
+                    let mut value = self.value;
+                    for slot in output[pos..pos + digits].iter_mut().rev() {
+                        #[allow(clippy::cast_possible_truncation)]
+                        let digit = (value % 16) as usize;
+                        *slot = DIGITS[digit];
+                        value /= 16;
+                    }
+                    pos += digits;
+
+                    if self.format.quoted {
+                        output[pos] = b'"';
+                        pos += 1;
+                    }
+
+                    Ok(pos)
+                }
+            }
+        )*
+    };
+}
+
+impl_hex!(u8, u16, u32, u64, u128, usize);
+
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum HexBytesRepr<'a> {
+    Hex(&'a [u8]),
+    Bytes(&'a [u8]),
+}
+
+/// Byte string encoded as pairs of hexadecimal digits, such as `"A0B1C2"`
+///
+/// Used for SMS PDUs, SIM APDUs and other binary parameters. When read, the
+/// digits are borrowed from the input and decoded on access. When created from
+/// bytes with [`HexBytes::new`], the bytes are encoded as they're written.
+///
+/// Reading accepts quoted and bare digits of either case and records whether
+/// they were quoted. Values are written as uppercase digits.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HexBytes<'a> {
+    repr: HexBytesRepr<'a>,
+    quoted: bool,
+}
+
+impl<'a> HexBytes<'a> {
+    /// Wrap bytes to be written as bare hex digits
+    #[must_use]
+    pub const fn new(bytes: &'a [u8]) -> Self {
+        HexBytes {
+            repr: HexBytesRepr::Bytes(bytes),
+            quoted: false,
+        }
+    }
+
+    /// Wrap hex digits
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if there are an odd number of
+    /// digits, or [`HayesError::ParseError`] if `hex` contains a non-hex
+    /// character.
+    pub fn from_hex(hex: &'a [u8]) -> Result<Self, HayesError> {
+        if !hex.len().is_multiple_of(2) {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        if hex.iter().any(|&b| hex_digit(b).is_none()) {
+            return Err(HayesError::ParseError);
+        }
+
+        Ok(HexBytes {
+            repr: HexBytesRepr::Hex(hex),
+            quoted: false,
+        })
+    }
+
+    /// Write the digits wrapped in double quotes
+    #[must_use]
+    pub const fn quoted(mut self) -> Self {
+        self.quoted = true;
+        self
+    }
+
+    /// Whether the digits were, or will be, wrapped in double quotes
+    #[must_use]
+    pub const fn is_quoted(&self) -> bool {
+        self.quoted
+    }
+
+    /// Number of bytes
+    #[must_use]
+    pub const fn len(&self) -> usize {
+        match self.repr {
+            HexBytesRepr::Hex(hex) => hex.len() / 2,
+            HexBytesRepr::Bytes(bytes) => bytes.len(),
+        }
+    }
+
+    /// Whether there are no bytes
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The byte at `index`, if in range
+    #[must_use]
+    pub fn get(&self, index: usize) -> Option<u8> {
+        match self.repr {
+            HexBytesRepr::Hex(hex) => {
+                let pair = hex.get(index * 2..index * 2 + 2)?;
+                // Digits were validated when the value was created
+                Some(
+                    pair.iter()
+                        .fold(0, |byte, &b| (byte << 4) | hex_digit(b).unwrap_or_default()),
+                )
+            }
+            HexBytesRepr::Bytes(bytes) => bytes.get(index).copied(),
+        }
+    }
+
+    /// The bytes in `range`, if in range
+    #[must_use]
+    pub fn slice(&self, range: core::ops::Range<usize>) -> Option<Self> {
+        let repr = match self.repr {
+            HexBytesRepr::Hex(hex) => HexBytesRepr::Hex(hex.get(range.start * 2..range.end * 2)?),
+            HexBytesRepr::Bytes(bytes) => HexBytesRepr::Bytes(bytes.get(range)?),
+        };
+
+        Some(HexBytes {
+            repr,
+            quoted: self.quoted,
+        })
+    }
+
+    /// Iterate over the bytes
+    #[must_use]
+    pub fn iter(&self) -> HexBytesIter<'a> {
+        HexBytesIter {
+            bytes: *self,
+            index: 0,
+        }
+    }
+
+    /// Decode the bytes into `buffer`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b [u8], HayesError> {
+        let required = self.len();
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        for (slot, byte) in buffer.iter_mut().zip(self.iter()) {
+            *slot = byte;
+        }
+
+        Ok(&buffer[..required])
+    }
+}
+
+impl PartialEq for HexBytes<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.quoted == other.quoted && self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl Eq for HexBytes<'_> {}
+
+impl<'a> IntoIterator for HexBytes<'a> {
+    type Item = u8;
+    type IntoIter = HexBytesIter<'a>;
+
+    fn into_iter(self) -> HexBytesIter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &HexBytes<'a> {
+    type Item = u8;
+    type IntoIter = HexBytesIter<'a>;
+
+    fn into_iter(self) -> HexBytesIter<'a> {
+        self.iter()
+    }
+}
+
+/// Iterator over the bytes of a [`HexBytes`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct HexBytesIter<'a> {
+    bytes: HexBytes<'a>,
+    index: usize,
+}
+
+impl Iterator for HexBytesIter<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.bytes.get(self.index)?;
+        self.index += 1;
+        Some(byte)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.bytes.len() - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for HexBytesIter<'_> {}
+
+impl<'at> AtReadable<'at> for HexBytes<'at> {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        Self::read_located(input).map_err(HayesError::unlocated)
+    }
+
+    fn read_located(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        if input.is_empty() {
+            return Err(HayesError::UnexpectedEnd);
+        }
+
+        let quoted = input[0] == b'"';
+        let start = usize::from(quoted);
+        let digits = input[start..]
+            .iter()
+            .take_while(|&&b| hex_digit(b).is_some())
+            .count();
+
+        if digits == 0 && !quoted {
+            return Err(HayesError::ParseError.expected(Expected::HexDigit));
+        }
+
+        let mut consumed = start + digits;
+        if quoted {
+            match input.get(consumed) {
+                Some(b'"') => consumed += 1,
+                Some(_) => {
+                    return Err(HayesError::InvalidFormat
+                        .expected(Expected::Quote)
+                        .at_offset(consumed));
+                }
+                None => {
+                    return Err(HayesError::UnexpectedEnd
+                        .expected(Expected::Quote)
+                        .at_offset(consumed));
+                }
+            }
+        }
+
+        let mut bytes = HexBytes::from_hex(&input[start..start + digits])?;
+        bytes.quoted = quoted;
+
+        Ok((bytes, consumed))
+    }
+}
+
+impl AtWritable for HexBytes<'_> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let quotes = if self.quoted { 2 } else { 0 };
+        let required = self.len() * 2 + quotes;
+
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        let mut pos = 0;
+        if self.quoted {
+            output[pos] = b'"';
+            pos += 1;
+        }
+
+        match self.repr {
+            HexBytesRepr::Hex(hex) => {
+                for (slot, &b) in output[pos..pos + hex.len()].iter_mut().zip(hex) {
+                    *slot = b.to_ascii_uppercase();
+                }
+            }
+            HexBytesRepr::Bytes(bytes) => {
+                for (digits, &byte) in output[pos..].chunks_exact_mut(2).zip(bytes) {
+                    digits[0] = DIGITS[usize::from(byte >> 4)];
+                    digits[1] = DIGITS[usize::from(byte & 0xF)];
+                }
+            }
+        }
+        pos += self.len() * 2;
+
+        if self.quoted {
+            output[pos] = b'"';
+            pos += 1;
+        }
+
+        Ok(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_hex() {
+        let (hex, consumed) = Hex::<u16>::read(b"\"1A2B\",").unwrap();
+        assert_eq!((hex.value, consumed), (0x1A2B, 6));
+        assert!(hex.format.quoted);
+        assert_eq!(hex.format.width, 4);
+
+        let (hex, consumed) = Hex::<u32>::read(b"\"01A2D101\"").unwrap();
+        assert_eq!((hex.value, consumed), (0x01A2_D101, 10));
+        assert_eq!(hex.format.width, 8);
+
+        let (hex, consumed) = Hex::<u32>::read(b"0x01090000\r\n").unwrap();
+        assert_eq!((hex.value, consumed), (0x0109_0000, 10));
+        assert!(hex.format.prefix);
+        assert!(!hex.format.quoted);
+
+        assert_eq!(
+            Hex::<u8>::read(b"ff").map(|(h, c)| (h.value, c)),
+            Ok((0xFF, 2))
+        );
+        assert_eq!(
+            Hex::<u8>::read(b"\"0X0a\"").map(|(h, c)| (h.value, c)),
+            Ok((0x0A, 6))
+        );
+    }
+
+    #[test]
+    fn test_read_hex_invalid() {
+        assert_eq!(Hex::<u8>::read(b""), Err(HayesError::UnexpectedEnd));
+        assert_eq!(Hex::<u8>::read(b"xyz"), Err(HayesError::ParseError));
+        assert_eq!(Hex::<u8>::read(b"\"\""), Err(HayesError::ParseError));
+        assert_eq!(Hex::<u8>::read(b"\"1A"), Err(HayesError::UnexpectedEnd));
+        assert_eq!(Hex::<u8>::read(b"\"1A-\""), Err(HayesError::InvalidFormat));
+
+        // Overflow
+        assert_eq!(Hex::<u8>::read(b"100"), Err(HayesError::ParseError));
+        assert_eq!(Hex::<u16>::read(b"0x10000"), Err(HayesError::ParseError));
+
+        // Leading zeros don't count towards overflow
+        assert_eq!(Hex::<u8>::read(b"0000FF").map(|(h, _)| h.value), Ok(0xFF));
+    }
+
+    #[test]
+    fn test_read_hex_located() {
+        let location = |input: &[u8]| {
+            let error = Hex::<u8>::read_located(input).unwrap_err();
+            error.location().map(|l| (l.offset, l.expected))
+        };
+
+        assert_eq!(location(b"0xZZ"), Some((2, Some(Expected::HexDigit))));
+        assert_eq!(location(b"\"1A-\""), Some((3, Some(Expected::Quote))));
+        assert_eq!(location(b"\"1A"), Some((3, Some(Expected::Quote))));
+        assert_eq!(location(b"100"), Some((2, None)));
+    }
+
+    #[test]
+    fn test_write_hex() {
+        let mut buf = [0u8; 20];
+
+        assert_eq!(Hex::new(0x1A2Bu16).write(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"1A2B");
+
+        assert_eq!(Hex::new(0u8).write(&mut buf), Ok(1));
+        assert_eq!(&buf[..1], b"0");
+
+        assert_eq!(Hex::new(0x1A2Bu16).quoted().write(&mut buf), Ok(6));
+        assert_eq!(&buf[..6], b"\"1A2B\"");
+
+        assert_eq!(
+            Hex::new(0x01A2_D101_u32).quoted().width(8).write(&mut buf),
+            Ok(10)
+        );
+        assert_eq!(&buf[..10], b"\"01A2D101\"");
+
+        assert_eq!(
+            Hex::new(0x0109_0000u32).prefixed().width(8).write(&mut buf),
+            Ok(10)
+        );
+        assert_eq!(&buf[..10], b"0x01090000");
+
+        // Width smaller than the value doesn't truncate
+        assert_eq!(Hex::new(0xABCu16).width(2).write(&mut buf), Ok(3));
+        assert_eq!(&buf[..3], b"ABC");
+
+        assert_eq!(
+            Hex::new(u128::MAX).write(&mut buf[..]),
+            Err(HayesError::InsufficientBuffer {
+                required: 32,
+                available: 20,
+            })
+        );
+    }
+
+    #[test]
+    fn test_hex_round_trip() {
+        let mut buf = [0u8; 20];
+        let (hex, consumed) = Hex::<u32>::read(b"\"0x00FF\"").unwrap();
+        assert_eq!(hex.write(&mut buf), Ok(consumed));
+        assert_eq!(&buf[..consumed], b"\"0x00FF\"");
+    }
+
+    #[test]
+    fn test_read_hex_bytes() {
+        let (bytes, consumed) = HexBytes::read(b"\"a0B1c2\",1").unwrap();
+        assert_eq!(consumed, 8);
+        assert!(bytes.is_quoted());
+        assert_eq!(bytes.len(), 3);
+        assert!(bytes.iter().eq([0xA0, 0xB1, 0xC2]));
+        assert_eq!(bytes.get(1), Some(0xB1));
+        assert_eq!(bytes.get(3), None);
+        assert_eq!(
+            bytes.slice(1..3),
+            Some(HexBytes::new(&[0xB1, 0xC2]).quoted())
+        );
+
+        let (bytes, consumed) = HexBytes::read(b"0791\r\n").unwrap();
+        assert_eq!(consumed, 4);
+        assert_eq!(bytes, HexBytes::new(&[0x07, 0x91]));
+
+        let (bytes, consumed) = HexBytes::read(b"\"\"").unwrap();
+        assert_eq!((bytes.len(), consumed), (0, 2));
+
+        assert_eq!(HexBytes::read(b"079"), Err(HayesError::InvalidFormat));
+        assert_eq!(HexBytes::read(b"\"07,\""), Err(HayesError::InvalidFormat));
+        assert_eq!(HexBytes::read(b"\"07"), Err(HayesError::UnexpectedEnd));
+        assert_eq!(HexBytes::read(b"xyz"), Err(HayesError::ParseError));
+    }
+
+    #[test]
+    fn test_write_hex_bytes() {
+        let mut buf = [0u8; 16];
+
+        assert_eq!(HexBytes::new(&[0x00, 0x9F, 0xFF]).write(&mut buf), Ok(6));
+        assert_eq!(&buf[..6], b"009FFF");
+
+        assert_eq!(HexBytes::new(&[0x12]).quoted().write(&mut buf), Ok(4));
+        assert_eq!(&buf[..4], b"\"12\"");
+
+        let bytes = HexBytes::from_hex(b"9000ab").unwrap();
+        assert_eq!(bytes.write(&mut buf), Ok(6));
+        assert_eq!(&buf[..6], b"9000AB");
+
+        let mut out = [0u8; 2];
+        assert_eq!(
+            bytes.decode_into(&mut out),
+            Err(HayesError::InsufficientBuffer {
+                required: 3,
+                available: 2,
+            })
+        );
+        assert_eq!(
+            bytes.write(&mut buf[..5]),
+            Err(HayesError::InsufficientBuffer {
+                required: 6,
+                available: 5,
+            })
+        );
+    }
+}
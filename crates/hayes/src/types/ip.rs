@@ -0,0 +1,320 @@
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use crate::{
+    AtReadable, AtWritable, HayesError,
+    impls::{
+        net::{Ipv6Display, parse_ip, parse_octets, read_text},
+        write_fmt,
+    },
+};
+
+/// IPv6 notation, mirroring the `+CGPIAF` parameters of 3GPP TS 27.007
+///
+/// The [`Default`] is the 27.007 default of dotted-decimal notation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+#[allow(clippy::struct_excessive_bools)]
+pub struct Ipv6Format {
+    /// Use colon notation (`2001:DB8::1`) instead of dotted decimal
+    pub colon: bool,
+    /// Write subnet masks as a prefix length (`/64`) instead of a full address
+    pub prefix_length: bool,
+    /// Keep leading zeros in each group (`2001:0DB8::0001`)
+    pub leading_zeros: bool,
+    /// Replace the longest run of zero groups with `::`
+    pub compress_zeros: bool,
+}
+
+impl Ipv6Format {
+    /// 27.007 dotted-decimal notation (`+CGPIAF=0,0,0,0`)
+    pub const DOTTED: Self = Ipv6Format {
+        colon: false,
+        prefix_length: false,
+        leading_zeros: false,
+        compress_zeros: false,
+    };
+
+    /// Compressed colon notation with prefix lengths (`+CGPIAF=1,1,0,1`)
+    pub const COLON: Self = Ipv6Format {
+        colon: true,
+        prefix_length: true,
+        leading_zeros: false,
+        compress_zeros: true,
+    };
+}
+
+/// An address written with an explicit [`Ipv6Format`]
+///
+/// IPv4 addresses are unaffected by the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Formatted<T> {
+    /// The wrapped address
+    pub value: T,
+    /// The notation used for IPv6 addresses
+    pub format: Ipv6Format,
+}
+
+impl<T> Formatted<T> {
+    /// Wrap a value to be written with `format`
+    pub const fn new(value: T, format: Ipv6Format) -> Self {
+        Formatted { value, format }
+    }
+}
+
+/// Address and subnet mask pair, such as `+CGCONTRDP`'s `<local_addr and subnet_mask>`
+///
+/// Reads the 27.007 combined dotted notation (`"10.0.0.2.255.255.255.0"`,
+/// or 32 octets for IPv6), as well as the `+CGPIAF` colon forms
+/// `"2001:DB8::1 FFFF:FFFF::"` and `"2001:DB8::1/32"`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AddressMask {
+    /// The address
+    pub address: IpAddr,
+    /// The subnet mask, of the same family as `address`
+    pub mask: IpAddr,
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for AddressMask {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        defmt::write!(
+            f,
+            "AddressMask {{ address: {}, mask: {} }}",
+            defmt::Display2Format(&self.address),
+            defmt::Display2Format(&self.mask),
+        );
+    }
+}
+
+impl AddressMask {
+    /// Build a pair from an address and a prefix length
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `prefix` is longer than the address.
+    pub fn with_prefix(address: IpAddr, prefix: u8) -> Result<Self, HayesError> {
+        let mask = match address {
+            IpAddr::V4(_) if prefix <= 32 => IpAddr::V4(Ipv4Addr::from(
+                u32::MAX.checked_shl(32 - u32::from(prefix)).unwrap_or(0),
+            )),
+            IpAddr::V6(_) if prefix <= 128 => IpAddr::V6(Ipv6Addr::from(
+                u128::MAX.checked_shl(128 - u32::from(prefix)).unwrap_or(0),
+            )),
+            _ => return Err(HayesError::OutOfRange),
+        };
+
+        Ok(AddressMask { address, mask })
+    }
+
+    /// The number of leading one bits in the mask
+    #[must_use]
+    pub fn prefix_len(&self) -> u8 {
+        let ones = match self.mask {
+            IpAddr::V4(mask) => u32::from(mask).leading_ones(),
+            IpAddr::V6(mask) => u128::from(mask).leading_ones(),
+        };
+
+        #[allow(clippy::cast_possible_truncation)]
+        let ones = ones as u8;
+        ones
+    }
+
+    fn parse(text: &[u8]) -> Result<Self, HayesError> {
+        if let Some(slash) = text.iter().position(|&b| b == b'/') {
+            let (prefix, consumed) = u8::read(&text[slash + 1..])?;
+            if slash + 1 + consumed != text.len() {
+                return Err(HayesError::ParseError);
+            }
+            return AddressMask::with_prefix(parse_ip(&text[..slash])?, prefix);
+        }
+
+        if let Some(space) = text.iter().position(|&b| b == b' ') {
+            let address = parse_ip(&text[..space])?;
+            let mask = parse_ip(&text[space + 1..])?;
+            if address.is_ipv4() != mask.is_ipv4() {
+                return Err(HayesError::InvalidFormat);
+            }
+            return Ok(AddressMask { address, mask });
+        }
+
+        match text.split(|&b| b == b'.').count() {
+            8 => {
+                let mut octets = [0; 8];
+                parse_octets(text, &mut octets)?;
+                Ok(AddressMask {
+                    address: IpAddr::from([octets[0], octets[1], octets[2], octets[3]]),
+                    mask: IpAddr::from([octets[4], octets[5], octets[6], octets[7]]),
+                })
+            }
+            32 => {
+                let mut octets = [0; 32];
+                parse_octets(text, &mut octets)?;
+                let mut address = [0; 16];
+                let mut mask = [0; 16];
+                address.copy_from_slice(&octets[..16]);
+                mask.copy_from_slice(&octets[16..]);
+                Ok(AddressMask {
+                    address: IpAddr::from(address),
+                    mask: IpAddr::from(mask),
+                })
+            }
+            _ => Err(HayesError::InvalidFormat),
+        }
+    }
+}
+
+impl<'at> AtReadable<'at> for AddressMask {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (text, consumed) = read_text(input)?;
+        Ok((AddressMask::parse(text)?, consumed))
+    }
+}
+
+/// Writes the default [`Ipv6Format`], dotted decimal
+impl AtWritable for AddressMask {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        Formatted::new(*self, Ipv6Format::default()).write(output)
+    }
+}
+
+impl AtWritable for Formatted<Ipv6Addr> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        write_fmt(
+            output,
+            format_args!("\"{}\"", Ipv6Display(self.value, self.format)),
+        )
+    }
+}
+
+impl AtWritable for Formatted<IpAddr> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        match self.value {
+            IpAddr::V4(addr) => addr.write(output),
+            IpAddr::V6(addr) => Formatted::new(addr, self.format).write(output),
+        }
+    }
+}
+
+impl AtWritable for Formatted<AddressMask> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let AddressMask { address, mask } = self.value;
+
+        match (address, mask) {
+            (IpAddr::V4(address), IpAddr::V4(mask)) => {
+                write_fmt(output, format_args!("\"{address}.{mask}\""))
+            }
+            (IpAddr::V6(address), IpAddr::V6(mask)) if self.format.colon => {
+                let address = Ipv6Display(address, self.format);
+                if self.format.prefix_length {
+                    write_fmt(
+                        output,
+                        format_args!("\"{address}/{}\"", self.value.prefix_len()),
+                    )
+                } else {
+                    let mask = Ipv6Display(mask, self.format);
+                    write_fmt(output, format_args!("\"{address} {mask}\""))
+                }
+            }
+            (IpAddr::V6(address), IpAddr::V6(mask)) => write_fmt(
+                output,
+                format_args!(
+                    "\"{}.{}\"",
+                    Ipv6Display(address, self.format),
+                    Ipv6Display(mask, self.format)
+                ),
+            ),
+            _ => Err(HayesError::InvalidFormat),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v6(segments: [u16; 8]) -> IpAddr {
+        IpAddr::from(segments)
+    }
+
+    #[test]
+    fn test_read_address_mask() {
+        let (pair, consumed) = AddressMask::read(b"\"10.0.0.2.255.255.255.0\",").unwrap();
+        assert_eq!(consumed, 24);
+        assert_eq!(pair.address, IpAddr::from([10, 0, 0, 2]));
+        assert_eq!(pair.mask, IpAddr::from([255, 255, 255, 0]));
+        assert_eq!(pair.prefix_len(), 24);
+
+        let (pair, _) = AddressMask::read(b"\"2001:DB8::1 FFFF:FFFF::\"").unwrap();
+        assert_eq!(pair.address, v6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]));
+        assert_eq!(pair.prefix_len(), 32);
+
+        let (pair, _) = AddressMask::read(b"\"2001:DB8::1/64\"").unwrap();
+        assert_eq!(pair.mask, v6([0xffff, 0xffff, 0xffff, 0xffff, 0, 0, 0, 0]));
+
+        let dotted = b"\"32.1.13.184.0.0.0.0.0.0.0.0.0.0.0.1.\
+            255.255.255.255.255.255.255.255.0.0.0.0.0.0.0.0\"";
+        let (pair, consumed) = AddressMask::read(dotted).unwrap();
+        assert_eq!(consumed, dotted.len());
+        assert_eq!(pair.address, v6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]));
+        assert_eq!(pair.prefix_len(), 64);
+
+        assert!(AddressMask::read(b"\"10.0.0.2\"").is_err());
+        assert!(AddressMask::read(b"\"10.0.0.2/33\"").is_err());
+        assert!(AddressMask::read(b"\"10.0.0.2 FFFF::\"").is_err());
+    }
+
+    #[test]
+    fn test_write_formatted_ipv6() {
+        let mut buf = [0u8; 160];
+        let addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+
+        let len = Formatted::new(addr, Ipv6Format::DOTTED)
+            .write(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], b"\"32.1.13.184.0.0.0.0.0.0.0.0.0.0.0.1\"");
+
+        let format = Ipv6Format {
+            colon: true,
+            leading_zeros: true,
+            ..Ipv6Format::default()
+        };
+        let len = Formatted::new(addr, format).write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"\"2001:0DB8:0000:0000:0000:0000:0000:0001\"");
+
+        let len = Formatted::new(IpAddr::from([10, 0, 0, 1]), Ipv6Format::DOTTED)
+            .write(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], b"\"10.0.0.1\"");
+    }
+
+    #[test]
+    fn test_write_address_mask() {
+        let mut buf = [0u8; 160];
+
+        let pair = AddressMask::with_prefix(IpAddr::from([10, 0, 0, 2]), 24).unwrap();
+        let len = pair.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"\"10.0.0.2.255.255.255.0\"");
+
+        let pair = AddressMask::with_prefix(v6([0x2001, 0xdb8, 0, 0, 0, 0, 0, 1]), 32).unwrap();
+        let len = Formatted::new(pair, Ipv6Format::COLON)
+            .write(&mut buf)
+            .unwrap();
+        assert_eq!(&buf[..len], b"\"2001:DB8::1/32\"");
+
+        let format = Ipv6Format {
+            prefix_length: false,
+            ..Ipv6Format::COLON
+        };
+        let len = Formatted::new(pair, format).write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"\"2001:DB8::1 FFFF:FFFF::\"");
+
+        let len = pair.write(&mut buf).unwrap();
+        assert_eq!(
+            &buf[..len],
+            b"\"32.1.13.184.0.0.0.0.0.0.0.0.0.0.0.1.255.255.255.255.0.0.0.0.0.0.0.0.0.0.0.0\""
+        );
+
+        assert!(AddressMask::with_prefix(IpAddr::from([10, 0, 0, 2]), 33).is_err());
+    }
+}
@@ -0,0 +1,92 @@
+use crate::{AtContext, AtReadable, AtReadableWith, AtWritable, AtWritableWith, HayesError};
+
+/// Optional field that reads as `None` when it isn't valid for `T`
+///
+/// Unlike `Option<T>`, which only reads an empty field as `None`, any error
+/// reading the field is swallowed and nothing is consumed. The invalid field
+/// is left in the input, so it has to be skipped before the following fields
+/// can be read. Written like `Option<T>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Lenient<T>(pub Option<T>);
+
+impl<T> Lenient<T> {
+    /// The value, if it was valid
+    #[must_use]
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+}
+
+impl<T> From<Option<T>> for Lenient<T> {
+    fn from(value: Option<T>) -> Self {
+        Lenient(value)
+    }
+}
+
+impl<'at, T: AtReadable<'at>> AtReadable<'at> for Lenient<T> {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        match Option::<T>::read(input) {
+            Ok((value, consumed)) => Ok((Lenient(value), consumed)),
+            Err(_) => Ok((Lenient(None), 0)),
+        }
+    }
+}
+
+impl<'at, T: AtReadableWith<'at>> AtReadableWith<'at> for Lenient<T> {
+    fn read_with(input: &'at [u8], context: &AtContext) -> Result<(Self, usize), HayesError> {
+        match Option::<T>::read_with(input, context) {
+            Ok((value, consumed)) => Ok((Lenient(value), consumed)),
+            Err(_) => Ok((Lenient(None), 0)),
+        }
+    }
+}
+
+impl<T: AtWritable> AtWritable for Lenient<T> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        self.0.write(output)
+    }
+}
+
+impl<T: AtWritableWith> AtWritableWith for Lenient<T> {
+    fn write_with(&self, output: &mut [u8], context: &AtContext) -> Result<usize, HayesError> {
+        self.0.write_with(output, context)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::at::Fields;
+
+    #[test]
+    fn test_read() {
+        assert_eq!(Lenient::<u8>::read(b"42,1"), Ok((Lenient(Some(42)), 2)));
+        assert_eq!(Lenient::<u8>::read(b",1"), Ok((Lenient(None), 0)));
+        assert_eq!(Lenient::<u8>::read(b""), Ok((Lenient(None), 0)));
+        assert_eq!(Lenient::<u8>::read(b"abc,1"), Ok((Lenient(None), 0)));
+        assert_eq!(Lenient::<u8>::read(b"-"), Ok((Lenient(None), 0)));
+        assert_eq!(Lenient::<&str>::read(b"\"abc"), Ok((Lenient(None), 0)));
+    }
+
+    #[test]
+    fn test_read_fields() {
+        let mut fields = Fields::new(b",\"SM\",7");
+        assert_eq!(fields.next::<Lenient<u8>>(), Ok(Lenient(None)));
+        assert_eq!(fields.next::<&str>(), Ok("SM"));
+        assert_eq!(fields.next::<Lenient<u8>>(), Ok(Lenient(Some(7))));
+
+        // The invalid field is left for the next read
+        let mut fields = Fields::new(b"-,1");
+        assert_eq!(fields.next::<Lenient<u8>>(), Ok(Lenient(None)));
+        assert_eq!(fields.next::<u8>(), Err(HayesError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_write() {
+        let mut buf = [0u8; 4];
+        assert_eq!(Lenient(Some(42u8)).write(&mut buf), Ok(2));
+        assert_eq!(&buf[..2], b"42");
+        assert_eq!(Lenient::<u8>(None).write(&mut buf), Ok(0));
+    }
+}
@@ -0,0 +1,8 @@
+pub(crate) mod bits;
+pub(crate) mod datetime;
+pub(crate) mod hex;
+pub(crate) mod ip;
+pub(crate) mod lenient;
+pub(crate) mod phone;
+pub(crate) mod raw;
+pub(crate) mod timer;
@@ -0,0 +1,233 @@
+use crate::{AtReadable, AtWritable, HayesError};
+
+/// Type-of-address octet accompanying a phone number (3GPP TS 24.008 §10.5.4.7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TypeOfAddress(pub u8);
+
+impl TypeOfAddress {
+    /// Unknown type of number, ISDN numbering plan (129)
+    pub const UNKNOWN: Self = TypeOfAddress(129);
+    /// International number, ISDN numbering plan (145)
+    pub const INTERNATIONAL: Self = TypeOfAddress(145);
+    /// National number, ISDN numbering plan (161)
+    pub const NATIONAL: Self = TypeOfAddress(161);
+
+    /// The 3-bit type of number
+    #[must_use]
+    pub const fn type_of_number(self) -> u8 {
+        (self.0 >> 4) & 0b111
+    }
+
+    /// The 4-bit numbering plan identification
+    #[must_use]
+    pub const fn numbering_plan(self) -> u8 {
+        self.0 & 0b1111
+    }
+
+    /// Whether the type of number is international
+    #[must_use]
+    pub const fn is_international(self) -> bool {
+        self.type_of_number() == 0b001
+    }
+}
+
+/// Phone number with its type of address, such as `"+14255551234",145`
+///
+/// `+CLIP`, `+CMGS`, `+CPBW`, `+CCFC` and `+CSCA` carry a quoted number
+/// followed by a `<type>` octet; this reads and writes both as one value. When
+/// reading, a missing type is inferred from the number. When writing, a
+/// `+`-prefixed number is always written with [`TypeOfAddress::INTERNATIONAL`].
+///
+/// Numbers may only contain the dial characters `0-9*#+ABCD`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PhoneNumber<'at> {
+    number: &'at str,
+    type_of_address: TypeOfAddress,
+}
+
+impl<'at> PhoneNumber<'at> {
+    /// Create a phone number, inferring the type of address from a leading `+`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if the number contains anything
+    /// other than dial characters.
+    pub fn new(number: &'at str) -> Result<Self, HayesError> {
+        let type_of_address = if number.starts_with('+') {
+            TypeOfAddress::INTERNATIONAL
+        } else {
+            TypeOfAddress::UNKNOWN
+        };
+
+        PhoneNumber::with_type(number, type_of_address)
+    }
+
+    /// Create a phone number with an explicit type of address
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InvalidFormat`] if the number contains anything
+    /// other than dial characters.
+    pub fn with_type(number: &'at str, type_of_address: TypeOfAddress) -> Result<Self, HayesError> {
+        if !number.bytes().all(is_dial_character) {
+            return Err(HayesError::InvalidFormat);
+        }
+
+        Ok(PhoneNumber {
+            number,
+            type_of_address,
+        })
+    }
+
+    /// The number as written by the modem
+    #[must_use]
+    pub const fn number(&self) -> &'at str {
+        self.number
+    }
+
+    /// The type of address
+    #[must_use]
+    pub const fn type_of_address(&self) -> TypeOfAddress {
+        self.type_of_address
+    }
+}
+
+const fn is_dial_character(byte: u8) -> bool {
+    matches!(byte, b'0'..=b'9' | b'*' | b'#' | b'+' | b'A'..=b'D')
+}
+
+impl<'at> AtReadable<'at> for PhoneNumber<'at> {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (number, mut consumed) = <&str>::read(input)?;
+
+        // The type is only present if a number follows the comma
+        if input.get(consumed) == Some(&b',')
+            && input.get(consumed + 1).is_some_and(u8::is_ascii_digit)
+        {
+            let (octet, len) = u8::read(&input[consumed + 1..])?;
+            consumed += 1 + len;
+            return Ok((
+                PhoneNumber::with_type(number, TypeOfAddress(octet))?,
+                consumed,
+            ));
+        }
+
+        Ok((PhoneNumber::new(number)?, consumed))
+    }
+}
+
+impl AtWritable for PhoneNumber<'_> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let type_of_address = if self.number.starts_with('+') {
+            TypeOfAddress::INTERNATIONAL
+        } else {
+            self.type_of_address
+        };
+
+        let octet_digits = match type_of_address.0 {
+            0..=9 => 1,
+            10..=99 => 2,
+            _ => 3,
+        };
+        let required = self.number.len() + 2 + 1 + octet_digits;
+
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        let number = self.number.write(output)?;
+        output[number] = b',';
+        let octet = type_of_address.0.write(&mut output[number + 1..])?;
+
+        Ok(number + 1 + octet)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_phone_number() {
+        let (phone, consumed) = PhoneNumber::read(b"\"+14255551234\",145,,,,0").unwrap();
+        assert_eq!(consumed, 18);
+        assert_eq!(phone.number(), "+14255551234");
+        assert_eq!(phone.type_of_address(), TypeOfAddress::INTERNATIONAL);
+
+        let (phone, consumed) = PhoneNumber::read(b"\"4255551234\",129\r\n").unwrap();
+        assert_eq!(consumed, 16);
+        assert_eq!(phone.type_of_address(), TypeOfAddress::UNKNOWN);
+
+        // Missing type is inferred, the trailing comma belongs to the next field
+        let (phone, consumed) = PhoneNumber::read(b"\"+4917012345\",,1").unwrap();
+        assert_eq!(consumed, 13);
+        assert_eq!(phone.type_of_address(), TypeOfAddress::INTERNATIONAL);
+
+        // Withheld caller ID
+        let (phone, _) = PhoneNumber::read(b"\"\",128").unwrap();
+        assert_eq!(phone.number(), "");
+        assert_eq!(phone.type_of_address(), TypeOfAddress(128));
+
+        let (phone, _) = PhoneNumber::read(b"\"**21*123#\"").unwrap();
+        assert_eq!(phone.number(), "**21*123#");
+    }
+
+    #[test]
+    fn test_read_phone_number_invalid() {
+        assert_eq!(
+            PhoneNumber::read(b"\"555-1234\",129"),
+            Err(HayesError::InvalidFormat)
+        );
+        assert!(PhoneNumber::read(b"\"+1425\",999").is_err());
+        assert!(PhoneNumber::read(b"14255551234").is_err());
+    }
+
+    #[test]
+    fn test_type_of_address() {
+        assert!(TypeOfAddress::INTERNATIONAL.is_international());
+        assert!(!TypeOfAddress::UNKNOWN.is_international());
+        assert_eq!(TypeOfAddress::NATIONAL.type_of_number(), 0b010);
+        assert_eq!(TypeOfAddress::UNKNOWN.numbering_plan(), 0b0001);
+    }
+
+    #[test]
+    fn test_write_phone_number() {
+        let mut buf = [0u8; 32];
+
+        let phone = PhoneNumber::new("+14255551234").unwrap();
+        assert_eq!(phone.write(&mut buf), Ok(18));
+        assert_eq!(&buf[..18], b"\"+14255551234\",145");
+
+        let phone = PhoneNumber::new("4255551234").unwrap();
+        assert_eq!(phone.write(&mut buf), Ok(16));
+        assert_eq!(&buf[..16], b"\"4255551234\",129");
+
+        // A leading '+' always means international
+        let phone = PhoneNumber::with_type("+4255551234", TypeOfAddress::NATIONAL).unwrap();
+        assert_eq!(phone.write(&mut buf), Ok(17));
+        assert_eq!(&buf[..17], b"\"+4255551234\",145");
+
+        assert!(PhoneNumber::new("555 1234").is_err());
+    }
+
+    #[test]
+    fn test_write_phone_number_insufficient_buffer() {
+        let phone = PhoneNumber::new("+1425").unwrap();
+
+        for len in 7..11 {
+            let mut buf = [0u8; 16];
+            assert_eq!(
+                phone.write(&mut buf[..len]),
+                Err(HayesError::InsufficientBuffer {
+                    required: 11,
+                    available: len,
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use crate::{AtWritable, HayesError};
+
+/// Parameter written exactly as given, without quotes or checks
+///
+/// Strings written as `&str` or [`Text`](crate::charset::Text) are rejected
+/// if they contain characters that could end the parameter or the command
+/// line. `Raw` is the escape hatch for trusted data, such as a parameter the
+/// application quotes itself. Never use it for data from outside the device.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Raw<'a>(pub &'a [u8]);
+
+impl AtWritable for Raw<'_> {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        let required = self.0.len();
+        if output.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: output.len(),
+            });
+        }
+
+        output[..required].copy_from_slice(self.0);
+        Ok(required)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write() {
+        let mut buf = [0u8; 8];
+        assert_eq!(Raw(b"\"a\"\r;").write(&mut buf), Ok(5));
+        assert_eq!(&buf[..5], b"\"a\"\r;");
+        assert!(Raw(b"too long!").write(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,273 @@
+use core::time::Duration;
+
+use crate::{AtReadable, AtWritable, HayesError, types::bits::BitString};
+
+/// Unit bits marking a GPRS timer as deactivated
+const DEACTIVATED: u8 = 0b111;
+
+/// Largest value representable in the 5 timer value bits
+const MAX_VALUE: u64 = 0b1_1111;
+
+/// GPRS Timer 3 units (3GPP TS 24.008 §10.5.7.4a), finest first
+const TIMER_3_UNITS: [(u8, u64); 7] = [
+    (0b011, 2),
+    (0b100, 30),
+    (0b101, 60),
+    (0b000, 10 * 60),
+    (0b001, 60 * 60),
+    (0b010, 10 * 60 * 60),
+    (0b110, 320 * 60 * 60),
+];
+
+/// GPRS Timer 2 units (3GPP TS 24.008 §10.5.7.3), finest first
+const TIMER_2_UNITS: [(u8, u64); 3] = [(0b000, 2), (0b001, 60), (0b010, 6 * 60)];
+
+/// Find the unit and value closest to `duration`
+///
+/// Ties go to the finer unit.
+fn encode(duration: Duration, units: &[(u8, u64)]) -> Result<u8, HayesError> {
+    let target = duration.as_millis();
+    let mut best: Option<(u8, u128)> = None;
+
+    for &(unit, seconds) in units {
+        let step = u128::from(seconds) * 1000;
+        let value = (target + step / 2) / step;
+        if value > u128::from(MAX_VALUE) {
+            continue;
+        }
+
+        let error = (value * step).abs_diff(target);
+        if best.is_none_or(|(_, best_error)| error < best_error) {
+            #[allow(clippy::cast_possible_truncation)]
+            let octet = (unit << 5) | value as u8;
+            best = Some((octet, error));
+        }
+    }
+
+    best.map(|(octet, _)| octet).ok_or(HayesError::OutOfRange)
+}
+
+fn decode(octet: u8, units: &[(u8, u64)], fallback: u64) -> Option<Duration> {
+    let unit = octet >> 5;
+    if unit == DEACTIVATED {
+        return None;
+    }
+
+    let seconds = units
+        .iter()
+        .find(|&&(bits, _)| bits == unit)
+        .map_or(fallback, |&(_, seconds)| seconds);
+
+    Some(Duration::from_secs(u64::from(octet & 0x1F) * seconds))
+}
+
+/// Requested periodic TAU/RAU timer (T3412 extended), as used by `+CPSMS`
+///
+/// Encoded as a 3GPP TS 24.008 GPRS Timer 3 octet: 3 unit bits followed by
+/// 5 value bits, written as an 8-character bit string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PeriodicTau(u8);
+
+impl PeriodicTau {
+    /// The timer is deactivated
+    pub const DEACTIVATED: Self = PeriodicTau(DEACTIVATED << 5);
+
+    /// Create a timer from its raw GPRS Timer 3 octet
+    #[must_use]
+    pub const fn from_bits(octet: u8) -> Self {
+        PeriodicTau(octet)
+    }
+
+    /// The raw GPRS Timer 3 octet
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Encode the representable value nearest to `duration`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `duration` exceeds 31 × 320 hours.
+    pub fn from_duration(duration: Duration) -> Result<Self, HayesError> {
+        encode(duration, &TIMER_3_UNITS).map(PeriodicTau)
+    }
+
+    /// The timer duration, or `None` if the timer is deactivated
+    #[must_use]
+    pub fn to_duration(self) -> Option<Duration> {
+        decode(self.0, &TIMER_3_UNITS, 60 * 60)
+    }
+}
+
+impl TryFrom<Duration> for PeriodicTau {
+    type Error = HayesError;
+
+    fn try_from(duration: Duration) -> Result<Self, Self::Error> {
+        PeriodicTau::from_duration(duration)
+    }
+}
+
+/// Requested active time (T3324), as used by `+CPSMS`
+///
+/// Encoded as a 3GPP TS 24.008 GPRS Timer 2 octet: 3 unit bits followed by
+/// 5 value bits, written as an 8-character bit string.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ActiveTime(u8);
+
+impl ActiveTime {
+    /// The timer is deactivated
+    pub const DEACTIVATED: Self = ActiveTime(DEACTIVATED << 5);
+
+    /// Create a timer from its raw GPRS Timer 2 octet
+    #[must_use]
+    pub const fn from_bits(octet: u8) -> Self {
+        ActiveTime(octet)
+    }
+
+    /// The raw GPRS Timer 2 octet
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        self.0
+    }
+
+    /// Encode the representable value nearest to `duration`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::OutOfRange`] if `duration` exceeds 31 × 6 minutes.
+    pub fn from_duration(duration: Duration) -> Result<Self, HayesError> {
+        encode(duration, &TIMER_2_UNITS).map(ActiveTime)
+    }
+
+    /// The timer duration, or `None` if the timer is deactivated
+    ///
+    /// Unassigned unit values are interpreted as minutes, as required by the
+    /// specification.
+    #[must_use]
+    pub fn to_duration(self) -> Option<Duration> {
+        decode(self.0, &TIMER_2_UNITS, 60)
+    }
+}
+
+impl TryFrom<Duration> for ActiveTime {
+    type Error = HayesError;
+
+    fn try_from(duration: Duration) -> Result<Self, Self::Error> {
+        ActiveTime::from_duration(duration)
+    }
+}
+
+macro_rules! impl_timer {
+    ($($ty:ident),*) => {
+        $(
+            impl<'at> AtReadable<'at> for $ty {
+                fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+                    let (bits, consumed) = BitString::<8>::read(input)?;
+
+                    #[allow(clippy::cast_possible_truncation)]
+                    Ok(($ty(bits.value() as u8), consumed))
+                }
+            }
+
+            impl AtWritable for $ty {
+                fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+                    BitString::<8>::new(u32::from(self.0))?.write(output)
+                }
+            }
+        )*
+    };
+}
+
+impl_timer!(PeriodicTau, ActiveTime);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_periodic_tau_from_duration() {
+        // 1 hour: exact in both 10 minute and 1 hour units, finer unit wins
+        let tau = PeriodicTau::from_duration(Duration::from_hours(1)).unwrap();
+        assert_eq!(tau.bits(), 0b000_00110);
+
+        // 24 hours: 24 × 1 hour
+        let tau = PeriodicTau::from_duration(Duration::from_hours(24)).unwrap();
+        assert_eq!(tau.bits(), 0b001_11000);
+        assert_eq!(tau.to_duration(), Some(Duration::from_hours(24)));
+
+        // 61 seconds is equally close to 60 and 62 seconds, finer unit wins
+        let tau = PeriodicTau::from_duration(Duration::from_secs(61)).unwrap();
+        assert_eq!(tau.bits(), 0b011_11111);
+        assert_eq!(tau.to_duration(), Some(Duration::from_secs(62)));
+
+        // 96 minutes rounds to 10 × 10 minutes
+        let tau = PeriodicTau::from_duration(Duration::from_mins(96)).unwrap();
+        assert_eq!(tau.bits(), 0b000_01010);
+
+        // 100 hours rounds to 10 × 10 hours
+        let tau =
+            PeriodicTau::from_duration(Duration::from_hours(100) + Duration::from_secs(5)).unwrap();
+        assert_eq!(tau.bits(), 0b010_01010);
+
+        // Largest representable value
+        let max = Duration::from_hours(31 * 320);
+        assert_eq!(PeriodicTau::from_duration(max).unwrap().bits(), 0b110_11111);
+        assert_eq!(
+            PeriodicTau::from_duration(max * 2),
+            Err(HayesError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_active_time_from_duration() {
+        let time = ActiveTime::try_from(Duration::from_secs(6)).unwrap();
+        assert_eq!(time.bits(), 0b000_00011);
+
+        let time = ActiveTime::try_from(Duration::from_mins(10)).unwrap();
+        assert_eq!(time.bits(), 0b001_01010);
+
+        let time = ActiveTime::try_from(Duration::from_hours(1)).unwrap();
+        assert_eq!(time.bits(), 0b010_01010);
+
+        assert_eq!(
+            ActiveTime::try_from(Duration::from_hours(4)),
+            Err(HayesError::OutOfRange)
+        );
+    }
+
+    #[test]
+    fn test_timer_to_duration() {
+        assert_eq!(PeriodicTau::DEACTIVATED.to_duration(), None);
+        assert_eq!(ActiveTime::DEACTIVATED.to_duration(), None);
+
+        assert_eq!(
+            PeriodicTau::from_bits(0b110_00001).to_duration(),
+            Some(Duration::from_hours(320))
+        );
+
+        // Unassigned Timer 2 units are minutes
+        assert_eq!(
+            ActiveTime::from_bits(0b011_00010).to_duration(),
+            Some(Duration::from_mins(2))
+        );
+    }
+
+    #[test]
+    fn test_read_write_timer() {
+        assert_eq!(
+            PeriodicTau::read(b"\"00100001\",\"00000011\""),
+            Ok((PeriodicTau::from_bits(0b0010_0001), 10))
+        );
+        assert_eq!(
+            ActiveTime::read(b"\"00000011\""),
+            Ok((ActiveTime::from_bits(0b11), 10))
+        );
+
+        let mut buf = [0u8; 10];
+        assert_eq!(PeriodicTau::DEACTIVATED.write(&mut buf), Ok(10));
+        assert_eq!(&buf, b"\"11100000\"");
+    }
+}
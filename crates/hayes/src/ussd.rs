@@ -0,0 +1,547 @@
+//! Unstructured supplementary service data (3GPP TS 27.007 §7.15)
+//!
+//! USSD requests such as `*100#` are sent with [`Cusd`]. The network answers
+//! with a `+CUSD` result code, read as [`CusdResult`], whose string is encoded
+//! according to its data coding scheme. When the network expects a reply, such
+//! as a menu choice, the result is [`UssdStatus::FurtherActionRequired`] and
+//! the reply is sent with another [`Cusd`].
+
+use crate::{
+    AtCommand, AtContext, AtReadable, AtResponse, AtWritable, HayesError, HexBytes,
+    at::{CommandWriter, Fields, ResponseReader},
+    charset::{Charset, Text, TextChars, gsm7},
+    sms::pdu::Alphabet,
+    types::hex::HexBytesIter,
+};
+
+/// Cell broadcast data coding scheme, as used by USSD (3GPP TS 23.038 §5)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CbsDcs(pub u8);
+
+impl CbsDcs {
+    /// GSM 7-bit default alphabet, language unspecified (15)
+    pub const GSM7: Self = CbsDcs(0x0F);
+    /// 8-bit data (68)
+    pub const EIGHT_BIT: Self = CbsDcs(0x44);
+    /// UCS2 (72)
+    pub const UCS2: Self = CbsDcs(0x48);
+
+    /// The character set of the string
+    ///
+    /// Reserved codings are treated as the GSM 7-bit default alphabet, as
+    /// required by the specification. Strings coded with `0x10` or `0x11` start
+    /// with a language indication, which is kept.
+    #[must_use]
+    pub const fn alphabet(self) -> Alphabet {
+        match self.0 >> 4 {
+            0b0001 if self.0 & 0b1111 == 1 => Alphabet::Ucs2,
+            0b0100..=0b0111 | 0b1001 => match (self.0 >> 2) & 0b11 {
+                0b01 => Alphabet::EightBit,
+                0b10 => Alphabet::Ucs2,
+                _ => Alphabet::Gsm7,
+            },
+            0b1111 if self.0 & 0b100 != 0 => Alphabet::EightBit,
+            _ => Alphabet::Gsm7,
+        }
+    }
+
+    /// Whether the string is compressed
+    #[must_use]
+    pub const fn is_compressed(self) -> bool {
+        matches!(self.0 >> 4, 0b0110 | 0b0111)
+    }
+}
+
+impl<'at> AtReadable<'at> for CbsDcs {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (value, consumed) = u8::read(input)?;
+        Ok((CbsDcs(value), consumed))
+    }
+}
+
+impl AtWritable for CbsDcs {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        self.0.write(output)
+    }
+}
+
+/// How the string of a `+CUSD` result is encoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UssdEncoding {
+    /// Encoded in a character set, such as the TE character set for GSM 7-bit
+    /// strings, [`Charset::Ucs2`] for UCS2 strings, or [`Charset::Hex`] for
+    /// 8-bit data
+    Text(Charset),
+    /// GSM 7-bit septets packed into octets, written as hex digits
+    ///
+    /// Some modems pass GSM 7-bit strings through like this rather than
+    /// converting them to the TE character set.
+    PackedGsm7,
+}
+
+/// String of a `+CUSD` result, decoded according to its encoding
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UssdString<'at> {
+    raw: &'at [u8],
+    dcs: CbsDcs,
+    encoding: UssdEncoding,
+}
+
+impl<'at> UssdString<'at> {
+    /// Wrap a string coded with `dcs`
+    ///
+    /// GSM 7-bit strings are expected in the context's character set, UCS2
+    /// strings as UCS2 hex and 8-bit data as hex.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `raw` isn't valid in the expected encoding.
+    pub fn new(raw: &'at [u8], dcs: CbsDcs, context: &AtContext) -> Result<Self, HayesError> {
+        let charset = match dcs.alphabet() {
+            Alphabet::Gsm7 => context.charset,
+            Alphabet::EightBit => Charset::Hex,
+            Alphabet::Ucs2 => Charset::Ucs2,
+        };
+
+        UssdString {
+            raw,
+            dcs,
+            encoding: UssdEncoding::Text(charset),
+        }
+        .with_encoding(UssdEncoding::Text(charset))
+    }
+
+    /// Decode the string with another encoding
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the string isn't valid in `encoding`.
+    pub fn with_encoding(self, encoding: UssdEncoding) -> Result<Self, HayesError> {
+        match encoding {
+            UssdEncoding::Text(charset) => charset.validate(self.raw)?,
+            UssdEncoding::PackedGsm7 => {
+                HexBytes::from_hex(self.raw)?;
+            }
+        }
+
+        Ok(UssdString { encoding, ..self })
+    }
+
+    /// The string as received
+    #[must_use]
+    pub const fn raw(&self) -> &'at [u8] {
+        self.raw
+    }
+
+    /// The data coding scheme
+    #[must_use]
+    pub const fn dcs(&self) -> CbsDcs {
+        self.dcs
+    }
+
+    /// How the string is decoded
+    #[must_use]
+    pub const fn encoding(&self) -> UssdEncoding {
+        self.encoding
+    }
+
+    /// Whether the string is empty
+    #[must_use]
+    pub const fn is_empty(&self) -> bool {
+        self.raw.is_empty()
+    }
+
+    /// Iterate over the decoded characters
+    #[must_use]
+    pub fn chars(&self) -> UssdChars<'at> {
+        UssdChars(match self.encoding {
+            UssdEncoding::Text(charset) => match Text::from_encoded(self.raw, charset) {
+                Ok(text) => UssdCharsRepr::Text(text.chars()),
+                // Validated when the string was created
+                Err(_) => UssdCharsRepr::Text(Text::new("").chars()),
+            },
+            UssdEncoding::PackedGsm7 => {
+                let octets = HexBytes::from_hex(self.raw).unwrap_or(HexBytes::new(&[]));
+                let mut count = octets.len() * 8 / 7;
+                // Seven spare bits at the end are filled with a carriage return
+                if count > 0
+                    && (octets.len() * 8).is_multiple_of(7)
+                    && gsm7::unpacked(octets.iter(), 0, count).last() == Some(b'\r')
+                {
+                    count -= 1;
+                }
+
+                UssdCharsRepr::Packed(gsm7::Chars::new(gsm7::unpacked(octets.iter(), 0, count)))
+            }
+        })
+    }
+
+    /// Decode the string into `buffer` as UTF-8
+    ///
+    /// # Errors
+    ///
+    /// Returns [`HayesError::InsufficientBuffer`] if `buffer` is too small.
+    pub fn decode_into<'b>(&self, buffer: &'b mut [u8]) -> Result<&'b str, HayesError> {
+        let required = self.chars().map(char::len_utf8).sum();
+        if buffer.len() < required {
+            return Err(HayesError::InsufficientBuffer {
+                required,
+                available: buffer.len(),
+            });
+        }
+
+        let mut pos = 0;
+        for c in self.chars() {
+            pos += c.encode_utf8(&mut buffer[pos..]).len();
+        }
+
+        core::str::from_utf8(&buffer[..pos]).map_err(|_| HayesError::InvalidUtf8)
+    }
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+enum UssdCharsRepr<'at> {
+    Text(TextChars<'at>),
+    Packed(gsm7::Chars<gsm7::Unpacked<HexBytesIter<'at>>>),
+}
+
+/// Iterator over the characters of a [`UssdString`]
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct UssdChars<'at>(UssdCharsRepr<'at>);
+
+impl Iterator for UssdChars<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match &mut self.0 {
+            UssdCharsRepr::Text(chars) => chars.next(),
+            UssdCharsRepr::Packed(chars) => chars.next(),
+        }
+    }
+}
+
+/// Whether `+CUSD` results are shown, or the session is cancelled, the `<n>`
+/// of `+CUSD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UssdPresentation {
+    /// Don't show `+CUSD` results
+    Disable = 0,
+    /// Show `+CUSD` results
+    Enable = 1,
+    /// Cancel the session
+    Cancel = 2,
+}
+
+impl AtWritable for UssdPresentation {
+    fn write(&self, output: &mut [u8]) -> Result<usize, HayesError> {
+        (*self as u8).write(output)
+    }
+}
+
+/// `AT+CUSD=[<n>[,<str>[,<dcs>]]]`, sending a USSD request or reply
+///
+/// The request is encoded according to `dcs`: GSM 7-bit strings in the
+/// context's character set, UCS2 strings as UCS2 hex and 8-bit data as hex.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cusd<'a> {
+    /// Whether results are shown, or the session is cancelled
+    pub presentation: Option<UssdPresentation>,
+    /// The request, such as `*100#`, or the reply to a menu
+    pub request: Option<Text<'a>>,
+    /// How the request is coded
+    pub dcs: Option<CbsDcs>,
+}
+
+impl<'a> Cusd<'a> {
+    /// Send a request in the GSM 7-bit default alphabet, showing the result
+    #[must_use]
+    pub const fn request(request: &'a str) -> Self {
+        Cusd {
+            presentation: Some(UssdPresentation::Enable),
+            request: Some(Text::new(request)),
+            dcs: Some(CbsDcs::GSM7),
+        }
+    }
+
+    /// Cancel the current session
+    #[must_use]
+    pub const fn cancel() -> Self {
+        Cusd {
+            presentation: Some(UssdPresentation::Cancel),
+            request: None,
+            dcs: None,
+        }
+    }
+}
+
+impl AtCommand for Cusd<'_> {
+    type Response<'at> = CusdResponse<'at>;
+
+    fn write(&self, buffer: &mut [u8]) -> Result<usize, HayesError> {
+        self.write_with(buffer, &AtContext::default())
+    }
+
+    fn write_with(&self, buffer: &mut [u8], context: &AtContext) -> Result<usize, HayesError> {
+        let context = match self.dcs.map_or(Alphabet::Gsm7, CbsDcs::alphabet) {
+            Alphabet::Gsm7 => *context,
+            Alphabet::EightBit => AtContext::with_charset(Charset::Hex),
+            Alphabet::Ucs2 => AtContext::with_charset(Charset::Ucs2),
+        };
+
+        let mut writer = CommandWriter::new(buffer, "+CUSD")?;
+        writer.param(&self.presentation)?;
+        writer.param_with(&self.request, &context)?;
+        writer.param(&self.dcs)?;
+        writer.finish()
+    }
+}
+
+/// Status of a USSD session, the `<m>` of `+CUSD`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UssdStatus {
+    /// No further action required, the session has ended
+    NoFurtherAction = 0,
+    /// The network expects a reply, such as a menu choice
+    FurtherActionRequired = 1,
+    /// The session was terminated by the network
+    Terminated = 2,
+    /// Another local client has responded
+    OtherClientResponded = 3,
+    /// The operation isn't supported
+    NotSupported = 4,
+    /// The network timed out
+    TimedOut = 5,
+}
+
+impl<'at> AtReadable<'at> for UssdStatus {
+    fn read(input: &'at [u8]) -> Result<(Self, usize), HayesError> {
+        let (value, consumed) = u8::read(input)?;
+        let status = match value {
+            0 => UssdStatus::NoFurtherAction,
+            1 => UssdStatus::FurtherActionRequired,
+            2 => UssdStatus::Terminated,
+            3 => UssdStatus::OtherClientResponded,
+            4 => UssdStatus::NotSupported,
+            5 => UssdStatus::TimedOut,
+            _ => return Err(HayesError::OutOfRange),
+        };
+
+        Ok((status, consumed))
+    }
+}
+
+/// `+CUSD: <m>[,<str>,<dcs>]`, the network's answer to a USSD request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CusdResult<'at> {
+    /// Status of the session
+    pub status: UssdStatus,
+    /// The network's message, if any
+    pub text: Option<UssdString<'at>>,
+}
+
+impl<'at> CusdResult<'at> {
+    /// Whether the session is still open and expects a reply
+    #[must_use]
+    pub fn is_interactive(&self) -> bool {
+        self.status == UssdStatus::FurtherActionRequired
+    }
+
+    #[allow(clippy::trivially_copy_pass_by_ref)]
+    fn read_fields(fields: &mut Fields<'at>, context: &AtContext) -> Result<Self, HayesError> {
+        let status = fields.next()?;
+        let text = if fields.is_empty() {
+            None
+        } else {
+            let raw = fields.next_quoted()?;
+            let dcs = fields.optional()?.unwrap_or(CbsDcs::GSM7);
+            Some(UssdString::new(raw, dcs, context)?)
+        };
+
+        Ok(CusdResult { status, text })
+    }
+}
+
+/// Reads the unsolicited result code, which isn't followed by `OK`
+impl<'at> AtResponse<'at> for CusdResult<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut fields = ResponseReader::new(buffer, context).prefixed("+CUSD")?;
+        CusdResult::read_fields(&mut fields, context)
+    }
+}
+
+/// Response to [`Cusd`]
+///
+/// The network usually answers after `OK`, with a separate [`CusdResult`], but
+/// some modems wait for the answer and show it before `OK`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CusdResponse<'at> {
+    /// The network's answer, if it came before `OK`
+    pub result: Option<CusdResult<'at>>,
+}
+
+impl<'at> AtResponse<'at> for CusdResponse<'at> {
+    fn read(buffer: &'at [u8]) -> Result<Self, HayesError> {
+        Self::read_with(buffer, &AtContext::default())
+    }
+
+    fn read_with(buffer: &'at [u8], context: &AtContext) -> Result<Self, HayesError> {
+        let mut reader = ResponseReader::new(buffer, context);
+        let result = match reader.optional("+CUSD")? {
+            Some(mut fields) => Some(CusdResult::read_fields(&mut fields, context)?),
+            None => None,
+        };
+        reader.finish()?;
+
+        Ok(CusdResponse { result })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CmeError;
+
+    #[test]
+    fn test_dcs_alphabet() {
+        assert_eq!(CbsDcs::GSM7.alphabet(), Alphabet::Gsm7);
+        assert_eq!(CbsDcs::EIGHT_BIT.alphabet(), Alphabet::EightBit);
+        assert_eq!(CbsDcs::UCS2.alphabet(), Alphabet::Ucs2);
+        assert_eq!(CbsDcs(0x11).alphabet(), Alphabet::Ucs2);
+        assert_eq!(CbsDcs(0x10).alphabet(), Alphabet::Gsm7);
+        assert_eq!(CbsDcs(0x99).alphabet(), Alphabet::Ucs2);
+        assert_eq!(CbsDcs(0xF4).alphabet(), Alphabet::EightBit);
+        assert_eq!(CbsDcs(0xA0).alphabet(), Alphabet::Gsm7);
+        assert!(CbsDcs(0x60).is_compressed());
+        assert!(!CbsDcs::GSM7.is_compressed());
+    }
+
+    #[test]
+    fn test_write_cusd() {
+        let mut buf = [0u8; 64];
+
+        let len = Cusd::request("*100#").write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CUSD=1,\"*100#\",15\r\n");
+
+        let len = Cusd::cancel().write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CUSD=2\r\n");
+
+        let reply = Cusd {
+            dcs: Some(CbsDcs::UCS2),
+            ..Cusd::request("1")
+        };
+        let len = reply.write(&mut buf).unwrap();
+        assert_eq!(&buf[..len], b"AT+CUSD=1,\"0031\",72\r\n");
+
+        let len = Cusd::request("*100#")
+            .write_with(&mut buf, &AtContext::with_charset(Charset::Ucs2))
+            .unwrap();
+        assert_eq!(&buf[..len], b"AT+CUSD=1,\"002A0031003000300023\",15\r\n");
+    }
+
+    #[test]
+    fn test_read_cusd_result() {
+        let mut buf = [0u8; 64];
+
+        let result = CusdResult::read(b"\r\n+CUSD: 0,\"Balance: 5.00\",15\r\n").unwrap();
+        assert_eq!(result.status, UssdStatus::NoFurtherAction);
+        assert!(!result.is_interactive());
+        let text = result.text.unwrap();
+        assert_eq!(text.dcs(), CbsDcs::GSM7);
+        assert_eq!(text.decode_into(&mut buf), Ok("Balance: 5.00"));
+
+        let result = CusdResult::read(b"\r\n+CUSD: 1,\"0031002E00200414\",72\r\n").unwrap();
+        assert!(result.is_interactive());
+        assert_eq!(result.text.unwrap().decode_into(&mut buf), Ok("1. Д"));
+
+        let result = CusdResult::read(b"\r\n+CUSD: 2\r\n").unwrap();
+        assert_eq!(result.status, UssdStatus::Terminated);
+        assert_eq!(result.text, None);
+
+        assert_eq!(
+            CusdResult::read(b"\r\n+CUSD: 0,\"0031\",72,\r\n").map(|r| r.status),
+            Ok(UssdStatus::NoFurtherAction)
+        );
+        assert_eq!(
+            CusdResult::read(b"\r\n+CUSD: 0,\"003\",72\r\n"),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_packed_gsm7() {
+        let mut buf = [0u8; 64];
+
+        let result = CusdResult::read(b"\r\n+CUSD: 0,\"E8329BFD4697D9EC37\",15\r\n").unwrap();
+        let text = result
+            .text
+            .unwrap()
+            .with_encoding(UssdEncoding::PackedGsm7)
+            .unwrap();
+        assert_eq!(text.decode_into(&mut buf), Ok("hellohello"));
+
+        // "ABCDEFG" fills seven octets, with a carriage return as padding
+        let mut packed = [0u8; 7];
+        gsm7::pack(b"ABCDEFG\r", 0, &mut packed).unwrap();
+        let mut hex = [0u8; 14];
+        HexBytes::new(&packed).write(&mut hex).unwrap();
+
+        let text = UssdString::new(&hex, CbsDcs::GSM7, &AtContext::default())
+            .and_then(|text| text.with_encoding(UssdEncoding::PackedGsm7))
+            .unwrap();
+        assert_eq!(text.decode_into(&mut buf), Ok("ABCDEFG"));
+
+        assert_eq!(
+            UssdString::new(b"Hi!", CbsDcs::GSM7, &AtContext::default())
+                .and_then(|text| text.with_encoding(UssdEncoding::PackedGsm7)),
+            Err(HayesError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_read_cusd_response() {
+        assert_eq!(
+            CusdResponse::read(b"\r\nOK\r\n"),
+            Ok(CusdResponse { result: None })
+        );
+
+        let response =
+            CusdResponse::read(b"AT+CUSD=1,\"*100#\",15\r\r\n+CUSD: 0,\"OK\",15\r\n\r\nOK\r\n")
+                .unwrap();
+        assert_eq!(
+            response.result.map(|result| result.status),
+            Some(UssdStatus::NoFurtherAction)
+        );
+
+        assert_eq!(
+            CusdResponse::read(b"\r\n+CME ERROR: 30\r\n"),
+            Err(HayesError::CmeError(CmeError::NoNetworkService))
+        );
+    }
+
+    #[test]
+    fn test_read_cusd_response_non_ascii() {
+        let mut buf = [0u8; 32];
+        let utf8 = AtContext::with_charset(Charset::Utf8);
+
+        let buffer = "\r\n+CUSD: 0,\"Solde: 5€\",15\r\n\r\nOK\r\n".as_bytes();
+        let result = CusdResponse::read_with(buffer, &utf8)
+            .unwrap()
+            .result
+            .unwrap();
+        assert_eq!(result.text.unwrap().decode_into(&mut buf), Ok("Solde: 5€"));
+    }
+}
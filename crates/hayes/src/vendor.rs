@@ -0,0 +1,142 @@
+use crate::{CmeError, CmsError, HayesError};
+
+/// An error from a manufacturer-specific error family, created by a
+/// [`VendorErrors`] implementation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct VendorError {
+    /// Name of the error family, such as `"ESP-AT"`
+    pub family: &'static str,
+    /// Code of the error within the family
+    pub code: u32,
+}
+
+/// Recognizes the error lines and codes a vendor adds to the standard ones
+///
+/// Responses are read the same way for every modem, so a vendor's errors
+/// show up as whatever the standard reading makes of them: an unknown
+/// [`CmeError::Other`] code, a bare `ERROR`, or an unexpected line. Pass such
+/// an error to [`VendorErrors::classify`] along with the buffer it was read
+/// from to turn it into the vendor's error.
+///
+/// ```rust,ignore
+/// let response = Csq::read(buffer).map_err(|error| Esp::classify(buffer, error))?;
+/// ```
+///
+/// All methods recognize nothing by default.
+pub trait VendorErrors {
+    /// Classify a line of a response, such as ESP-AT's
+    /// `ERR CODE:0x01090000`, or return `None` if it isn't an error line
+    #[must_use]
+    fn classify_line(line: &[u8]) -> Option<HayesError> {
+        let _ = line;
+        None
+    }
+
+    /// Classify a `+CME ERROR` code missing from the 27.007 table, such as
+    /// u-blox codes from 1000
+    #[must_use]
+    fn classify_cme(code: u16) -> Option<HayesError> {
+        let _ = code;
+        None
+    }
+
+    /// Classify a `+CMS ERROR` code missing from the 27.005 table
+    #[must_use]
+    fn classify_cms(code: u16) -> Option<HayesError> {
+        let _ = code;
+        None
+    }
+
+    /// Refine an `error` from reading `buffer` into the vendor's error
+    ///
+    /// Unknown `+CME ERROR` and `+CMS ERROR` codes are classified by code.
+    /// Other errors about the response are classified by the first line of
+    /// `buffer` the vendor recognizes. Errors the vendor doesn't recognize
+    /// are returned unchanged.
+    #[must_use]
+    fn classify(buffer: &[u8], error: HayesError) -> HayesError {
+        let classified = match error {
+            HayesError::CmeError(CmeError::Other(code)) => Self::classify_cme(code),
+            HayesError::CmsError(CmsError::Other(code)) => Self::classify_cms(code),
+            HayesError::CmeError(_)
+            | HayesError::CmsError(_)
+            | HayesError::InsufficientBuffer { .. }
+            | HayesError::Vendor(_) => None,
+            _ => buffer
+                .split(|&b| b == b'\r' || b == b'\n')
+                .filter(|line| !line.is_empty())
+                .find_map(Self::classify_line),
+        };
+
+        classified.unwrap_or(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AtResponse, sms::CmgsResponse};
+
+    /// ESP-AT style error lines and u-blox style error codes
+    struct Vendor;
+
+    impl VendorErrors for Vendor {
+        fn classify_line(line: &[u8]) -> Option<HayesError> {
+            let digits = line.strip_prefix(b"ERR CODE:0x")?;
+            let code = u32::from_str_radix(core::str::from_utf8(digits).ok()?, 16).ok()?;
+
+            Some(HayesError::Vendor(VendorError {
+                family: "ESP-AT",
+                code,
+            }))
+        }
+
+        fn classify_cme(code: u16) -> Option<HayesError> {
+            (code >= 1000).then_some(HayesError::Vendor(VendorError {
+                family: "u-blox",
+                code: code.into(),
+            }))
+        }
+    }
+
+    fn read(buffer: &[u8]) -> Result<CmgsResponse, HayesError> {
+        CmgsResponse::read(buffer).map_err(|error| Vendor::classify(buffer, error))
+    }
+
+    #[test]
+    fn test_classify_line() {
+        let esp = HayesError::Vendor(VendorError {
+            family: "ESP-AT",
+            code: 0x0109_0000,
+        });
+        assert_eq!(read(b"\r\nERR CODE:0x01090000\r\n\r\nERROR\r\n"), Err(esp));
+
+        // The vendor's line can also break reading before the result code
+        assert_eq!(read(b"\r\nERR CODE:0x01090000\r\n"), Err(esp));
+        assert_eq!(read(b"\r\nERROR\r\n"), Err(HayesError::ModemError));
+    }
+
+    #[test]
+    fn test_classify_codes() {
+        assert_eq!(
+            read(b"\r\n+CME ERROR: 1050\r\n"),
+            Err(HayesError::Vendor(VendorError {
+                family: "u-blox",
+                code: 1050,
+            }))
+        );
+        assert_eq!(
+            read(b"\r\n+CME ERROR: 700\r\n"),
+            Err(HayesError::CmeError(CmeError::Other(700)))
+        );
+        assert_eq!(
+            read(b"\r\n+CMS ERROR: 1050\r\n"),
+            Err(HayesError::CmsError(CmsError::Other(1050)))
+        );
+        assert_eq!(
+            read(b"\r\n+CME ERROR: 10\r\nERR CODE:0x01090000\r\n"),
+            Err(HayesError::CmeError(CmeError::SimNotInserted))
+        );
+    }
+}